@@ -126,12 +126,12 @@ test.describe("ReMedia app", () => {
     await page.evaluate((url) => window.__E2E_addUrl?.(url), url);
 
     // Inject media info event to update title and thumbnail
-    await emitTauriEvent(page, "update-media-info", [
-      0,
-      url,
-      "Nice Title",
-      "https://img/thumb.jpg",
-    ]);
+    await emitTauriEvent(page, "update-media-info", {
+      mediaIdx: 0,
+      mediaSourceUrl: url,
+      title: "Nice Title",
+      thumbnail: "https://img/thumb.jpg",
+    });
     await expect(page.getByText("Nice Title")).toBeVisible();
 
     // Inject progress
@@ -201,7 +201,12 @@ test.describe("ReMedia app", () => {
     await page.evaluate((url) => window.__E2E_addUrl?.(url), url);
 
     // Emit media info with empty thumbnail
-    await emitTauriEvent(page, "update-media-info", [0, url, "Test Video", ""]);
+    await emitTauriEvent(page, "update-media-info", {
+      mediaIdx: 0,
+      mediaSourceUrl: url,
+      title: "Test Video",
+      thumbnail: "",
+    });
 
     // Should show placeholder image
     const thumbnail = page.locator('img[alt="Media thumbnail"]').first();
@@ -502,8 +507,8 @@ test.describe("ReMedia app", () => {
     await page.getByRole("button", { name: "Cancel" }).click();
 
     // Simulate backend cancellation events for both rows
-    await emitTauriEvent(page, "download-cancelled", 0);
-    await emitTauriEvent(page, "download-cancelled", 1);
+    await emitTauriEvent(page, "download-cancelled", [0, "all"]);
+    await emitTauriEvent(page, "download-cancelled", [1, "all"]);
 
     // Both items should show "Cancelled" status
     await expect(page.getByRole("cell", { name: "Cancelled" }).first()).toBeVisible({
@@ -528,7 +533,7 @@ test.describe("ReMedia app", () => {
     await page.getByRole("menuitem", { name: "Cancel All" }).click();
 
     // Simulate backend cancellation event
-    await emitTauriEvent(page, "download-cancelled", 0);
+    await emitTauriEvent(page, "download-cancelled", [0, "all"]);
 
     // Status should update to "Cancelled"
     await expect(page.getByRole("cell", { name: "Cancelled" })).toBeVisible({
@@ -544,7 +549,7 @@ test.describe("ReMedia app", () => {
     await page.evaluate((url) => window.__E2E_addUrl?.(url), url);
 
     // Emit download-cancelled event
-    await emitTauriEvent(page, "download-cancelled", 0);
+    await emitTauriEvent(page, "download-cancelled", [0, "user"]);
 
     // Status should update to "Cancelled"
     await expect(page.getByRole("cell", { name: "Cancelled" })).toBeVisible();
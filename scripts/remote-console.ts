@@ -11,6 +11,7 @@ const WAIT_TIMEOUT_DEFAULT = Number(process.env.REMOTE_CONSOLE_WAIT_TIMEOUT_MS ?
 
 interface RemoteHelloPayload {
   pid?: number;
+  connId?: string;
   enableRemoteHarnessEnv?: string;
   [key: string]: unknown;
 }
@@ -4,7 +4,19 @@ use std::sync::LazyLock;
 /// Manages concurrent downloads with a queue system.
 /// Limits the number of simultaneous downloads and queues additional requests.
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::sync::{Arc, Mutex};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::error::DownloaderError;
+
+/// Consecutive failures from one host before it is put on cooldown
+pub const HOST_FAILURE_THRESHOLD: u32 = 3;
+
+/// How long queued items for a failing host are held back
+pub const HOST_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Most downloads that may wait in the queue (active ones not counted) unless raised with `set_max_queued`
+pub const DEFAULT_MAX_QUEUED: usize = 10_000;
 
 /// Download status for queue management
 #[derive(Debug, Clone, PartialEq)]
@@ -16,6 +28,36 @@ pub enum DownloadStatus {
     Cancelled,
 }
 
+/// Result of a successful enqueue call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnqueueOutcome {
+    /// Added to the queue
+    Queued,
+    /// This media index is already queued or downloading (idempotent no-op)
+    AlreadyPresent,
+    /// Another index with the same URL is queued or downloading (only with URL dedupe enabled)
+    DuplicateUrl,
+}
+
+/// Payload of `EVT_QUEUE_FULL`: the rejected download with the queue's size and limit,
+/// so the UI can offer to raise the limit instead of showing a generic error
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueFull {
+    pub media_idx: i32,
+    pub queued: usize,
+    pub max_queued: usize,
+}
+
+/// Outcome counts for the current batch, reported in `EVT_BATCH_SUMMARY` when the queue drains
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct BatchSummary {
+    pub completed: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+    pub skipped: usize,
+}
+
 /// Download item in the queue
 #[derive(Debug, Clone)]
 pub struct QueuedDownload {
@@ -25,6 +67,105 @@ pub struct QueuedDownload {
     pub settings: String,          // JSON serialized settings
     pub subfolder: Option<String>, // Playlist name or channel name for folder organization
     pub status: DownloadStatus,
+    pub title: Option<String>, // Display title, when known, for sorting
+    pub priority: i32,         // Higher sorts first with `QueueSortKey::Priority`
+}
+
+/// Orderings `DownloadQueue::sort_queued_by` supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueSortKey {
+    Title,
+    Url,
+    Priority,
+}
+
+impl QueueSortKey {
+    /// Parse a frontend key: "title", "url" or "priority"
+    pub fn parse(key: &str) -> Option<Self> {
+        match key {
+            "title" => Some(Self::Title),
+            "url" => Some(Self::Url),
+            "priority" => Some(Self::Priority),
+            _ => None,
+        }
+    }
+}
+
+/// Extract the lowercase host of a URL, used to group downloads for backoff
+pub fn url_host(url: &str) -> Option<String> {
+    let parsed = reqwest::Url::parse(url).ok()?;
+    parsed.host_str().map(|h| h.to_lowercase())
+}
+
+/// Even share of a fragment budget among `active` downloads, at least one each
+pub fn fragment_allowance(budget: usize, active: usize) -> usize {
+    (budget / active.max(1)).max(1)
+}
+
+/// Whether the collection `subfolder` already holds `limit` active downloads.
+/// Downloads without a subfolder (ad-hoc ones) are never limited.
+fn collection_at_limit(active: &HashMap<i32, QueuedDownload>, subfolder: Option<&str>, limit: Option<usize>) -> bool {
+    let (Some(subfolder), Some(limit)) = (subfolder, limit) else {
+        return false;
+    };
+    active.values().filter(|d| d.subfolder.as_deref() == Some(subfolder)).count() >= limit
+}
+
+/// Per-host failure tracking: after `threshold` consecutive failures a host
+/// enters a cooldown, and leaves it once `duration` has elapsed.
+#[derive(Debug)]
+pub struct HostCooldowns {
+    threshold: u32,
+    duration: Duration,
+    failures: HashMap<String, u32>,
+    cooling_until: HashMap<String, Instant>,
+}
+
+impl HostCooldowns {
+    pub fn new(threshold: u32, duration: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            duration,
+            failures: HashMap::new(),
+            cooling_until: HashMap::new(),
+        }
+    }
+
+    /// Record a failure; returns true if this failure put the host on cooldown
+    pub fn record_failure(&mut self, host: &str, now: Instant) -> bool {
+        let count = self.failures.entry(host.to_string()).or_insert(0);
+        *count += 1;
+
+        if *count >= self.threshold {
+            self.failures.remove(host);
+            self.cooling_until.insert(host.to_string(), now + self.duration);
+            return true;
+        }
+
+        false
+    }
+
+    /// Record a success, resetting the host's consecutive failure count
+    pub fn record_success(&mut self, host: &str) {
+        self.failures.remove(host);
+    }
+
+    /// Whether the host is cooling down at `now` (expired cooldowns are cleared)
+    pub fn is_cooling(&mut self, host: &str, now: Instant) -> bool {
+        match self.cooling_until.get(host) {
+            Some(until) if now < *until => true,
+            Some(_) => {
+                self.cooling_until.remove(host);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Cooldown length applied to failing hosts
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
 }
 
 /// Download Queue Manager
@@ -35,11 +176,57 @@ pub struct DownloadQueue {
     /// Queue of pending downloads
     queue: VecDeque<QueuedDownload>,
 
+    /// Most downloads `queue` may hold; enqueueing beyond it fails with a queue-full error
+    max_queued: usize,
+
     /// Set of queued media indices for O(1) duplicate checking
     queued_set: HashSet<i32>,
 
     /// Currently active downloads
     active: HashMap<i32, QueuedDownload>,
+
+    /// Start sequence number and start time of each active download, for oldest-first
+    /// lookup and age-based cancellation
+    active_started: HashMap<i32, (u64, Instant)>,
+
+    /// Next start sequence number to hand out
+    next_start_seq: u64,
+
+    /// Active downloads picked by `pause_overflow` whose processes have not exited yet;
+    /// `requeue_paused` moves them back to the queue once they have
+    pausing: HashSet<i32>,
+
+    /// URLs downloaded successfully this session, for dedupe on enqueue
+    completed_urls: HashSet<String>,
+
+    /// Backoff state for hosts that keep failing
+    host_cooldowns: HostCooldowns,
+
+    /// URLs of queued and active downloads, with how many entries use each
+    pending_urls: HashMap<String, usize>,
+
+    /// Reject a URL already queued or active under a different media index
+    dedupe_urls: bool,
+
+    /// Most downloads one collection (subfolder) may run at once; None = only `max_concurrent` applies
+    collection_limit: Option<usize>,
+
+    /// Fragment connections shared by all active downloads; None = each uses what it asks for
+    fragment_budget: Option<usize>,
+
+    /// After `max_concurrent` is lowered, let surplus active downloads finish (true) instead of
+    /// pausing the newest-started ones with `pause_overflow` (false)
+    drain_overflow_on_decrease: bool,
+
+    /// When paused, queued items stay queued and nothing new is started
+    paused: bool,
+
+    /// On a metered connection nothing new is started; kept apart from `paused` so
+    /// leaving the metered network doesn't undo a pause the user asked for
+    metered: bool,
+
+    /// Outcomes accumulated since the queue last drained
+    batch: BatchSummary,
 }
 
 impl DownloadQueue {
@@ -48,69 +235,247 @@ impl DownloadQueue {
         Self {
             max_concurrent: max_concurrent.max(1), // At least 1
             queue: VecDeque::new(),
+            max_queued: DEFAULT_MAX_QUEUED,
             queued_set: HashSet::new(),
             active: HashMap::new(),
+            active_started: HashMap::new(),
+            next_start_seq: 0,
+            pausing: HashSet::new(),
+            completed_urls: HashSet::new(),
+            host_cooldowns: HostCooldowns::new(HOST_FAILURE_THRESHOLD, HOST_COOLDOWN),
+            pending_urls: HashMap::new(),
+            dedupe_urls: false,
+            collection_limit: None,
+            fragment_budget: None,
+            drain_overflow_on_decrease: true,
+            paused: false,
+            metered: false,
+            batch: BatchSummary::default(),
         }
     }
 
-    /// Add a download to the queue (O(1) duplicate checking)
-    pub fn enqueue(&mut self, download: QueuedDownload) -> Result<(), String> {
+    /// Limit how many downloads may wait in the queue (0 is treated as 1).
+    /// Lowering it below the current size keeps queued items; only new enqueues are refused.
+    pub fn set_max_queued(&mut self, max: usize) {
+        self.max_queued = max.max(1);
+    }
+
+    /// Queue size and limit reported when `media_idx` is refused because the queue is full
+    pub fn queue_full(&self, media_idx: i32) -> QueueFull {
+        QueueFull { media_idx, queued: self.queue.len(), max_queued: self.max_queued }
+    }
+
+    /// Enable or disable URL-level dedupe on enqueue (off by default)
+    pub fn set_dedupe_urls(&mut self, enabled: bool) {
+        self.dedupe_urls = enabled;
+    }
+
+    /// Cap how many slots one collection (downloads sharing a subfolder) may hold, so a large
+    /// playlist leaves room for ad-hoc downloads. None removes the cap; 0 is treated as 1.
+    pub fn set_collection_limit(&mut self, limit: Option<usize>) {
+        self.collection_limit = limit.map(|l| l.max(1));
+    }
+
+    /// Limit the fragment connections all downloads may open together (None removes the limit)
+    pub fn set_fragment_budget(&mut self, budget: Option<usize>) {
+        self.fragment_budget = budget;
+    }
+
+    /// Fragments a download starting now may use: its even share of the budget among active downloads
+    pub fn fragment_allowance(&self) -> Option<usize> {
+        self.fragment_budget.map(|budget| fragment_allowance(budget, self.active.len()))
+    }
+
+    /// Pause or resume starting queued downloads (active ones keep running)
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Whether the queue is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Hold or release new starts for a metered connection (active ones keep running)
+    pub fn set_metered(&mut self, metered: bool) {
+        self.metered = metered;
+    }
+
+    /// Whether the queue is held for a metered connection
+    pub fn is_metered(&self) -> bool {
+        self.metered
+    }
+
+    fn track_url(&mut self, url: &str) {
+        *self.pending_urls.entry(url.to_string()).or_insert(0) += 1;
+    }
+
+    fn untrack_url(&mut self, url: &str) {
+        if let Some(count) = self.pending_urls.get_mut(url) {
+            *count -= 1;
+            if *count == 0 {
+                self.pending_urls.remove(url);
+            }
+        }
+    }
+
+    /// Add a download to the queue (O(1) duplicate checking).
+    /// Fails with a queue-full error when `max_queued` downloads are already waiting.
+    pub fn enqueue(&mut self, download: QueuedDownload) -> Result<EnqueueOutcome, DownloaderError> {
         let idx = download.media_idx;
 
         // O(1) check if already queued or active
         if self.queued_set.contains(&idx) || self.active.contains_key(&idx) {
             // Idempotent: already queued or downloading
-            return Ok(());
+            return Ok(EnqueueOutcome::AlreadyPresent);
+        }
+
+        if self.dedupe_urls && self.pending_urls.contains_key(&download.url) {
+            return Ok(EnqueueOutcome::DuplicateUrl);
+        }
+
+        if self.queue.len() >= self.max_queued {
+            return Err(DownloaderError::queue_full(format!("{} downloads already queued", self.queue.len())));
         }
 
         self.queued_set.insert(idx);
+        self.track_url(&download.url);
         self.queue.push_back(download);
-        Ok(())
+        Ok(EnqueueOutcome::Queued)
     }
 
-    /// Get next download to start (if slots available)
+    /// Get next download to start (if slots available), with no task still running for any index
+    #[allow(dead_code)]
     pub fn next_to_start(&mut self) -> Option<QueuedDownload> {
-        if self.active.len() >= self.max_concurrent {
+        self.next_to_start_at(Instant::now(), &HashSet::new())
+    }
+
+    /// Get next download to start (if slots available).
+    /// Items whose host is on cooldown, whose collection is at its limit, or whose index is in
+    /// `running` (a previous task for it has not finished) are skipped and stay queued.
+    pub fn next_to_start_except(&mut self, running: &HashSet<i32>) -> Option<QueuedDownload> {
+        self.next_to_start_at(Instant::now(), running)
+    }
+
+    fn next_to_start_at(&mut self, now: Instant, running: &HashSet<i32>) -> Option<QueuedDownload> {
+        if self.paused || self.metered || self.active.len() >= self.max_concurrent {
             return None;
         }
 
-        if let Some(mut download) = self.queue.pop_front() {
-            self.queued_set.remove(&download.media_idx);
-            download.status = DownloadStatus::Downloading;
-            self.active.insert(download.media_idx, download.clone());
-            Some(download)
-        } else {
-            None
-        }
+        let cooldowns = &mut self.host_cooldowns;
+        let active = &self.active;
+        let collection_limit = self.collection_limit;
+        let pos = self.queue.iter().position(|d| {
+            !running.contains(&d.media_idx)
+                && url_host(&d.url).is_none_or(|host| !cooldowns.is_cooling(&host, now))
+                && !collection_at_limit(active, d.subfolder.as_deref(), collection_limit)
+        })?;
+
+        let mut download = self.queue.remove(pos)?;
+        self.queued_set.remove(&download.media_idx);
+        download.status = DownloadStatus::Downloading;
+        self.active.insert(download.media_idx, download.clone());
+        self.active_started.insert(download.media_idx, (self.next_start_seq, now));
+        self.next_start_seq += 1;
+        Some(download)
+    }
+
+    fn remove_active(&mut self, media_idx: i32) -> Option<QueuedDownload> {
+        self.pausing.remove(&media_idx);
+        self.active_started.remove(&media_idx);
+        self.active.remove(&media_idx)
+    }
+
+    /// Media index of the active download that started first
+    pub fn oldest_active(&self) -> Option<i32> {
+        self.active_started.iter().min_by_key(|(_, (seq, _))| *seq).map(|(idx, _)| *idx)
+    }
+
+    /// Media indices of active downloads running for more than `age`, oldest first
+    pub fn active_older_than(&self, age: Duration) -> Vec<i32> {
+        self.active_older_than_at(age, Instant::now())
+    }
+
+    fn active_older_than_at(&self, age: Duration, now: Instant) -> Vec<i32> {
+        let mut stale: Vec<(u64, i32)> = self
+            .active_started
+            .iter()
+            .filter(|(_, (_, started))| now.saturating_duration_since(*started) > age)
+            .map(|(idx, (seq, _))| (*seq, *idx))
+            .collect();
+        stale.sort_unstable();
+        stale.into_iter().map(|(_, idx)| idx).collect()
     }
 
     /// Mark download as completed
     pub fn complete(&mut self, media_idx: i32) {
-        if let Some(mut download) = self.active.remove(&media_idx) {
+        if let Some(mut download) = self.remove_active(media_idx) {
             download.status = DownloadStatus::Completed;
+            self.batch.completed += 1;
+            self.untrack_url(&download.url);
+            if let Some(host) = url_host(&download.url) {
+                self.host_cooldowns.record_success(&host);
+            }
+            self.completed_urls.insert(download.url);
         }
     }
 
-    /// Mark download as failed
-    pub fn fail(&mut self, media_idx: i32) {
-        if let Some(mut download) = self.active.remove(&media_idx) {
-            download.status = DownloadStatus::Failed;
-        }
+    /// Mark download as failed.
+    /// Returns the host if this failure put it on cooldown.
+    pub fn fail(&mut self, media_idx: i32) -> Option<String> {
+        self.fail_at(media_idx, Instant::now())
+    }
+
+    fn fail_at(&mut self, media_idx: i32, now: Instant) -> Option<String> {
+        let mut download = self.remove_active(media_idx)?;
+        download.status = DownloadStatus::Failed;
+        self.batch.failed += 1;
+        self.untrack_url(&download.url);
+
+        let host = url_host(&download.url)?;
+        self.host_cooldowns.record_failure(&host, now).then_some(host)
+    }
+
+    /// Cooldown length applied to failing hosts
+    pub fn host_cooldown(&self) -> Duration {
+        self.host_cooldowns.duration()
+    }
+
+    /// URLs waiting in the queue, in queue order
+    pub fn queued_urls(&self) -> Vec<String> {
+        self.queue.iter().map(|d| d.url.clone()).collect()
+    }
+
+    /// URLs currently downloading, ordered by media index
+    pub fn active_urls(&self) -> Vec<String> {
+        let mut active: Vec<&QueuedDownload> = self.active.values().collect();
+        active.sort_by_key(|d| d.media_idx);
+        active.into_iter().map(|d| d.url.clone()).collect()
+    }
+
+    /// Check if a URL was already downloaded successfully this session
+    pub fn has_completed(&self, url: &str) -> bool {
+        self.completed_urls.contains(url)
     }
 
     /// Cancel a specific download
     pub fn cancel(&mut self, media_idx: i32) -> bool {
         // Remove from queued_set and queue if queued
         if self.queued_set.remove(&media_idx) {
-            if let Some(pos) = self.queue.iter().position(|d| d.media_idx == media_idx) {
-                self.queue.remove(pos);
+            if let Some(pos) = self.queue.iter().position(|d| d.media_idx == media_idx)
+                && let Some(download) = self.queue.remove(pos)
+            {
+                self.untrack_url(&download.url);
             }
+            self.batch.cancelled += 1;
             return true;
         }
 
         // Remove from active if downloading
-        if let Some(mut download) = self.active.remove(&media_idx) {
+        if let Some(mut download) = self.remove_active(media_idx) {
             download.status = DownloadStatus::Cancelled;
+            self.untrack_url(&download.url);
+            self.batch.cancelled += 1;
             return true;
         }
 
@@ -131,10 +496,67 @@ impl DownloadQueue {
         for (idx, _) in self.active.drain() {
             cancelled.push(idx);
         }
+        self.active_started.clear();
+        self.pausing.clear();
+        self.pending_urls.clear();
+        self.batch.cancelled += cancelled.len();
 
         cancelled
     }
 
+    /// Change the subfolder of a download that is still waiting in the queue.
+    /// Fails if the download already started or isn't queued.
+    pub fn update_subfolder(&mut self, media_idx: i32, subfolder: Option<String>) -> Result<(), String> {
+        if self.active.contains_key(&media_idx) {
+            return Err(format!("Download {} is already in progress", media_idx));
+        }
+
+        let download = self
+            .queue
+            .iter_mut()
+            .find(|d| d.media_idx == media_idx)
+            .ok_or_else(|| format!("Download {} is not queued", media_idx))?;
+        download.subfolder = subfolder;
+        Ok(())
+    }
+
+    /// Reorder queued downloads; active ones are untouched. The sort is stable, so items
+    /// with equal keys keep their relative order. Titles compare case-insensitively with
+    /// untitled items last; priority sorts highest first.
+    pub fn sort_queued_by(&mut self, key: QueueSortKey) {
+        let queued = self.queue.make_contiguous();
+        match key {
+            QueueSortKey::Title => queued.sort_by_cached_key(|d| {
+                let title = d.title.as_deref().map(str::to_lowercase);
+                (title.is_none(), title)
+            }),
+            QueueSortKey::Url => queued.sort_by(|a, b| a.url.cmp(&b.url)),
+            QueueSortKey::Priority => queued.sort_by_key(|d| std::cmp::Reverse(d.priority)),
+        }
+    }
+
+    /// Count a download that was skipped instead of queued
+    pub fn record_skipped(&mut self) {
+        self.batch.skipped += 1;
+    }
+
+    /// Drop an active download yt-dlp declined to fetch (e.g. over `max_file_size`), counting it as skipped
+    pub fn skip(&mut self, media_idx: i32) {
+        if let Some(download) = self.remove_active(media_idx) {
+            self.batch.skipped += 1;
+            self.untrack_url(&download.url);
+        }
+    }
+
+    /// Once nothing is queued or active, return the accumulated batch counts and reset them.
+    /// Returns None while work remains or if nothing happened since the last drain.
+    pub fn take_batch_summary(&mut self) -> Option<BatchSummary> {
+        if !self.active.is_empty() || !self.queue.is_empty() || self.batch == BatchSummary::default() {
+            return None;
+        }
+        Some(std::mem::take(&mut self.batch))
+    }
+
     /// Get current queue size
     #[allow(dead_code)]
     pub fn queue_size(&self) -> usize {
@@ -142,11 +564,15 @@ impl DownloadQueue {
     }
 
     /// Get number of active downloads
-    #[allow(dead_code)]
     pub fn active_count(&self) -> usize {
         self.active.len()
     }
 
+    /// Slots free for new downloads; 0 while more are active than a lowered limit allows
+    pub fn free_slots(&self) -> usize {
+        self.max_concurrent.saturating_sub(self.active.len())
+    }
+
     /// Check if a download is active
     #[allow(dead_code)]
     pub fn is_active(&self, media_idx: i32) -> bool {
@@ -158,6 +584,55 @@ impl DownloadQueue {
         self.max_concurrent = max.max(1);
     }
 
+    /// Choose how a lowered `max_concurrent` treats surplus active downloads (drain by default)
+    pub fn set_drain_overflow_on_decrease(&mut self, drain: bool) {
+        self.drain_overflow_on_decrease = drain;
+    }
+
+    /// In strict mode, mark the newest-started active downloads above `max_concurrent` as pausing
+    /// and return their indices (newest first) so their processes can be stopped. They stay active,
+    /// holding their slots, until `requeue_paused` is called after the process exits.
+    /// In drain mode nothing is marked: surplus downloads finish, and nothing new starts until
+    /// active falls below the limit.
+    pub fn pause_overflow(&mut self) -> Vec<i32> {
+        let mut paused = Vec::new();
+        if self.drain_overflow_on_decrease {
+            return paused;
+        }
+
+        while self.active.len().saturating_sub(self.pausing.len()) > self.max_concurrent {
+            let Some(newest) = self
+                .active_started
+                .iter()
+                .filter(|(idx, _)| !self.pausing.contains(idx))
+                .max_by_key(|(_, (seq, _))| *seq)
+                .map(|(idx, _)| *idx)
+            else {
+                break;
+            };
+            self.pausing.insert(newest);
+            paused.push(newest);
+        }
+
+        paused
+    }
+
+    /// Move a download marked by `pause_overflow` from active back to the front of the queue once
+    /// its process has exited. Returns false if it was not pausing (e.g. it finished first).
+    pub fn requeue_paused(&mut self, media_idx: i32) -> bool {
+        if !self.pausing.contains(&media_idx) {
+            return false;
+        }
+        let Some(mut download) = self.remove_active(media_idx) else {
+            return false;
+        };
+        // The URL stays tracked: the item moves from active to queued
+        download.status = DownloadStatus::Queued;
+        self.queued_set.insert(media_idx);
+        self.queue.push_front(download);
+        true
+    }
+
     /// Get queue status summary
     pub fn status(&self) -> QueueStatus {
         QueueStatus {
@@ -176,12 +651,18 @@ pub struct QueueStatus {
     pub max_concurrent: usize,
 }
 
-/// Global download queue instance
-static DOWNLOAD_QUEUE: LazyLock<Arc<Mutex<DownloadQueue>>> = LazyLock::new(|| Arc::new(Mutex::new(DownloadQueue::new(3))));
-
-/// Get global download queue
-pub fn get_queue() -> Arc<Mutex<DownloadQueue>> {
-    DOWNLOAD_QUEUE.clone()
+/// Global download queue instance. Only reachable through `with_queue`, so every access
+/// recovers from poisoning.
+static DOWNLOAD_QUEUE: LazyLock<Mutex<DownloadQueue>> = LazyLock::new(|| Mutex::new(DownloadQueue::new(3)));
+
+/// Lock a queue, recovering from poisoning left by a thread that panicked while holding it.
+/// The poison flag is cleared so the recovery is only reported once.
+fn lock_queue(queue: &Mutex<DownloadQueue>) -> MutexGuard<'_, DownloadQueue> {
+    queue.lock().unwrap_or_else(|poisoned| {
+        eprintln!("Warning: Queue lock was poisoned, recovering");
+        queue.clear_poison();
+        poisoned.into_inner()
+    })
 }
 
 /// Execute a closure with the download queue, recovering from poisoned locks.
@@ -193,30 +674,31 @@ pub fn with_queue<F, R>(f: F) -> R
 where
     F: FnOnce(&mut DownloadQueue) -> R,
 {
-    let queue = get_queue();
-    let mut guard = match queue.lock() {
-        Ok(guard) => guard,
-        Err(poisoned) => {
-            eprintln!("Warning: Queue lock was poisoned, recovering");
-            poisoned.into_inner()
-        }
-    };
-    f(&mut guard)
+    f(&mut lock_queue(&DOWNLOAD_QUEUE))
+}
+
+/// Queued download for `https://example.com/{idx}`, for tests
+#[cfg(test)]
+pub(crate) fn create_test_download(idx: i32) -> QueuedDownload {
+    QueuedDownload {
+        media_idx: idx,
+        url: format!("https://example.com/{}", idx),
+        output_location: "/tmp".to_string(),
+        settings: "{}".to_string(),
+        subfolder: None,
+        status: DownloadStatus::Queued,
+        title: None,
+        priority: 0,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
 
-    fn create_test_download(idx: i32) -> QueuedDownload {
-        QueuedDownload {
-            media_idx: idx,
-            url: format!("https://example.com/{}", idx),
-            output_location: "/tmp".to_string(),
-            settings: "{}".to_string(),
-            subfolder: None,
-            status: DownloadStatus::Queued,
-        }
+    fn queued_order(queue: &DownloadQueue) -> Vec<i32> {
+        queue.queue.iter().map(|d| d.media_idx).collect()
     }
 
     #[test]
@@ -256,6 +738,125 @@ mod tests {
         assert_eq!(queue.queue_size(), 1);
     }
 
+    /// Queue with 3 of 4 downloads started (1, 2, 3 in that order) and the limit lowered to 1
+    fn queue_after_decrease(drain: bool) -> DownloadQueue {
+        let mut queue = DownloadQueue::new(3);
+        queue.set_drain_overflow_on_decrease(drain);
+        for idx in 1..=4 {
+            queue.enqueue(create_test_download(idx)).unwrap();
+        }
+        for _ in 0..3 {
+            queue.next_to_start();
+        }
+        queue.set_max_concurrent(1);
+        queue
+    }
+
+    #[test]
+    fn test_decrease_drains_overflow_by_default() {
+        let mut queue = queue_after_decrease(true);
+        assert!(queue.pause_overflow().is_empty());
+        assert_eq!(queue.active_count(), 3, "surplus downloads keep running");
+
+        // Nothing new starts until active falls below the new limit
+        assert!(queue.next_to_start().is_none());
+        queue.complete(1);
+        assert!(queue.next_to_start().is_none());
+        queue.complete(2);
+        assert!(queue.next_to_start().is_none());
+        queue.complete(3);
+        assert_eq!(queue.next_to_start().map(|d| d.media_idx), Some(4));
+    }
+
+    #[test]
+    fn test_decrease_pauses_newest_overflow_in_strict_mode() {
+        let mut queue = queue_after_decrease(false);
+        assert_eq!(queue.pause_overflow(), vec![3, 2]);
+        assert!(queue.pause_overflow().is_empty(), "already marked");
+
+        // Pausing downloads keep their slots until their processes exit
+        assert_eq!(queue.active_count(), 3);
+        assert_eq!(queue.queued_urls(), vec!["https://example.com/4"]);
+        assert!(queue.next_to_start().is_none());
+
+        assert!(queue.requeue_paused(3));
+        assert!(queue.requeue_paused(2));
+        assert!(!queue.requeue_paused(1), "1 was never paused");
+        assert_eq!(queue.active_count(), 1);
+        assert!(queue.is_active(1));
+        assert_eq!(
+            queue.queued_urls(),
+            vec!["https://example.com/2", "https://example.com/3", "https://example.com/4"]
+        );
+
+        // Paused downloads resume first, oldest-started first, as slots free up
+        assert!(queue.next_to_start().is_none());
+        queue.complete(1);
+        assert_eq!(queue.next_to_start().map(|d| d.media_idx), Some(2));
+    }
+
+    #[test]
+    fn test_paused_download_that_finished_first_is_not_requeued() {
+        let mut queue = queue_after_decrease(false);
+        assert_eq!(queue.pause_overflow(), vec![3, 2]);
+
+        // 3 completed before it saw the pause request
+        queue.complete(3);
+        assert!(!queue.requeue_paused(3));
+        assert!(queue.has_completed("https://example.com/3"));
+        assert!(queue.requeue_paused(2));
+        assert_eq!(queue.queued_urls(), vec!["https://example.com/2", "https://example.com/4"]);
+    }
+
+    #[test]
+    fn test_cancel_all_forgets_pausing_downloads() {
+        let mut queue = queue_after_decrease(false);
+        assert_eq!(queue.pause_overflow(), vec![3, 2]);
+        queue.cancel_all();
+
+        // A paused download cancelled by cancel-all is not requeued later
+        assert!(!queue.requeue_paused(3));
+        assert!(queue.pause_overflow().is_empty());
+
+        // New downloads over the limit are still paused normally
+        for idx in 5..=7 {
+            queue.enqueue(create_test_download(idx)).unwrap();
+        }
+        queue.set_max_concurrent(3);
+        while queue.next_to_start().is_some() {}
+        queue.set_max_concurrent(2);
+        assert_eq!(queue.pause_overflow(), vec![7]);
+    }
+
+    #[test]
+    fn test_next_to_start_skips_index_still_running() {
+        let mut queue = DownloadQueue::new(2);
+        queue.enqueue(create_test_download(1)).unwrap();
+        queue.enqueue(create_test_download(2)).unwrap();
+
+        // The previous task for 1 has not released its cancel flag yet
+        let running = HashSet::from([1]);
+        assert_eq!(queue.next_to_start_except(&running).map(|d| d.media_idx), Some(2));
+        assert!(queue.next_to_start_except(&running).is_none());
+        assert_eq!(queue.next_to_start_except(&HashSet::new()).map(|d| d.media_idx), Some(1));
+    }
+
+    #[test]
+    fn test_free_slots_clamped_after_concurrency_decrease() {
+        let mut queue = DownloadQueue::new(3);
+        assert_eq!(queue.free_slots(), 3);
+
+        queue.enqueue(create_test_download(1)).unwrap();
+        queue.enqueue(create_test_download(2)).unwrap();
+        queue.next_to_start();
+        queue.next_to_start();
+        assert_eq!(queue.free_slots(), 1);
+
+        // Running downloads keep going, so active now exceeds the limit
+        queue.set_max_concurrent(1);
+        assert_eq!(queue.free_slots(), 0);
+    }
+
     #[test]
     fn test_complete_and_start_next() {
         let mut queue = DownloadQueue::new(2);
@@ -342,6 +943,421 @@ mod tests {
         assert_eq!(status.max_concurrent, 2);
     }
 
+    #[test]
+    fn test_has_completed_tracks_successful_downloads_only() {
+        let mut queue = DownloadQueue::new(3);
+        queue.enqueue(create_test_download(1)).unwrap();
+        queue.enqueue(create_test_download(2)).unwrap();
+        queue.enqueue(create_test_download(3)).unwrap();
+        queue.next_to_start();
+        queue.next_to_start();
+        queue.next_to_start();
+
+        assert!(!queue.has_completed("https://example.com/1"));
+
+        queue.complete(1);
+        queue.fail(2);
+        queue.cancel(3);
+
+        assert!(queue.has_completed("https://example.com/1"));
+        assert!(!queue.has_completed("https://example.com/2"));
+        assert!(!queue.has_completed("https://example.com/3"));
+    }
+
+    #[test]
+    fn test_has_completed_survives_cancel_all() {
+        let mut queue = DownloadQueue::new(1);
+        queue.enqueue(create_test_download(1)).unwrap();
+        queue.next_to_start();
+        queue.complete(1);
+        queue.cancel_all();
+
+        assert!(queue.has_completed("https://example.com/1"));
+    }
+
+    #[test]
+    fn test_oldest_active_follows_start_order() {
+        let mut queue = DownloadQueue::new(3);
+        assert_eq!(queue.oldest_active(), None);
+
+        for idx in [5, 2, 9] {
+            queue.enqueue(create_test_download(idx)).unwrap();
+        }
+        queue.next_to_start();
+        queue.next_to_start();
+        queue.next_to_start();
+
+        // Start order, not media index, decides
+        assert_eq!(queue.oldest_active(), Some(5));
+
+        queue.complete(5);
+        assert_eq!(queue.oldest_active(), Some(2));
+
+        queue.cancel(2);
+        queue.enqueue(create_test_download(1)).unwrap();
+        queue.next_to_start();
+        assert_eq!(queue.oldest_active(), Some(9));
+
+        queue.fail(9);
+        assert_eq!(queue.oldest_active(), Some(1));
+
+        queue.cancel_all();
+        assert_eq!(queue.oldest_active(), None);
+    }
+
+    #[test]
+    fn test_paused_queue_starts_nothing() {
+        let mut queue = DownloadQueue::new(2);
+        queue.enqueue(create_test_download(1)).unwrap();
+        queue.enqueue(create_test_download(2)).unwrap();
+
+        queue.set_paused(true);
+        assert!(queue.is_paused());
+        assert!(queue.next_to_start().is_none());
+        assert_eq!(queue.queue_size(), 2);
+
+        queue.set_paused(false);
+        assert_eq!(queue.next_to_start().map(|d| d.media_idx), Some(1));
+    }
+
+    #[test]
+    fn test_metered_queue_starts_nothing_and_keeps_user_pause() {
+        let mut queue = DownloadQueue::new(2);
+        queue.enqueue(create_test_download(1)).unwrap();
+        queue.enqueue(create_test_download(2)).unwrap();
+
+        queue.set_metered(true);
+        assert!(queue.is_metered());
+        assert!(queue.next_to_start().is_none());
+        assert_eq!(queue.queue_size(), 2);
+
+        // Leaving the metered network does not resume a user pause
+        queue.set_paused(true);
+        queue.set_metered(false);
+        assert!(queue.next_to_start().is_none());
+
+        queue.set_paused(false);
+        assert_eq!(queue.next_to_start().map(|d| d.media_idx), Some(1));
+    }
+
+    #[test]
+    fn test_update_subfolder_of_queued_item() {
+        let mut queue = DownloadQueue::new(1);
+        queue.enqueue(create_test_download(1)).unwrap();
+        queue.enqueue(create_test_download(2)).unwrap();
+        queue.next_to_start();
+
+        assert!(queue.update_subfolder(2, Some("Playlist A".to_string())).is_ok());
+        queue.complete(1);
+        let next = queue.next_to_start().unwrap();
+        assert_eq!(next.subfolder.as_deref(), Some("Playlist A"));
+    }
+
+    #[test]
+    fn test_update_subfolder_rejects_active_and_unknown() {
+        let mut queue = DownloadQueue::new(1);
+        queue.enqueue(create_test_download(1)).unwrap();
+        queue.next_to_start();
+
+        let err = queue.update_subfolder(1, Some("x".to_string())).unwrap_err();
+        assert!(err.contains("in progress"));
+        assert!(queue.update_subfolder(42, None).is_err());
+    }
+
+    #[test]
+    fn test_batch_summary_accumulates_until_drain() {
+        let mut queue = DownloadQueue::new(3);
+        for idx in 1..=4 {
+            queue.enqueue(create_test_download(idx)).unwrap();
+        }
+        queue.next_to_start();
+        queue.next_to_start();
+        queue.next_to_start();
+        queue.record_skipped();
+
+        queue.complete(1);
+        queue.fail(2);
+        assert_eq!(queue.take_batch_summary(), None, "still active and queued");
+
+        queue.cancel(4);
+        assert_eq!(queue.take_batch_summary(), None, "still active");
+
+        queue.complete(3);
+        let summary = queue.take_batch_summary().unwrap();
+        assert_eq!(summary, BatchSummary { completed: 2, failed: 1, cancelled: 1, skipped: 1 });
+
+        // Counts reset after the drain
+        assert_eq!(queue.take_batch_summary(), None);
+        queue.enqueue(create_test_download(5)).unwrap();
+        queue.next_to_start();
+        queue.complete(5);
+        assert_eq!(queue.take_batch_summary().unwrap(), BatchSummary { completed: 1, ..Default::default() });
+    }
+
+    #[test]
+    fn test_active_older_than_selects_by_start_time() {
+        let mut queue = DownloadQueue::new(3);
+        for idx in 1..=3 {
+            queue.enqueue(create_test_download(idx)).unwrap();
+        }
+        let start = Instant::now();
+        queue.next_to_start_at(start, &HashSet::new()).unwrap();
+        queue.next_to_start_at(start + Duration::from_secs(60), &HashSet::new()).unwrap();
+        queue.next_to_start_at(start + Duration::from_secs(110), &HashSet::new()).unwrap();
+
+        let now = start + Duration::from_secs(120);
+        assert_eq!(queue.active_older_than_at(Duration::from_secs(30), now), vec![1, 2]);
+        assert_eq!(queue.active_older_than_at(Duration::from_secs(5), now), vec![1, 2, 3]);
+        assert!(queue.active_older_than_at(Duration::from_secs(600), now).is_empty());
+
+        // Finished downloads are no longer candidates
+        queue.complete(1);
+        assert_eq!(queue.active_older_than_at(Duration::from_secs(30), now), vec![2]);
+    }
+
+    #[test]
+    fn test_skip_frees_slot_and_counts_as_skipped() {
+        let mut queue = DownloadQueue::new(1);
+        queue.enqueue(create_test_download(1)).unwrap();
+        queue.next_to_start();
+
+        queue.skip(1);
+        assert!(!queue.is_active(1));
+        assert!(!queue.has_completed(&create_test_download(1).url));
+        assert_eq!(queue.take_batch_summary().unwrap(), BatchSummary { skipped: 1, ..Default::default() });
+
+        // The URL can be queued again
+        assert!(queue.enqueue(create_test_download(1)).is_ok());
+    }
+
+    #[test]
+    fn test_batch_summary_counts_cancel_all() {
+        let mut queue = DownloadQueue::new(1);
+        queue.enqueue(create_test_download(1)).unwrap();
+        queue.enqueue(create_test_download(2)).unwrap();
+        queue.next_to_start();
+
+        queue.cancel_all();
+        assert_eq!(queue.take_batch_summary().unwrap().cancelled, 2);
+    }
+
+    #[test]
+    fn test_queued_and_active_urls() {
+        let mut queue = DownloadQueue::new(2);
+        for idx in [3, 1, 2, 4] {
+            queue.enqueue(create_test_download(idx)).unwrap();
+        }
+        queue.next_to_start();
+        queue.next_to_start();
+
+        assert_eq!(queue.active_urls(), vec!["https://example.com/1", "https://example.com/3"]);
+        assert_eq!(queue.queued_urls(), vec!["https://example.com/2", "https://example.com/4"]);
+    }
+
+    #[test]
+    fn test_enqueue_refuses_new_items_once_full() {
+        let mut queue = DownloadQueue::new(1);
+        queue.set_max_queued(2);
+        queue.enqueue(create_test_download(1)).unwrap();
+        queue.enqueue(create_test_download(2)).unwrap();
+
+        let err = queue.enqueue(create_test_download(3)).unwrap_err();
+        assert!(matches!(err, DownloaderError::Queue { kind: crate::error::QueueErrorKind::Full, .. }));
+        assert_eq!(queue.queue_full(3), QueueFull { media_idx: 3, queued: 2, max_queued: 2 });
+        // Re-enqueueing a waiting item is still an idempotent no-op
+        assert_eq!(queue.enqueue(create_test_download(1)).unwrap(), EnqueueOutcome::AlreadyPresent);
+
+        // Starting one frees a slot
+        queue.next_to_start();
+        assert_eq!(queue.enqueue(create_test_download(3)).unwrap(), EnqueueOutcome::Queued);
+    }
+
+    #[test]
+    fn test_enqueue_same_url_different_idx_allowed_by_default() {
+        let mut queue = DownloadQueue::new(2);
+        let first = create_test_download(1);
+        let second = QueuedDownload { url: first.url.clone(), ..create_test_download(2) };
+
+        assert_eq!(queue.enqueue(first).unwrap(), EnqueueOutcome::Queued);
+        assert_eq!(queue.enqueue(second).unwrap(), EnqueueOutcome::Queued);
+        assert_eq!(queue.queue_size(), 2);
+    }
+
+    #[test]
+    fn test_enqueue_dedupes_url_when_enabled() {
+        let mut queue = DownloadQueue::new(2);
+        queue.set_dedupe_urls(true);
+        let first = create_test_download(1);
+        let url = first.url.clone();
+
+        assert_eq!(queue.enqueue(first.clone()).unwrap(), EnqueueOutcome::Queued);
+        assert_eq!(queue.enqueue(first).unwrap(), EnqueueOutcome::AlreadyPresent);
+        assert_eq!(
+            queue.enqueue(QueuedDownload { url: url.clone(), ..create_test_download(2) }).unwrap(),
+            EnqueueOutcome::DuplicateUrl
+        );
+
+        // Still a duplicate while downloading
+        queue.next_to_start();
+        assert_eq!(
+            queue.enqueue(QueuedDownload { url: url.clone(), ..create_test_download(3) }).unwrap(),
+            EnqueueOutcome::DuplicateUrl
+        );
+
+        // Once finished, the URL may be queued again
+        queue.complete(1);
+        assert_eq!(
+            queue.enqueue(QueuedDownload { url, ..create_test_download(4) }).unwrap(),
+            EnqueueOutcome::Queued
+        );
+        assert_eq!(queue.queue_size(), 1);
+    }
+
+    #[test]
+    fn test_url_host() {
+        assert_eq!(url_host("https://www.Example.com/watch?v=1").as_deref(), Some("www.example.com"));
+        assert_eq!(url_host("not a url"), None);
+    }
+
+    #[test]
+    fn test_host_cooldown_enters_after_threshold() {
+        let mut cooldowns = HostCooldowns::new(3, Duration::from_secs(60));
+        let now = Instant::now();
+
+        assert!(!cooldowns.record_failure("a.com", now));
+        assert!(!cooldowns.record_failure("a.com", now));
+        assert!(!cooldowns.is_cooling("a.com", now));
+        assert!(cooldowns.record_failure("a.com", now));
+        assert!(cooldowns.is_cooling("a.com", now));
+        assert!(!cooldowns.is_cooling("b.com", now));
+    }
+
+    #[test]
+    fn test_host_cooldown_success_resets_failures() {
+        let mut cooldowns = HostCooldowns::new(2, Duration::from_secs(60));
+        let now = Instant::now();
+
+        assert!(!cooldowns.record_failure("a.com", now));
+        cooldowns.record_success("a.com");
+        assert!(!cooldowns.record_failure("a.com", now));
+        assert!(!cooldowns.is_cooling("a.com", now));
+    }
+
+    #[test]
+    fn test_host_cooldown_exits_after_duration() {
+        let duration = Duration::from_secs(60);
+        let mut cooldowns = HostCooldowns::new(1, duration);
+        let now = Instant::now();
+
+        assert!(cooldowns.record_failure("a.com", now));
+        assert!(cooldowns.is_cooling("a.com", now + duration - Duration::from_secs(1)));
+        assert!(!cooldowns.is_cooling("a.com", now + duration));
+
+        // Counting starts over after the cooldown
+        assert!(cooldowns.record_failure("a.com", now + duration));
+    }
+
+    #[test]
+    fn test_collection_limit_caps_slots_per_subfolder() {
+        let mut queue = DownloadQueue::new(4);
+        queue.set_collection_limit(Some(2));
+        let in_folder = |idx: i32, folder: &str| QueuedDownload {
+            subfolder: Some(folder.to_string()),
+            ..create_test_download(idx)
+        };
+        for idx in 1..=4 {
+            queue.enqueue(in_folder(idx, "Mix")).unwrap();
+        }
+        queue.enqueue(create_test_download(5)).unwrap();
+        queue.enqueue(in_folder(6, "Other")).unwrap();
+
+        // Two slots for "Mix", then its remaining items are passed over
+        let started: Vec<i32> = std::iter::from_fn(|| queue.next_to_start()).map(|d| d.media_idx).collect();
+        assert_eq!(started, vec![1, 2, 5, 6]);
+        assert_eq!(queued_order(&queue), vec![3, 4]);
+
+        // A finished "Mix" item frees a slot for the next one
+        queue.complete(1);
+        assert_eq!(queue.next_to_start().map(|d| d.media_idx), Some(3));
+        assert!(queue.next_to_start().is_none());
+
+        // Without a cap the collection may use every free slot
+        queue.set_collection_limit(None);
+        queue.complete(5);
+        assert_eq!(queue.next_to_start().map(|d| d.media_idx), Some(4));
+    }
+
+    #[test]
+    fn test_fragment_allowance_splits_budget_across_active() {
+        assert_eq!(fragment_allowance(16, 0), 16);
+        assert_eq!(fragment_allowance(16, 1), 16);
+        assert_eq!(fragment_allowance(16, 3), 5);
+        assert_eq!(fragment_allowance(4, 10), 1, "every download keeps one connection");
+
+        let mut queue = DownloadQueue::new(4);
+        assert_eq!(queue.fragment_allowance(), None);
+        queue.set_fragment_budget(Some(8));
+        for idx in 1..=2 {
+            queue.enqueue(create_test_download(idx)).unwrap();
+            queue.next_to_start().unwrap();
+        }
+        // The allowance counts the download that just started
+        assert_eq!(queue.fragment_allowance(), Some(4));
+    }
+
+    #[test]
+    fn test_next_to_start_skips_cooling_host() {
+        let mut queue = DownloadQueue::new(10);
+        let now = Instant::now();
+        let host_download = |idx: i32, host: &str| QueuedDownload {
+            url: format!("https://{}/{}", host, idx),
+            ..create_test_download(idx)
+        };
+
+        // Fail the same host until it cools down
+        for idx in 1..=HOST_FAILURE_THRESHOLD as i32 {
+            queue.enqueue(host_download(idx, "bad.com")).unwrap();
+            queue.next_to_start_at(now, &HashSet::new()).unwrap();
+            let entered = queue.fail_at(idx, now);
+            assert_eq!(entered.is_some(), idx == HOST_FAILURE_THRESHOLD as i32);
+        }
+
+        queue.enqueue(host_download(10, "bad.com")).unwrap();
+        queue.enqueue(host_download(11, "good.com")).unwrap();
+
+        // bad.com is skipped while cooling, good.com starts
+        assert_eq!(queue.next_to_start_at(now, &HashSet::new()).unwrap().media_idx, 11);
+        assert!(queue.next_to_start_at(now, &HashSet::new()).is_none());
+        assert_eq!(queue.queue_size(), 1);
+
+        // After the cooldown, the held-back item starts
+        assert_eq!(queue.next_to_start_at(now + HOST_COOLDOWN, &HashSet::new()).unwrap().media_idx, 10);
+    }
+
+    #[test]
+    fn test_poisoned_queue_lock_still_allows_operations() {
+        let queue = Arc::new(Mutex::new(DownloadQueue::new(2)));
+        queue.lock().unwrap().enqueue(create_test_download(1)).unwrap();
+
+        let queue_clone = queue.clone();
+        let panicked = std::thread::spawn(move || {
+            let _guard = queue_clone.lock().unwrap();
+            panic!("poison the queue lock");
+        })
+        .join();
+        assert!(panicked.is_err());
+        assert!(queue.is_poisoned());
+
+        {
+            let mut q = lock_queue(&queue);
+            q.enqueue(create_test_download(2)).unwrap();
+            assert_eq!(q.next_to_start().map(|d| d.media_idx), Some(1));
+            q.complete(1);
+            assert_eq!(q.queue_size(), 1);
+        }
+        assert!(!queue.is_poisoned(), "recovery clears the poison flag");
+    }
+
     #[test]
     fn test_concurrent_access() {
         use std::sync::Barrier;
@@ -378,4 +1394,49 @@ mod tests {
         assert_eq!(q.active_count(), 1);
         assert_eq!(q.queue_size(), 1);
     }
+
+    #[test]
+    fn test_sort_queued_by_url_leaves_active_untouched() {
+        let mut queue = DownloadQueue::new(1);
+        for (idx, path) in [(1, "zeta"), (2, "beta"), (3, "alpha"), (4, "gamma")] {
+            let download = QueuedDownload { url: format!("https://example.com/{}", path), ..create_test_download(idx) };
+            queue.enqueue(download).unwrap();
+        }
+        queue.next_to_start(); // 1 becomes active
+
+        queue.sort_queued_by(QueueSortKey::Url);
+        assert_eq!(queued_order(&queue), vec![3, 2, 4]);
+        assert!(queue.is_active(1));
+    }
+
+    #[test]
+    fn test_sort_queued_by_priority_is_stable_and_descending() {
+        let mut queue = DownloadQueue::new(1);
+        for (idx, priority) in [(1, 0), (2, 5), (3, 0), (4, 10), (5, 5)] {
+            queue.enqueue(QueuedDownload { priority, ..create_test_download(idx) }).unwrap();
+        }
+
+        queue.sort_queued_by(QueueSortKey::Priority);
+        assert_eq!(queued_order(&queue), vec![4, 2, 5, 1, 3]);
+    }
+
+    #[test]
+    fn test_sort_queued_by_title_puts_untitled_last() {
+        let mut queue = DownloadQueue::new(1);
+        for (idx, title) in [(1, None), (2, Some("banana")), (3, Some("Apple"))] {
+            let title = title.map(str::to_string);
+            queue.enqueue(QueuedDownload { title, ..create_test_download(idx) }).unwrap();
+        }
+
+        queue.sort_queued_by(QueueSortKey::Title);
+        assert_eq!(queued_order(&queue), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn test_queue_sort_key_parse() {
+        assert_eq!(QueueSortKey::parse("title"), Some(QueueSortKey::Title));
+        assert_eq!(QueueSortKey::parse("url"), Some(QueueSortKey::Url));
+        assert_eq!(QueueSortKey::parse("priority"), Some(QueueSortKey::Priority));
+        assert_eq!(QueueSortKey::parse("host"), None);
+    }
 }
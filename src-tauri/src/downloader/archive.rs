@@ -0,0 +1,94 @@
+//! yt-dlp download archive reading, for resuming interrupted playlists.
+//!
+//! Archive files hold one `<extractor> <id>` pair per line (see `--download-archive`).
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::paths::resolve_output_dir;
+use super::playlist::PlaylistItem;
+
+/// Parse archive contents into the set of archived video IDs.
+/// Matching on the ID alone avoids case differences in extractor names.
+pub fn parse_archive(contents: &str) -> HashSet<String> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let _extractor = parts.next()?;
+            parts.next().map(|id| id.to_string())
+        })
+        .collect()
+}
+
+/// Read an archive file; a missing file means nothing was archived yet.
+pub fn read_archive(path: &Path) -> std::io::Result<HashSet<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(parse_archive(&contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashSet::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Resolve the archive path: absolute paths are used as-is, relative ones
+/// are taken relative to the collection's output folder.
+pub fn resolve_archive_path(output_location: &str, subfolder: Option<&str>, archive_file: &str) -> PathBuf {
+    let archive = Path::new(archive_file);
+    if archive.is_absolute() {
+        archive.to_path_buf()
+    } else {
+        Path::new(&resolve_output_dir(output_location, subfolder)).join(archive)
+    }
+}
+
+/// Keep playlist entries whose ID isn't in the archive.
+/// Entries without an ID can't be matched and are kept.
+pub fn filter_unarchived(entries: Vec<PlaylistItem>, archived: &HashSet<String>) -> Vec<PlaylistItem> {
+    entries
+        .into_iter()
+        .filter(|item| item.id.as_ref().is_none_or(|id| !archived.contains(id)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    fn item(id: Option<&str>) -> PlaylistItem {
+        PlaylistItem {
+            url: format!("https://www.youtube.com/watch?v={}", id.unwrap_or("unknown")),
+            title: None,
+            id: id.map(|s| s.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_parse_archive() {
+        let archived = parse_archive("youtube abc123\n\nyoutube   def456  \nmalformed\n");
+        assert_eq!(archived.len(), 2);
+        assert!(archived.contains("abc123"));
+        assert!(archived.contains("def456"));
+    }
+
+    #[test]
+    fn test_filter_unarchived_from_sample_archive_file() {
+        let dir = TestDir::new("archive");
+        std::fs::create_dir_all(dir.join("My Playlist")).unwrap();
+        std::fs::write(dir.join("My Playlist").join("archive.txt"), "youtube aaa\nyoutube ccc\n").unwrap();
+
+        let path = resolve_archive_path(dir.path().to_str().unwrap(), Some("My Playlist"), "archive.txt");
+        let archived = read_archive(&path).unwrap();
+
+        let entries = vec![item(Some("aaa")), item(Some("bbb")), item(Some("ccc")), item(None)];
+        let remaining = filter_unarchived(entries, &archived);
+        let ids: Vec<Option<&str>> = remaining.iter().map(|i| i.id.as_deref()).collect();
+        assert_eq!(ids, vec![Some("bbb"), None]);
+    }
+
+    #[test]
+    fn test_missing_archive_is_empty() {
+        let dir = TestDir::new("no-archive");
+        assert!(read_archive(&dir.join("archive.txt")).unwrap().is_empty());
+    }
+}
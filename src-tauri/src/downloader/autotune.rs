@@ -0,0 +1,177 @@
+//! Experimental concurrency auto-tuning based on download throughput.
+//!
+//! While enabled, a background task samples aggregate download speed every
+//! `AUTOTUNE_INTERVAL` and nudges `max_concurrent` up while total throughput keeps
+//! rising, and down when per-download speed collapses (a sign of contention).
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::download_queue::with_queue;
+
+use super::notify_queue;
+use super::subprocess::aggregate_speed;
+
+/// Default lower bound for auto-tuned concurrency
+pub const AUTO_CONCURRENCY_MIN: usize = 1;
+
+/// Default upper bound for auto-tuned concurrency
+pub const AUTO_CONCURRENCY_MAX: usize = 6;
+
+/// Interval between throughput samples
+const AUTOTUNE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Number of recent samples kept for the controller
+const AUTOTUNE_WINDOW: usize = 4;
+
+/// Total throughput must grow by this factor to count as rising
+const RISING_FACTOR: f64 = 1.10;
+
+/// Per-download speed dropping below this fraction of the previous sample indicates contention
+const CONTENTION_FACTOR: f64 = 0.75;
+
+/// Incremented on every enable/disable; a running tuner exits once it no longer matches
+static AUTOTUNE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// One throughput observation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThroughputSample {
+    /// `max_concurrent` in effect when sampled
+    pub concurrency: usize,
+    /// Downloads reporting a speed
+    pub active: usize,
+    /// Sum of per-download speeds in bytes/sec
+    pub total_bps: f64,
+}
+
+impl ThroughputSample {
+    fn per_download_bps(&self) -> f64 {
+        if self.active == 0 { 0.0 } else { self.total_bps / self.active as f64 }
+    }
+}
+
+/// Suggest a concurrency from recent samples (oldest first), clamped to `min..=max`.
+///
+/// Compares the two latest samples taken with active downloads:
+/// - per-download speed fell sharply while the slots were in use: step down
+/// - total throughput rose and every slot is busy: step up
+/// - otherwise keep the current concurrency
+pub fn suggest_concurrency(samples: &[ThroughputSample], min: usize, max: usize) -> usize {
+    let min = min.max(1);
+    let max = max.max(min);
+
+    let Some(latest) = samples.last() else {
+        return min;
+    };
+    let current = latest.concurrency.clamp(min, max);
+
+    let busy: Vec<&ThroughputSample> = samples.iter().filter(|s| s.active > 0 && s.total_bps > 0.0).collect();
+    let [.., previous, latest] = busy.as_slice() else {
+        return current;
+    };
+
+    if latest.per_download_bps() < previous.per_download_bps() * CONTENTION_FACTOR && latest.active > 1 {
+        return current.saturating_sub(1).max(min);
+    }
+
+    if latest.total_bps > previous.total_bps * RISING_FACTOR && latest.active >= current {
+        return (current + 1).min(max);
+    }
+
+    current
+}
+
+/// Start (or restart) auto-tuning within `min..=max` concurrent downloads
+pub fn start_autotune(min: usize, max: usize) {
+    let generation = AUTOTUNE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    tauri::async_runtime::spawn(async move {
+        let mut samples: VecDeque<ThroughputSample> = VecDeque::with_capacity(AUTOTUNE_WINDOW);
+
+        loop {
+            tokio::time::sleep(AUTOTUNE_INTERVAL).await;
+            if AUTOTUNE_GENERATION.load(Ordering::SeqCst) != generation {
+                break;
+            }
+
+            let (active, total_bps) = aggregate_speed();
+            let concurrency = with_queue(|queue| queue.status().max_concurrent);
+            if samples.len() == AUTOTUNE_WINDOW {
+                samples.pop_front();
+            }
+            samples.push_back(ThroughputSample { concurrency, active, total_bps });
+
+            let suggested = suggest_concurrency(samples.make_contiguous(), min, max);
+            if suggested != concurrency {
+                eprintln!("Auto-concurrency: {} -> {} ({} active, {:.0} B/s)", concurrency, suggested, active, total_bps);
+                with_queue(|queue| queue.set_max_concurrent(suggested));
+                notify_queue();
+            }
+        }
+    });
+}
+
+/// Stop any running auto-tuner; `max_concurrent` keeps its last value
+pub fn stop_autotune() {
+    AUTOTUNE_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(points: &[(usize, usize, f64)]) -> Vec<ThroughputSample> {
+        points
+            .iter()
+            .map(|&(concurrency, active, total_bps)| ThroughputSample { concurrency, active, total_bps })
+            .collect()
+    }
+
+    #[test]
+    fn test_suggest_concurrency_steps_up_while_throughput_rises() {
+        let samples = series(&[(2, 2, 4_000_000.0), (2, 2, 5_000_000.0)]);
+        assert_eq!(suggest_concurrency(&samples, 1, 6), 3);
+
+        // Capped at max
+        let samples = series(&[(6, 6, 4_000_000.0), (6, 6, 8_000_000.0)]);
+        assert_eq!(suggest_concurrency(&samples, 1, 6), 6);
+    }
+
+    #[test]
+    fn test_suggest_concurrency_steps_down_on_contention() {
+        // Added a slot, total flat, per-download speed fell from 2 MB/s to ~1.3 MB/s
+        let samples = series(&[(2, 2, 4_000_000.0), (3, 3, 4_000_000.0)]);
+        assert_eq!(suggest_concurrency(&samples, 1, 6), 2);
+
+        // Never below min
+        let samples = series(&[(2, 2, 4_000_000.0), (2, 2, 1_000_000.0)]);
+        assert_eq!(suggest_concurrency(&samples, 2, 6), 2);
+    }
+
+    #[test]
+    fn test_suggest_concurrency_holds_when_stable_or_idle() {
+        let stable = series(&[(3, 3, 6_000_000.0), (3, 3, 6_100_000.0)]);
+        assert_eq!(suggest_concurrency(&stable, 1, 6), 3);
+
+        // Rising throughput with free slots doesn't need more concurrency
+        let underused = series(&[(4, 2, 2_000_000.0), (4, 2, 3_000_000.0)]);
+        assert_eq!(suggest_concurrency(&underused, 1, 6), 4);
+
+        // Idle samples are ignored
+        let idle = series(&[(3, 0, 0.0), (3, 0, 0.0)]);
+        assert_eq!(suggest_concurrency(&idle, 1, 6), 3);
+        assert_eq!(suggest_concurrency(&[], 2, 6), 2);
+    }
+
+    #[test]
+    fn test_suggest_concurrency_uses_latest_busy_samples() {
+        // An idle gap between busy samples doesn't reset the comparison
+        let samples = series(&[(2, 2, 4_000_000.0), (2, 0, 0.0), (2, 2, 6_000_000.0)]);
+        assert_eq!(suggest_concurrency(&samples, 1, 6), 3);
+
+        // Out-of-range current concurrency is clamped
+        let samples = series(&[(10, 0, 0.0)]);
+        assert_eq!(suggest_concurrency(&samples, 1, 6), 6);
+    }
+}
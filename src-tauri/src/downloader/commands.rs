@@ -2,157 +2,340 @@
 //!
 //! Exposes download-related functionality to the frontend via Tauri commands.
 
+use std::path::Path;
 use std::process::Stdio;
+use std::time::{Duration, Instant};
 
-use serde_json::{json, Value};
-use tauri::{AppHandle, Emitter, Window};
+use futures_util::stream::{self, StreamExt};
+use serde_json::json;
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Emitter, Manager, Window};
 use tokio::process::Command;
+use tokio_util::sync::CancellationToken;
 
-use crate::download_queue::{with_queue, DownloadStatus, QueuedDownload, QueueStatus};
+use crate::download_queue::{
+    with_queue, DownloadQueue, DownloadStatus, EnqueueOutcome, QueueFull, QueueSortKey, QueueStatus, QueuedDownload,
+};
+use crate::error::{classify_ytdlp_stderr, DownloaderError, ErrorCode, FrontendError, QueueErrorKind};
 use crate::events::*;
-use crate::logging::{append_yt_dlp_log, log_error_with_context, ErrorCategory};
+use crate::logging::{
+    append_yt_dlp_log, command_outcome, log_command_entry, log_command_exit, log_error_with_context, redact_url,
+    ErrorCategory,
+};
 use crate::remote_control::broadcast_remote_event;
+use crate::thumbnail::{cache_channel_art, cached_channel_art, parse_channel_art, ChannelArt, ThumbnailQuality};
 
-use super::events::emit_download_error;
-use super::media_info::{apply_provider_overrides, extract_media_info_from_value};
-use super::notify_queue;
-use super::playlist::{parse_playlist_expansion, PlaylistExpansion, MAX_PLAYLIST_ITEMS};
-use super::settings::{validate_output_location, validate_settings, validate_url, DownloadSettings};
-use super::subprocess::{request_cancel, request_cancel_all};
-use super::ytdlp::run_yt_dlp;
+use super::archive::{filter_unarchived, read_archive, resolve_archive_path};
+use super::autotune::{start_autotune, stop_autotune, AUTO_CONCURRENCY_MAX, AUTO_CONCURRENCY_MIN};
+use super::events::{emit_download_error, DownloadEventSink};
+use super::health::{
+    assemble_health_report, head_request, probe_dir_writable, probe_reachability, reach_target, HealthReport,
+    ReachResult,
+};
+use super::import::{read_url_list, write_url_list, UrlImport};
+use super::media_batch::{with_media_info_batches, MediaInfoRequest, MEDIA_INFO_BATCH_CONCURRENCY};
+use super::media_info::{
+    apply_provider_overrides, build_media_info_multi_payload, cache_media_info, cached_media_info, evict_media_info,
+    parse_media_info_lines, ExtractedMediaInfo, MediaInfoPayload,
+};
+use super::mock::{is_mock_mode, mock_media_info};
+use super::orphans::{find_orphans, is_orphan_cleanup_enabled, kill_process, ORPHAN_CLEANUP_ENV_VAR};
+use super::paths::resolve_output_dir;
+use super::preferences::{default_output_location, resolve_preferences_path, store_default_output_location};
+use super::progress::ProgressSample;
+use super::resume::is_resumable;
+use super::{is_queue_pump_alive, notify_queue};
+use super::playlist::{
+    acquire_expansion_permit, cancel_expansion, compute_collection_meta, finish_expansion, json_is_playlist,
+    parse_expansion_entry_lines, parse_playlist_expansion, register_expansion, sanitize_folder_name, CollectionMeta,
+    ExpansionProgress, PlaylistExpansion, EXPAND_ENTRY_FIELDS, EXPAND_ENTRY_MARKER, MAX_PLAYLIST_ITEMS,
+};
+use super::settings::{
+    build_cookie_args, build_extractor_args, build_output_template, date_filter_args, generate_unique_id,
+    is_valid_yyyymmdd, validate_cookies, validate_extractor_args, validate_output_location, validate_settings,
+    validate_url, BatchItem, BatchValidation, DownloadSettings,
+};
+use super::subprocess::{
+    download_eta, download_output, progress_history, request_cancel, request_cancel_all, running_download_pids,
+    CancelReason,
+};
+use super::ytdlp::{
+    build_update_status, fetch_latest_ytdlp_version, probe_ytdlp_version, run_yt_dlp, run_yt_dlp_lines,
+    self_update_ytdlp, RunError, UpdateStatus,
+};
 
-#[tauri::command]
-pub async fn get_media_info(
-    app: AppHandle,
-    window: Window,
-    media_idx: i32,
-    media_source_url: String,
-) -> Result<(), String> {
-    // Validate inputs at boundary
-    validate_url(&media_source_url)?;
-
-    if media_idx < 0 {
-        return Err("Media index must be non-negative".to_string());
-    }
+/// The error to report when yt-dlp's stderr says no extractor handles `url`
+fn unsupported_url_error(url: &str, stderr: &str) -> Option<DownloaderError> {
+    stderr
+        .lines()
+        .any(|line| classify_ytdlp_stderr(line) == Some(ErrorCode::EValUnsupportedUrl))
+        .then(|| DownloaderError::unsupported_url(format!("yt-dlp cannot download from {}", url)))
+}
 
+/// Run `yt-dlp -j` for a URL and extract every media entry it reports.
+/// Triggering `cancel` kills yt-dlp and fails with "yt-dlp was cancelled".
+async fn extract_media_info_items(
+    app: &AppHandle,
+    media_idx: i32,
+    media_source_url: &str,
+    extractor_args: Option<&[String]>,
+    cookie_args: Vec<String>,
+    thumbnail_quality: ThumbnailQuality,
+    cancel: Option<&CancellationToken>,
+) -> Result<Vec<(serde_json::Value, ExtractedMediaInfo)>, String> {
     let mut cmd = Command::new("yt-dlp");
-    cmd.arg(&media_source_url)
+    cmd.arg(media_source_url)
         .arg("-j")
-        .arg("--extractor-args")
-        .arg("generic:impersonate")
+        .args(build_extractor_args(extractor_args))
+        .args(cookie_args)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
 
     #[cfg(windows)]
     cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
 
-    let (output, errors) = run_yt_dlp(&mut cmd).await.map_err(|e| e.to_string())?;
+    let (output, errors) = run_yt_dlp(&mut cmd, cancel).await.map_err(|e| e.to_string())?;
 
     if !errors.is_empty() {
         for line in errors.lines().filter(|l| !l.trim().is_empty()) {
-            append_yt_dlp_log(&app, media_idx, line);
+            append_yt_dlp_log(app, media_idx, line);
         }
     }
 
-    // yt-dlp outputs one JSON object per line for playlists, or a single object for a single video
-    let mut found_any = false;
-    for line in output.lines() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
+    // yt-dlp outputs one JSON object per line; some single URLs (e.g. threads) yield several entries
+    let mut items = parse_media_info_lines(&output, media_source_url, thumbnail_quality);
+    if items.is_empty() {
+        if let Some(err) = unsupported_url_error(media_source_url, &errors) {
+            return Err(err.into());
         }
+        return Err("No valid media info found in yt-dlp output.".to_string());
+    }
 
-        let v: Value = match serde_json::from_str(trimmed) {
-            Ok(v) => v,
-            Err(e) => {
-                println!(
-                    "Failed to parse yt-dlp output line as JSON in get_media_info: {}: {}",
-                    e, trimmed
-                );
-                continue;
-            }
-        };
-
-        let mut info = match extract_media_info_from_value(&v, &media_source_url) {
-            Some(info) => info,
-            None => {
-                println!("Failed to extract media info from yt-dlp JSON: {trimmed}");
-                continue;
-            }
-        };
-
+    for (v, info) in items.iter_mut() {
         // Apply provider-specific overrides (RedGifs, Twitter/X, etc.)
-        apply_provider_overrides(&app, media_idx, &media_source_url, &v, &mut info).await;
+        apply_provider_overrides(app, media_idx, media_source_url, v, info).await;
 
         if info.thumbnail.is_empty() {
-            println!("Invalid thumbnail URL extracted from: '{}'", trimmed);
+            println!("Invalid thumbnail URL extracted for: '{}'", info.title);
         }
+    }
 
-        found_any = true;
-        window
-            .emit(
-                EVT_UPDATE_MEDIA_INFO,
-                (
-                    media_idx,
-                    media_source_url.clone(),
-                    info.title.clone(),
-                    info.thumbnail.clone(),
-                    info.preview_url.clone(),
-                    info.uploader.clone(),
-                    info.collection_id.clone(),
-                    info.collection_kind.clone(),
-                    info.collection_name.clone(),
-                    info.folder_slug.clone(),
-                ),
-            )
-            .map_err(|e| e.to_string())?;
-        broadcast_remote_event(
-            EVT_UPDATE_MEDIA_INFO,
-            json!([
-                media_idx,
-                media_source_url.clone(),
-                info.title,
-                info.thumbnail,
-                info.preview_url,
-                info.uploader,
-                info.collection_id,
-                info.collection_kind,
-                info.collection_name,
-                info.folder_slug,
-            ]),
-        );
+    Ok(items)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_media_info(
+    app: AppHandle,
+    window: Window,
+    media_idx: i32,
+    media_source_url: String,
+    extractor_args: Option<Vec<String>>,
+    cookies_source: Option<String>,
+    cookies_file: Option<String>,
+    thumbnail_quality: Option<String>,
+) -> Result<(), String> {
+    let args = format!("media_idx={}, url={}", media_idx, redact_url(&media_source_url));
+    log_command_entry(&app, "get_media_info", &args);
+    let result = fetch_media_info(
+        app.clone(),
+        window,
+        media_idx,
+        media_source_url,
+        extractor_args,
+        cookies_source,
+        cookies_file,
+        thumbnail_quality,
+        None,
+    )
+    .await;
+    log_command_exit(&app, "get_media_info", &command_outcome(&result));
+    result
+}
+
+/// Fetch media info for one row and emit it as `update-media-info` (plus `media-info-multi`).
+/// `cancel` aborts the yt-dlp probe (used by `get_media_info_batch`).
+#[allow(clippy::too_many_arguments)]
+async fn fetch_media_info(
+    app: AppHandle,
+    window: Window,
+    media_idx: i32,
+    media_source_url: String,
+    extractor_args: Option<Vec<String>>,
+    cookies_source: Option<String>,
+    cookies_file: Option<String>,
+    thumbnail_quality: Option<String>,
+    cancel: Option<CancellationToken>,
+) -> Result<(), String> {
+    // Validate inputs at boundary
+    validate_url(&media_source_url)?;
+
+    if media_idx < 0 {
+        return Err("Media index must be non-negative".to_string());
     }
-    if !found_any {
-        return Err("No valid media info found in yt-dlp output.".to_string());
+
+    if let Some(extra) = &extractor_args {
+        validate_extractor_args(extra)?;
+    }
+
+    validate_cookies(cookies_source.as_deref(), cookies_file.as_deref())?;
+    let cookie_args = build_cookie_args(cookies_source.as_deref(), cookies_file.as_deref());
+    let thumbnail_quality = ThumbnailQuality::from_arg(thumbnail_quality.as_deref())?;
+
+    let items = if is_mock_mode() {
+        vec![(serde_json::Value::Null, mock_media_info(&media_source_url))]
+    } else {
+        extract_media_info_items(
+            &app,
+            media_idx,
+            &media_source_url,
+            extractor_args.as_deref(),
+            cookie_args,
+            thumbnail_quality,
+            cancel.as_ref(),
+        )
+        .await?
+    };
+
+    // The first entry always fills the requesting row
+    let payload = MediaInfoPayload::new(media_idx, &media_source_url, &items[0].1);
+    cache_media_info(&payload);
+    window.emit(EVT_UPDATE_MEDIA_INFO, &payload).map_err(|e| e.to_string())?;
+    broadcast_remote_event(EVT_UPDATE_MEDIA_INFO, json!(payload));
+
+    // Additional entries are reported together so the frontend can allocate rows for them
+    if items.len() > 1 {
+        let payload = build_media_info_multi_payload(media_idx, &media_source_url, items.iter().map(|(_, i)| i));
+        window.emit(EVT_MEDIA_INFO_MULTI, &payload).map_err(|e| e.to_string())?;
+        broadcast_remote_event(EVT_MEDIA_INFO_MULTI, json!(payload));
     }
 
     Ok(())
 }
 
+/// Re-send every cached `update-media-info` payload and the queue status, so a reloaded webview
+/// can rebuild its list. Returns how many media info entries were sent.
+#[tauri::command]
+pub fn resync_media_info(app: AppHandle) -> usize {
+    emit_resync(&app)
+}
+
+/// Drop cached media info for rows the user removed (`media_indices`), or for the whole list when
+/// it was cleared (None), so `resync_media_info` doesn't bring them back. Returns entries dropped.
 #[tauri::command]
-pub async fn expand_playlist(app: AppHandle, media_source_url: String) -> Result<PlaylistExpansion, String> {
+pub fn clear_media_info_cache(media_indices: Option<Vec<i32>>) -> usize {
+    evict_media_info(media_indices.as_deref())
+}
+
+fn emit_resync(sink: &impl DownloadEventSink) -> usize {
+    let cached = cached_media_info();
+    for payload in &cached {
+        sink.emit_event(EVT_UPDATE_MEDIA_INFO, json!(payload));
+    }
+    sink.emit_event(EVT_QUEUE_CHANGED, json!(get_queue_status()));
+    cached.len()
+}
+
+/// List a playlist's or channel's entries. With `dateafter` (YYYYMMDD) only items uploaded on or
+/// after that day are listed, and a newest-first channel scan stops at the first older item.
+#[tauri::command]
+pub async fn expand_playlist(
+    app: AppHandle,
+    media_source_url: String,
+    extractor_args: Option<Vec<String>>,
+    dateafter: Option<String>,
+) -> Result<PlaylistExpansion, String> {
+    log_command_entry(&app, "expand_playlist", &format!("url={}", redact_url(&media_source_url)));
+    let result = run_playlist_expansion(&app, media_source_url, extractor_args, dateafter).await;
+    log_command_exit(&app, "expand_playlist", &command_outcome(&result));
+    result
+}
+
+/// Abort a running `expand_playlist`/`resume_playlist` of `media_source_url`, killing its yt-dlp
+/// process; the expansion then fails with "yt-dlp was cancelled". Returns false if none was running.
+#[tauri::command]
+pub fn cancel_playlist_expansion(media_source_url: String) -> bool {
+    cancel_expansion(&media_source_url)
+}
+
+/// List a playlist's entries (capped at `MAX_PLAYLIST_ITEMS`) with `--flat-playlist -J`.
+/// `cancel_playlist_expansion` aborts it while it runs.
+async fn run_playlist_expansion(
+    app: &AppHandle,
+    media_source_url: String,
+    extractor_args: Option<Vec<String>>,
+    dateafter: Option<String>,
+) -> Result<PlaylistExpansion, String> {
     validate_url(&media_source_url)?;
 
+    if let Some(extra) = &extractor_args {
+        validate_extractor_args(extra)?;
+    }
+    if let Some(date) = dateafter.as_deref()
+        && !is_valid_yyyymmdd(date)
+    {
+        return Err(format!("Invalid dateafter: {} (expected YYYYMMDD)", date));
+    }
+
+    // Cancellable from the moment it is requested, including while waiting for a permit
+    let (expansion_id, cancel) = register_expansion(&media_source_url);
+    let result = expand_with_ytdlp(app, &media_source_url, extractor_args, dateafter.as_deref(), &cancel).await;
+    finish_expansion(&media_source_url, expansion_id);
+    result
+}
+
+/// The yt-dlp half of `run_playlist_expansion`, aborted by `cancel`
+async fn expand_with_ytdlp(
+    app: &AppHandle,
+    media_source_url: &str,
+    extractor_args: Option<Vec<String>>,
+    dateafter: Option<&str>,
+    cancel: &CancellationToken,
+) -> Result<PlaylistExpansion, String> {
+    // Held until this expansion's yt-dlp process has exited
+    let _permit = tokio::select! {
+        permit = acquire_expansion_permit() => permit,
+        _ = cancel.cancelled() => return Err(RunError::Cancelled.to_string()),
+    };
+
     let mut cmd = Command::new("yt-dlp");
     let playlist_window = format!("1-{}", MAX_PLAYLIST_ITEMS);
-    cmd.arg(&media_source_url)
+    cmd.arg(media_source_url)
         .arg("--playlist-items")
         .arg(&playlist_window)
         .arg("--flat-playlist")
         .arg("-J")
-        .arg("--extractor-args")
-        .arg("generic:impersonate")
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
+        .arg("--print")
+        .arg(format!("{}{}", EXPAND_ENTRY_MARKER, EXPAND_ENTRY_FIELDS))
+        .args(date_filter_args(dateafter))
+        .args(build_extractor_args(extractor_args.as_deref()));
 
-    #[cfg(windows)]
-    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    // Entry markers stream in as yt-dlp pages through the playlist; the -J document comes last
+    let mut progress = ExpansionProgress::new();
+    let mut entry_lines = Vec::new();
+    let mut output = String::new();
+    let errors = run_yt_dlp_lines(&mut cmd, Some(cancel), |line| {
+        if line.starts_with(EXPAND_ENTRY_MARKER) {
+            if let Some(count) = progress.observe(&line, Instant::now()) {
+                let _ = app.emit(EVT_PLAYLIST_EXPAND_PROGRESS, json!([media_source_url, count]));
+            }
+            entry_lines.push(line);
+        } else {
+            output.push_str(&line);
+            output.push('\n');
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?;
 
-    let (output, errors) = run_yt_dlp(&mut cmd).await.map_err(|e| e.to_string())?;
+    // Throttling may have held back the last few entries; report the final count
+    if progress.entries_seen() > 0 {
+        let _ = app.emit(EVT_PLAYLIST_EXPAND_PROGRESS, json!([media_source_url, progress.entries_seen()]));
+    }
 
     if !errors.is_empty() {
         log_error_with_context(
-            &app,
+            app,
             ErrorCategory::Download,
             "yt-dlp stderr during playlist expansion",
             json!({
@@ -163,51 +346,390 @@ pub async fn expand_playlist(app: AppHandle, media_source_url: String) -> Result
         );
     }
 
+    // `--break-on-reject` stopped the scan at the first older item, so there is no -J document
+    if dateafter.is_some() && output.trim().is_empty() {
+        return Ok(parse_expansion_entry_lines(&entry_lines));
+    }
     parse_playlist_expansion(&output)
 }
 
+/// Cheaply check whether a URL is a playlist/channel by listing only its first item.
+/// Lets the UI decide whether to offer full expansion.
+#[tauri::command]
+pub async fn is_playlist(media_source_url: String) -> Result<bool, String> {
+    validate_url(&media_source_url)?;
+
+    let mut cmd = Command::new("yt-dlp");
+    cmd.arg(&media_source_url)
+        .arg("--flat-playlist")
+        .arg("--playlist-items")
+        .arg("1")
+        .arg("-J")
+        .args(build_extractor_args(None))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    #[cfg(windows)]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let (output, _errors) = run_yt_dlp(&mut cmd, None).await.map_err(|e| e.to_string())?;
+    json_is_playlist(&output)
+}
+
+/// Fetch a channel's avatar and banner URLs (cached per URL for the session).
+#[tauri::command]
+pub async fn fetch_channel_art(url: String) -> Result<ChannelArt, String> {
+    validate_url(&url)?;
+
+    if let Some(art) = cached_channel_art(&url) {
+        return Ok(art);
+    }
+
+    let mut cmd = Command::new("yt-dlp");
+    cmd.arg(&url)
+        .arg("--flat-playlist")
+        .arg("--playlist-items")
+        .arg("1")
+        .arg("-J")
+        .args(build_extractor_args(None))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    #[cfg(windows)]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let (output, _errors) = run_yt_dlp(&mut cmd, None).await.map_err(|e| e.to_string())?;
+    let v: serde_json::Value =
+        serde_json::from_str(&output).map_err(|e| format!("Failed to parse yt-dlp JSON: {}", e))?;
+
+    let art = parse_channel_art(&v);
+    cache_channel_art(&url, &art);
+    Ok(art)
+}
+
+/// Re-expand an interrupted playlist and enqueue the entries not yet recorded in its download
+/// archive nor completed this session. `archive_file` is relative to the collection folder unless
+/// absolute; a missing archive resumes every entry. The downloads record into the same archive
+/// (`download_archive`), so a later resume skips them too.
+///
+/// Entry `i` of the returned expansion is enqueued as `first_media_idx + i`, so the frontend can
+/// add its rows; each then reports `download-queued`, `download-skipped` or `download-error`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn resume_playlist(
+    app: AppHandle,
+    window: Window,
+    media_source_url: String,
+    output_location: String,
+    subfolder: Option<String>,
+    archive_file: String,
+    mut settings: DownloadSettings,
+    first_media_idx: i32,
+    extractor_args: Option<Vec<String>>,
+) -> Result<PlaylistExpansion, String> {
+    validate_output_location(&output_location)?;
+
+    let folder = settings.effective_subfolder(subfolder.as_deref());
+    let archive_path = resolve_archive_path(&output_location, folder, &archive_file);
+    let archived = read_archive(&archive_path)
+        .map_err(|e| format!("Failed to read archive {}: {}", archive_path.display(), e))?;
+
+    let dateafter = settings.dateafter.clone();
+    let mut expansion = run_playlist_expansion(&app, media_source_url, extractor_args, dateafter).await?;
+    expansion.entries = filter_unarchived(expansion.entries, &archived);
+    expansion.entries.retain(|entry| !with_queue(|queue| queue.has_completed(&entry.url)));
+
+    settings.download_archive = Some(archive_file);
+    for (offset, entry) in expansion.entries.iter().enumerate() {
+        let media_idx = first_media_idx + offset as i32;
+        let result = enqueue_media(
+            &window,
+            media_idx,
+            entry.url.clone(),
+            output_location.clone(),
+            subfolder.clone(),
+            settings.clone(),
+            None,
+            entry.title.clone(),
+            0,
+        );
+        if let Err(e) = &result {
+            emit_download_error(&window, media_idx, e);
+        }
+    }
+    Ok(expansion)
+}
+
+/// Compute the collection id and folder slug for a collection name, using the same
+/// naming rules as playlist expansion so the frontend can precompute consistent folders.
+#[tauri::command]
+pub fn compute_folder_slug(name: String, kind: String) -> Result<CollectionMeta, String> {
+    compute_collection_meta(&name, &kind)
+}
+
+/// Show how a playlist or channel name becomes a folder name on disk
+#[tauri::command]
+pub fn preview_folder_name(name: String) -> String {
+    sanitize_folder_name(&name)
+}
+
+/// The `[id]` filename suffix a URL gets in `hash` unique-id mode, so the UI can show expected filenames
+#[tauri::command]
+pub fn compute_unique_id(url: String) -> String {
+    generate_unique_id(&url)
+}
+
+/// Read a text/CSV file of URLs (one per line, `#` comments allowed) and return the valid ones
+/// along with how many entries were skipped as invalid.
+#[tauri::command]
+pub fn import_urls(path: String) -> Result<UrlImport, String> {
+    read_url_list(Path::new(&path)).map_err(|e| format!("Failed to read {}: {}", path, e))
+}
+
+/// Write queued URLs (plus active ones when `include_active` is set) to a newline-delimited file.
+/// Returns the number of URLs written.
+#[tauri::command]
+pub fn export_queue(path: String, include_active: Option<bool>) -> Result<usize, String> {
+    let urls = with_queue(|queue| {
+        let mut urls = Vec::new();
+        if include_active.unwrap_or(false) {
+            urls.extend(queue.active_urls());
+        }
+        urls.extend(queue.queued_urls());
+        urls
+    });
+
+    write_url_list(Path::new(&path), &urls).map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+/// Validate a URL being added to the list at `media_idx`
+fn validate_add_url(media_idx: i32, url: &str) -> Result<(), FrontendError> {
+    if media_idx < 0 {
+        return Err(DownloaderError::invalid_media_idx("Media index must be non-negative").to_frontend_error());
+    }
+    validate_url(url).map_err(|e| e.to_frontend_error())
+}
+
+/// Accept a URL added in the UI: validate it synchronously, then fetch its media info in the
+/// background (results arrive as `update-media-info`). Returns as soon as validation passes so
+/// the row can show a "validating" state; a failed fetch is reported as `download-error`.
+#[tauri::command]
+pub fn add_url(app: AppHandle, window: Window, media_idx: i32, url: String) -> Result<(), FrontendError> {
+    validate_add_url(media_idx, &url)?;
+
+    tauri::async_runtime::spawn(async move {
+        let error_window = window.clone();
+        if let Err(e) = get_media_info(app, window, media_idx, url, None, None, None, None).await {
+            emit_download_error(&error_window, media_idx, &format!("Media info fetch failed: {}", e));
+        }
+    });
+
+    Ok(())
+}
+
+/// Fetch media info for many rows at once, a few probes at a time. Results arrive as
+/// `update-media-info` like `get_media_info`; a failed probe is reported as `download-error`.
+/// Returns the number of rows accepted; `cancel_media_info_batch(batch_id)` aborts the rest.
+/// Extractor args and cookies apply to every row, as in `get_media_info`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn get_media_info_batch(
+    app: AppHandle,
+    window: Window,
+    batch_id: String,
+    items: Vec<MediaInfoRequest>,
+    extractor_args: Option<Vec<String>>,
+    cookies_source: Option<String>,
+    cookies_file: Option<String>,
+    thumbnail_quality: Option<String>,
+) -> Result<usize, String> {
+    if batch_id.trim().is_empty() {
+        return Err("Batch id cannot be empty".to_string());
+    }
+    ThumbnailQuality::from_arg(thumbnail_quality.as_deref())?;
+
+    let probes = with_media_info_batches(|batches| {
+        if batches.contains(&batch_id) {
+            return Err(format!("Batch {} is already running", batch_id));
+        }
+        Ok(items.into_iter().map(|item| (batches.register(&batch_id, item.media_idx), item)).collect::<Vec<_>>())
+    })?;
+    let accepted = probes.len();
+
+    tauri::async_runtime::spawn(async move {
+        stream::iter(probes)
+            .for_each_concurrent(MEDIA_INFO_BATCH_CONCURRENCY, |((id, token), item)| {
+                let (app, window, batch_id) = (app.clone(), window.clone(), batch_id.clone());
+                let (extractor_args, quality) = (extractor_args.clone(), thumbnail_quality.clone());
+                let (cookies_source, cookies_file) = (cookies_source.clone(), cookies_file.clone());
+                async move {
+                    let media_idx = item.media_idx;
+                    if token.is_cancelled() {
+                        return;
+                    }
+                    let result = fetch_media_info(
+                        app,
+                        window.clone(),
+                        media_idx,
+                        item.media_source_url,
+                        extractor_args,
+                        cookies_source,
+                        cookies_file,
+                        quality,
+                        Some(token.clone()),
+                    )
+                    .await;
+                    // A cancelled probe is not an error worth reporting
+                    if let Err(e) = result
+                        && !token.is_cancelled()
+                    {
+                        emit_download_error(&window, media_idx, &format!("Media info fetch failed: {}", e));
+                    }
+                    with_media_info_batches(|batches| batches.finish(&batch_id, media_idx, id));
+                }
+            })
+            .await;
+    });
+
+    Ok(accepted)
+}
+
+/// Abort the unfinished probes of a media-info batch. Returns how many were cancelled.
+#[tauri::command]
+pub fn cancel_media_info_batch(batch_id: String) -> usize {
+    with_media_info_batches(|batches| batches.cancel(&batch_id))
+}
+
+/// Validate download settings without starting a download, for live form validation.
+/// Returns the structured error so the UI can branch on `code`.
+#[tauri::command]
+pub fn validate_download_settings(settings: DownloadSettings) -> Result<(), FrontendError> {
+    validate_settings(&settings).map_err(|e| e.to_frontend_error())
+}
+
+/// Validate a bulk add in one call: `settings` once, then every row's index and URL.
+/// Returns which rows can be queued and the error for each that can't, so the dialog can show them together.
+#[tauri::command]
+pub fn validate_batch(items: Vec<BatchItem>, settings: DownloadSettings) -> BatchValidation {
+    super::settings::validate_batch(&items, &settings)
+}
+
+/// Whether a `source_codec` stream can be remuxed into `video_format` without re-encoding.
+/// The UI warns on false; the download is still allowed.
+#[tauri::command]
+pub fn check_remux_compat(video_format: String, source_codec: String) -> bool {
+    super::settings::check_remux_compat(&video_format, &source_codec)
+}
+
+/// Translate (WSL drive paths), expand (`~`) and validate a user-supplied path for this platform
+#[tauri::command]
+pub fn normalize_path(path: String) -> Result<String, String> {
+    super::paths::normalize_path(&path).map_err(|e| e.to_string())
+}
+
+/// Whether a partial `.part` download already exists for this URL, so the UI can offer to resume.
+/// Only detectable in hash unique-id mode; always false otherwise.
+/// The URL and output location are validated first, as for a download.
 #[tauri::command]
+pub fn check_resumable(
+    url: String,
+    output_location: String,
+    subfolder: Option<String>,
+    settings: DownloadSettings,
+) -> Result<bool, FrontendError> {
+    validate_url(&url).map_err(|e| e.to_frontend_error())?;
+    validate_output_location(&output_location).map_err(|e| e.to_frontend_error())?;
+    Ok(is_resumable(&url, &output_location, subfolder.as_deref(), &settings))
+}
+
+/// Preview the `--output` template a download would use, without spawning yt-dlp.
+/// In hash unique-id mode the id is computed from `media_source_url`, or shown as `<hash>` if omitted.
+#[tauri::command]
+pub fn resolve_output_path(
+    output_location: String,
+    subfolder: Option<String>,
+    settings: DownloadSettings,
+    media_source_url: Option<String>,
+) -> String {
+    let output_dir = resolve_output_dir(&output_location, settings.effective_subfolder(subfolder.as_deref()));
+    build_output_template(&output_dir, media_source_url.as_deref(), &settings)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn download_media(
-    _app: AppHandle,
+    app: AppHandle,
     window: Window,
     media_idx: i32,
     media_source_url: String,
     output_location: String,
     subfolder: Option<String>,
     settings: DownloadSettings,
+    skip_if_completed: Option<bool>,
+    title: Option<String>,
+    priority: Option<i32>,
 ) {
-    // Validate inputs at boundary
-    if let Err(e) = validate_url(&media_source_url) {
-        emit_download_error(&window, media_idx, &format!("URL validation failed: {}", e));
-        return;
-    }
+    let url = redact_url(&media_source_url);
+    let args = format!("media_idx={}, url={}, mode={}", media_idx, url, settings.download_mode);
+    log_command_entry(&app, "download_media", &args);
 
-    if let Err(e) = validate_output_location(&output_location) {
-        emit_download_error(&window, media_idx, &format!("Output location invalid: {}", e));
-        return;
+    let result = enqueue_media(
+        &window,
+        media_idx,
+        media_source_url,
+        output_location,
+        subfolder,
+        settings,
+        skip_if_completed,
+        title,
+        priority.unwrap_or(0),
+    );
+    if let Err(e) = &result {
+        emit_download_error(&window, media_idx, e);
     }
+    log_command_exit(&app, "download_media", &command_outcome(&result));
+}
 
-    if let Err(e) = validate_settings(&settings) {
-        emit_download_error(&window, media_idx, &format!("Settings validation failed: {}", e));
-        return;
-    }
+/// Validate and enqueue one download, emitting `download-queued` or `download-skipped`.
+/// Errors are returned for the caller to report as `download-error`.
+#[allow(clippy::too_many_arguments)]
+fn enqueue_media(
+    window: &Window,
+    media_idx: i32,
+    media_source_url: String,
+    output_location: String,
+    subfolder: Option<String>,
+    settings: DownloadSettings,
+    skip_if_completed: Option<bool>,
+    title: Option<String>,
+    priority: i32,
+) -> Result<(), String> {
+    // Validate inputs at boundary
+    validate_url(&media_source_url).map_err(|e| format!("URL validation failed: {}", e))?;
+    let output_location =
+        super::paths::normalize_path(&output_location).map_err(|e| format!("Output location invalid: {}", e))?;
+    validate_settings(&settings).map_err(|e| format!("Settings validation failed: {}", e))?;
 
     if media_idx < 0 {
-        emit_download_error(&window, media_idx, "Invalid media index");
-        return;
+        return Err("Invalid media index".to_string());
     }
 
     broadcast_remote_event(EVT_DOWNLOAD_INVOKE, json!([media_idx, media_source_url]));
     broadcast_remote_event(EVT_DOWNLOAD_INVOKE_ACK, json!([media_idx, media_source_url]));
 
-    // Serialize settings to JSON for queue storage
-    let settings_json = match serde_json::to_string(&settings) {
-        Ok(json) => json,
-        Err(e) => {
-            emit_download_error(&window, media_idx, &format!("Serialize settings failed: {}", e));
-            return;
+    // Optionally refuse URLs already downloaded successfully this session
+    if skip_if_completed.unwrap_or(false) && with_queue(|queue| queue.has_completed(&media_source_url)) {
+        with_queue(|queue| queue.record_skipped());
+        let payload = json!([media_idx, "already_completed"]);
+        if let Err(e) = window.emit(EVT_DOWNLOAD_SKIPPED, &payload) {
+            eprintln!("Failed to emit download-skipped: {}", e);
         }
-    };
+        broadcast_remote_event(EVT_DOWNLOAD_SKIPPED, payload);
+        return Ok(());
+    }
+
+    // Serialize settings to JSON for queue storage
+    let settings_json = serde_json::to_string(&settings).map_err(|e| format!("Serialize settings failed: {}", e))?;
 
     // Create queued download
     let queued_download = QueuedDownload {
@@ -217,13 +739,26 @@ pub fn download_media(
         settings: settings_json,
         subfolder,
         status: DownloadStatus::Queued,
+        title,
+        priority,
     };
 
-    // Enqueue the download
-    let enqueue_result = with_queue(|queue| queue.enqueue(queued_download));
-    if let Err(e) = enqueue_result {
-        emit_download_error(&window, media_idx, &format!("Queue enqueue failed: {}", e));
-        return;
+    // Enqueue the download, noting the queue's size and limit in case it is full
+    let enqueue_result =
+        with_queue(|queue| queue.enqueue(queued_download).map_err(|e| (e, queue.queue_full(media_idx))));
+    match enqueue_result {
+        Ok(EnqueueOutcome::DuplicateUrl) => {
+            // Same URL already queued or downloading under another index
+            with_queue(|queue| queue.record_skipped());
+            let payload = json!([media_idx, "duplicate"]);
+            if let Err(e) = window.emit(EVT_DOWNLOAD_SKIPPED, &payload) {
+                eprintln!("Failed to emit download-skipped: {}", e);
+            }
+            broadcast_remote_event(EVT_DOWNLOAD_SKIPPED, payload);
+            return Ok(());
+        }
+        Ok(_) => {}
+        Err((e, full)) => return report_enqueue_error(window.app_handle(), e, full),
     }
 
     // Emit download-queued event
@@ -234,15 +769,91 @@ pub fn download_media(
 
     // Try to start next download from queue
     notify_queue();
+    Ok(())
+}
+
+/// Report a failed enqueue. A full queue emits `queue-full` with its size and limit so the UI can
+/// offer to raise the limit; any other failure is returned for the caller to report as `download-error`.
+fn report_enqueue_error(sink: &impl DownloadEventSink, err: DownloaderError, full: QueueFull) -> Result<(), String> {
+    match err {
+        DownloaderError::Queue { kind: QueueErrorKind::Full, .. } => {
+            sink.emit_event(EVT_QUEUE_FULL, json!(full));
+            Ok(())
+        }
+        err => Err(format!("Queue enqueue failed: {}", err)),
+    }
+}
+
+#[tauri::command]
+pub fn cancel_download(app: AppHandle, media_idx: i32) {
+    log_command_entry(&app, "cancel_download", &format!("media_idx={}", media_idx));
+    let flagged = request_cancel(media_idx, CancelReason::User);
+    log_command_exit(&app, "cancel_download", if flagged { "cancel requested" } else { "not active" });
+}
+
+/// Cancel the active download that started first (for a "stop current" shortcut).
+/// Returns the cancelled media index, or None if nothing is downloading.
+#[tauri::command]
+pub fn cancel_current_download(app: AppHandle) -> Option<i32> {
+    log_command_entry(&app, "cancel_current_download", "");
+    let cancelled = with_queue(|queue| queue.oldest_active())
+        .and_then(|media_idx| request_cancel(media_idx, CancelReason::User).then_some(media_idx));
+    let outcome = cancelled.map_or("nothing active".to_string(), |idx| format!("cancelled media_idx={}", idx));
+    log_command_exit(&app, "cancel_current_download", &outcome);
+    cancelled
+}
+
+/// Error returned by the orphan commands unless `REMEDIA_ORPHAN_CLEANUP` is set
+fn orphan_cleanup_disabled() -> String {
+    format!("Orphaned process cleanup is disabled; set {}=1 to enable it", ORPHAN_CLEANUP_ENV_VAR)
+}
+
+/// PIDs of yt-dlp processes not belonging to a running download, e.g. left behind by a crash.
+/// Best-effort: an unreadable process list reports none.
+#[tauri::command]
+pub async fn find_orphaned_ytdlp(app: AppHandle) -> Result<Vec<u32>, String> {
+    log_command_entry(&app, "find_orphaned_ytdlp", "");
+    if !is_orphan_cleanup_enabled() {
+        return Err(orphan_cleanup_disabled());
+    }
+    let orphans = find_orphans(&running_download_pids()).await;
+    log_command_exit(&app, "find_orphaned_ytdlp", &format!("found {:?}", orphans));
+    Ok(orphans)
+}
+
+/// Kill the processes `find_orphaned_ytdlp` reports; returns how many were killed
+#[tauri::command]
+pub async fn kill_orphaned_ytdlp(app: AppHandle) -> Result<usize, String> {
+    log_command_entry(&app, "kill_orphaned_ytdlp", "");
+    if !is_orphan_cleanup_enabled() {
+        return Err(orphan_cleanup_disabled());
+    }
+    let mut killed = 0;
+    for pid in find_orphans(&running_download_pids()).await {
+        if kill_process(pid).await {
+            killed += 1;
+        }
+    }
+    log_command_exit(&app, "kill_orphaned_ytdlp", &format!("killed {}", killed));
+    Ok(killed)
 }
 
+/// Cancel active downloads that started more than `older_than_secs` ago, to clear stuck items.
+/// Returns the media indices flagged for cancellation, oldest first.
 #[tauri::command]
-pub fn cancel_download(media_idx: i32) {
-    request_cancel(media_idx);
+pub fn cancel_stale_downloads(app: AppHandle, older_than_secs: u64) -> Vec<i32> {
+    log_command_entry(&app, "cancel_stale_downloads", &format!("older_than_secs={}", older_than_secs));
+    let stale = with_queue(|queue| queue.active_older_than(Duration::from_secs(older_than_secs)));
+    let cancelled: Vec<i32> =
+        stale.into_iter().filter(|&media_idx| request_cancel(media_idx, CancelReason::TooOld)).collect();
+    log_command_exit(&app, "cancel_stale_downloads", &format!("cancelled {:?}", cancelled));
+    cancelled
 }
 
 #[tauri::command]
-pub fn cancel_all_downloads(window: Window) {
+pub fn cancel_all_downloads(app: AppHandle, window: Window) {
+    log_command_entry(&app, "cancel_all_downloads", "");
+
     // Cancel all downloads in queue (both queued and active)
     let cancelled_indices = with_queue(|queue| queue.cancel_all());
 
@@ -258,34 +869,413 @@ pub fn cancel_all_downloads(window: Window) {
     // Emit cancelled events only for queued items; active ones will emit when their tasks observe the flag
     for media_idx in cancelled_indices {
         if !active_indices.contains(&media_idx) {
-            if let Err(e) = window.emit(EVT_DOWNLOAD_CANCELLED, media_idx) {
+            if let Err(e) = window.emit(EVT_DOWNLOAD_CANCELLED, (media_idx, CancelReason::All)) {
                 eprintln!("Failed to emit download-cancelled for {}: {}", media_idx, e);
             }
-            broadcast_remote_event(EVT_DOWNLOAD_CANCELLED, json!(media_idx));
+            broadcast_remote_event(EVT_DOWNLOAD_CANCELLED, json!([media_idx, CancelReason::All]));
         }
     }
+
+    // Let the pump report the batch summary if nothing was running
+    notify_queue();
+    log_command_exit(&app, "cancel_all_downloads", "ok");
 }
 
-/// Update the maximum number of concurrent downloads.
-/// If capacity increased and there are queued items, immediately starts more downloads.
+/// Update the maximum number of concurrent downloads and return the resulting queue status,
+/// also broadcast as `queue-changed`. If capacity increased, queued items start immediately.
 #[tauri::command]
-pub fn set_max_concurrent_downloads(_window: Window, max_concurrent: usize) -> Result<(), String> {
+pub fn set_max_concurrent_downloads(app: AppHandle, max_concurrent: usize) -> Result<QueueStatus, String> {
+    log_command_entry(&app, "set_max_concurrent_downloads", &format!("max_concurrent={}", max_concurrent));
+    let result = with_queue(|queue| apply_max_concurrent(queue, max_concurrent));
+    log_command_exit(&app, "set_max_concurrent_downloads", &command_outcome(&result));
+
+    let result = result.map(|(status, paused)| {
+        // Strict overflow mode: stop the downloads moved back to the queue
+        for media_idx in paused {
+            request_cancel(media_idx, CancelReason::Paused);
+        }
+        status
+    });
+
+    if let Ok(status) = &result {
+        eprintln!("Updated max concurrent downloads to {}", max_concurrent);
+        if let Err(e) = app.emit(EVT_QUEUE_CHANGED, status) {
+            eprintln!("Failed to emit queue-changed: {}", e);
+        }
+        broadcast_remote_event(EVT_QUEUE_CHANGED, json!(status));
+
+        // Kick the queue so new capacity is used immediately
+        notify_queue();
+    }
+
+    result
+}
+
+/// Set the limit and, in strict overflow mode, mark surplus active downloads as pausing.
+/// Returns the new status and the paused indices whose processes must be stopped; each is
+/// requeued once its process exits.
+fn apply_max_concurrent(queue: &mut DownloadQueue, max_concurrent: usize) -> Result<(QueueStatus, Vec<i32>), String> {
     if max_concurrent == 0 {
         return Err("Max concurrent downloads must be at least 1".to_string());
     }
 
-    with_queue(|queue| queue.set_max_concurrent(max_concurrent));
+    queue.set_max_concurrent(max_concurrent);
+    let paused = queue.pause_overflow();
+    Ok((queue.status(), paused))
+}
+
+/// Choose what lowering the concurrency limit does to surplus active downloads: let them finish
+/// (`drain = true`, the default) or pause the newest-started ones and requeue them (`false`).
+#[tauri::command]
+pub fn set_drain_overflow_on_decrease(drain: bool) {
+    with_queue(|queue| queue.set_drain_overflow_on_decrease(drain));
+}
+
+/// Reorder the waiting downloads by `"title"`, `"url"` or `"priority"` (highest first).
+/// Active downloads are not affected.
+#[tauri::command]
+pub fn sort_queue(key: String) -> Result<(), String> {
+    let key = QueueSortKey::parse(&key).ok_or_else(|| format!("Invalid sort key: {}", key))?;
+    with_queue(|queue| queue.sort_queued_by(key));
+    Ok(())
+}
+
+/// Re-file a queued download under a different subfolder (None = output location root).
+/// Errors if the download has already started or isn't queued.
+#[tauri::command]
+pub fn set_download_subfolder(media_idx: i32, subfolder: Option<String>) -> Result<(), String> {
+    with_queue(|queue| queue.update_subfolder(media_idx, subfolder))
+}
+
+/// Enable or disable experimental concurrency auto-tuning within `min..=max` (defaults 1..=6).
+/// Disabling leaves `max_concurrent` at its last tuned value.
+#[tauri::command]
+pub fn set_auto_concurrency(enabled: bool, min: Option<usize>, max: Option<usize>) -> Result<(), String> {
+    if !enabled {
+        stop_autotune();
+        return Ok(());
+    }
+
+    let min = min.unwrap_or(AUTO_CONCURRENCY_MIN);
+    let max = max.unwrap_or(AUTO_CONCURRENCY_MAX);
+    if min == 0 || min > max {
+        return Err(format!("Invalid auto-concurrency bounds: {}..={}", min, max));
+    }
 
-    eprintln!("Updated max concurrent downloads to {}", max_concurrent);
+    start_autotune(min, max);
+    Ok(())
+}
 
-    // Kick the queue so new capacity is used immediately
+/// Cap how many concurrent slots one collection (playlist/channel subfolder) may use, leaving the
+/// rest for other downloads. `None` lifts the cap.
+#[tauri::command]
+pub fn set_collection_concurrency(limit: Option<usize>) -> Result<(), String> {
+    if limit == Some(0) {
+        return Err("Collection concurrency must be at least 1".to_string());
+    }
+    with_queue(|queue| queue.set_collection_limit(limit));
+    // Lifting or raising the cap may let held items start
     notify_queue();
+    Ok(())
+}
 
+/// Limit the fragment connections all downloads may open together. Each download starting
+/// afterwards gets at most `budget / active downloads` of its `concurrent_fragments`; `None` lifts the limit.
+#[tauri::command]
+pub fn set_fragment_budget(budget: Option<usize>) -> Result<(), String> {
+    if budget == Some(0) {
+        return Err("Fragment budget must be at least 1".to_string());
+    }
+    with_queue(|queue| queue.set_fragment_budget(budget));
+    Ok(())
+}
+
+/// Limit how many downloads may wait in the queue; enqueueing beyond it emits `queue-full`.
+/// Items already queued are kept when the limit is lowered.
+#[tauri::command]
+pub fn set_max_queued(max_queued: usize) -> Result<(), String> {
+    if max_queued == 0 {
+        return Err("Queue limit must be at least 1".to_string());
+    }
+    with_queue(|queue| queue.set_max_queued(max_queued));
     Ok(())
 }
 
+/// Enable or disable rejecting URLs already queued or downloading under another index
+#[tauri::command]
+pub fn set_dedupe_urls(enabled: bool) {
+    with_queue(|queue| queue.set_dedupe_urls(enabled));
+}
+
+/// Pause or resume the queue (e.g. resuming after a disk-full pause).
+/// Resuming immediately starts queued downloads if slots are free.
+#[tauri::command]
+pub fn set_queue_paused(paused: bool) {
+    with_queue(|queue| queue.set_paused(paused));
+    if !paused {
+        notify_queue();
+    }
+}
+
+/// Hold the queue while on a metered connection (the frontend detects it) and release it after.
+/// Downloads already running keep going: yt-dlp's rate limit is fixed when the process starts.
+#[tauri::command]
+pub fn set_metered(app: AppHandle, metered: bool) {
+    let changed = with_queue(|queue| {
+        let changed = queue.is_metered() != metered;
+        queue.set_metered(metered);
+        changed
+    });
+    if !changed {
+        return;
+    }
+
+    if let Err(e) = app.emit(EVT_METERED_CHANGED, metered) {
+        eprintln!("Failed to emit metered-changed: {}", e);
+    }
+    broadcast_remote_event(EVT_METERED_CHANGED, json!(metered));
+    if !metered {
+        notify_queue();
+    }
+}
+
+/// Compare the installed yt-dlp with the latest GitHub release.
+/// Offline or failed lookups report `latest: None` rather than an error.
+#[tauri::command]
+pub async fn check_ytdlp_update() -> Result<UpdateStatus, String> {
+    let current = probe_ytdlp_version().await.ok_or_else(|| "yt-dlp is not installed or not on PATH".to_string())?;
+    let latest = fetch_latest_ytdlp_version().await;
+    Ok(build_update_status(current, latest))
+}
+
+/// Update a standalone yt-dlp install in place via `yt-dlp -U`.
+/// Returns yt-dlp's result line; pip/package-manager and read-only installs return a hint instead.
+#[tauri::command]
+pub async fn update_ytdlp() -> Result<String, String> {
+    self_update_ytdlp().await
+}
+
 /// Get current queue status
 #[tauri::command]
 pub fn get_queue_status() -> QueueStatus {
     with_queue(|queue| queue.status())
 }
+
+/// Download slots not in use, for a "2 of 3 slots busy" indicator
+#[tauri::command]
+pub fn get_free_slots() -> usize {
+    with_queue(|queue| queue.free_slots())
+}
+
+/// Seconds remaining for one active download, from its latest progress line.
+/// None when the download is not running or yt-dlp has not estimated it yet.
+#[tauri::command]
+pub fn get_download_eta(media_idx: i32) -> Option<u64> {
+    download_eta(media_idx)
+}
+
+/// The last `limit` raw yt-dlp output lines of a download, oldest first, for troubleshooting one item.
+/// Output is kept while the download runs and after it fails or is cancelled; a successful download drops it.
+#[tauri::command]
+pub fn get_download_output(media_idx: i32, limit: usize) -> Vec<String> {
+    download_output(media_idx, limit)
+}
+
+/// Progress samples (timestamp, percent, speed) of a download, oldest first, for drawing a speed graph.
+/// At most one sample per second is kept, capped at the latest 600; they survive until the index starts again.
+#[tauri::command]
+pub fn get_progress_history(media_idx: i32) -> Vec<ProgressSample> {
+    progress_history(media_idx)
+}
+
+/// Validate and persist the output location used when a request does not specify one
+#[tauri::command]
+pub fn set_default_output_location(app: AppHandle, path: String) -> Result<(), String> {
+    let prefs_path = resolve_preferences_path(&app).ok_or("Failed to resolve config directory")?;
+    store_default_output_location(&prefs_path, &path)
+}
+
+/// The persisted default output location, or None if it has never been set
+#[tauri::command]
+pub fn get_default_output_location(app: AppHandle) -> Option<String> {
+    default_output_location(&app)
+}
+
+/// Diagnose the download subsystem in a single call (queue pump, yt-dlp, config dir, active downloads)
+#[tauri::command]
+pub async fn health_check(app: AppHandle) -> HealthReport {
+    let ytdlp_version = probe_ytdlp_version().await;
+    // Probe the logs directory under the config dir, which is where the app actually writes
+    let config_writable =
+        app.path().resolve("logs", BaseDirectory::Config).map(|dir| probe_dir_writable(&dir)).unwrap_or(false);
+    let active_downloads = with_queue(|queue| queue.active_count());
+
+    assemble_health_report(is_queue_pump_alive(), ytdlp_version, config_writable, active_downloads)
+}
+
+/// Check that the host behind `url` answers (HEAD to its root, 5s timeout) before starting a batch
+#[tauri::command]
+pub async fn check_host_reachable(url: String) -> Result<ReachResult, String> {
+    let target = reach_target(&url)?;
+    Ok(probe_reachability(target, head_request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::download_queue::create_test_download;
+    use crate::downloader::events::EventCollector;
+
+    #[test]
+    fn test_resync_emits_each_cached_media_info_then_queue_status() {
+        let info = |title: &str| ExtractedMediaInfo {
+            title: title.to_string(),
+            thumbnail: String::new(),
+            preview_url: String::new(),
+            uploader: None,
+            webpage_url: None,
+            collection_id: None,
+            collection_kind: None,
+            collection_name: None,
+            folder_slug: None,
+        };
+        cache_media_info(&MediaInfoPayload::new(-8101, "https://example.com/b", &info("stale")));
+        cache_media_info(&MediaInfoPayload::new(-8100, "https://example.com/a", &info("A")));
+        cache_media_info(&MediaInfoPayload::new(-8101, "https://example.com/b", &info("B")));
+
+        let sink = EventCollector::default();
+        let sent = emit_resync(&sink);
+        let events = sink.take();
+
+        assert_eq!(sent, events.len() - 1);
+        let (last_event, _) = events.last().unwrap();
+        assert_eq!(last_event, EVT_QUEUE_CHANGED);
+        assert!(events[..sent].iter().all(|(event, _)| event == EVT_UPDATE_MEDIA_INFO));
+
+        let ours: Vec<(i64, &str)> = events
+            .iter()
+            .filter(|(_, payload)| payload["mediaIdx"].as_i64().is_some_and(|idx| idx <= -8100))
+            .map(|(_, payload)| (payload["mediaIdx"].as_i64().unwrap(), payload["title"].as_str().unwrap()))
+            .collect();
+        assert_eq!(ours, vec![(-8101, "B"), (-8100, "A")]);
+    }
+
+    #[test]
+    fn test_removed_row_is_not_replayed_by_resync() {
+        let info = mock_media_info("https://example.com/removed");
+        cache_media_info(&MediaInfoPayload::new(-8090, "https://example.com/removed", &info));
+        cache_media_info(&MediaInfoPayload::new(-8091, "https://example.com/kept", &info));
+
+        assert_eq!(clear_media_info_cache(Some(vec![-8090, -8092])), 1);
+
+        let sink = EventCollector::default();
+        emit_resync(&sink);
+        let replayed: Vec<i64> = sink
+            .take()
+            .iter()
+            .filter_map(|(_, payload)| payload["mediaIdx"].as_i64())
+            .filter(|idx| (-8092..=-8090).contains(idx))
+            .collect();
+        assert_eq!(replayed, vec![-8091]);
+    }
+
+    #[test]
+    fn test_full_queue_routes_to_queue_full_event() {
+        let mut queue = DownloadQueue::new(1);
+        queue.set_max_queued(1);
+        queue.enqueue(create_test_download(1)).unwrap();
+        let err = queue.enqueue(create_test_download(2)).unwrap_err();
+
+        let sink = EventCollector::default();
+        assert!(report_enqueue_error(&sink, err, queue.queue_full(2)).is_ok());
+        let events = sink.take();
+        assert_eq!(events, vec![(EVT_QUEUE_FULL.to_string(), json!({"mediaIdx": 2, "queued": 1, "maxQueued": 1}))]);
+
+        // Other failures stay errors and emit nothing
+        let sink = EventCollector::default();
+        let result = report_enqueue_error(&sink, DownloaderError::queue_duplicate("dup"), queue.queue_full(3));
+        assert!(result.is_err());
+        assert!(sink.take().is_empty());
+    }
+
+    #[test]
+    fn test_check_resumable_validates_before_probing() {
+        let settings = DownloadSettings::remote_defaults;
+        let err = check_resumable("file:///etc/passwd".into(), "/tmp".into(), None, settings()).unwrap_err();
+        assert_eq!(err.code, "E_VAL_INVALID_URL");
+        let err = check_resumable("https://example.com/v".into(), " ".into(), None, settings()).unwrap_err();
+        assert_eq!(err.code, "E_VAL_INVALID_PATH");
+
+        assert!(!check_resumable("https://example.com/v".into(), "/tmp".into(), None, settings()).unwrap());
+    }
+
+    #[test]
+    fn test_unsupported_url_stderr_maps_to_validation_error() {
+        let stderr = "WARNING: [generic] Falling back on generic information extractor\n\
+                      ERROR: Unsupported URL: https://example.com/about\n";
+        let err = unsupported_url_error("https://example.com/about", stderr).unwrap();
+        assert_eq!(err.to_frontend_error().code, "E_VAL_UNSUPPORTED_URL");
+        assert!(err.to_string().contains("https://example.com/about"));
+
+        assert!(unsupported_url_error("https://example.com/v", "ERROR: Unable to download webpage\n").is_none());
+        assert!(unsupported_url_error("https://example.com/v", "").is_none());
+    }
+
+    #[test]
+    fn test_validate_add_url_rejects_invalid_urls() {
+        for url in ["", "   ", "not a url", "ftp://example.com/file"] {
+            let err = validate_add_url(0, url).unwrap_err();
+            assert_eq!(err.code, "E_VAL_INVALID_URL", "{url:?}");
+            assert!(!err.retryable);
+        }
+
+        let err = validate_add_url(-1, "https://example.com/video").unwrap_err();
+        assert_eq!(err.code, "E_VAL_INVALID_MEDIA_IDX");
+
+        assert!(validate_add_url(3, "https://example.com/video").is_ok());
+    }
+
+    #[test]
+    fn test_apply_max_concurrent_returns_updated_status() {
+        let mut queue = DownloadQueue::new(3);
+
+        let (status, paused) = apply_max_concurrent(&mut queue, 5).unwrap();
+        assert_eq!(status.max_concurrent, 5);
+        assert!(paused.is_empty());
+        assert_eq!((status.queued, status.active), (0, 0));
+
+        assert!(apply_max_concurrent(&mut queue, 0).is_err());
+        assert_eq!(queue.status().max_concurrent, 5, "a rejected value leaves the limit unchanged");
+    }
+
+    #[test]
+    fn test_preview_folder_name_matches_sanitizer() {
+        for name in ["Best of 2024 | Top Picks", "  AC/DC: Live  ", "Plain", ""] {
+            assert_eq!(preview_folder_name(name.to_string()), sanitize_folder_name(name));
+        }
+        assert_eq!(preview_folder_name("  AC/DC: Live  ".to_string()), "AC_DC_ Live");
+    }
+
+    #[test]
+    fn test_compute_unique_id_matches_internal_hash() {
+        for url in ["https://www.youtube.com/watch?v=abc123", "https://example.com/v?a=1&b=2", ""] {
+            assert_eq!(compute_unique_id(url.to_string()), generate_unique_id(url));
+        }
+    }
+
+    #[test]
+    fn test_validate_download_settings_accepts_defaults() {
+        assert!(validate_download_settings(DownloadSettings::remote_defaults()).is_ok());
+    }
+
+    #[test]
+    fn test_validate_download_settings_invalid_video_format() {
+        let mut settings = DownloadSettings::remote_defaults();
+        settings.video_format = "avi".to_string();
+
+        let err = validate_download_settings(settings).unwrap_err();
+        assert_eq!(err.code, "E_VAL_INVALID_SETTINGS");
+        assert!(err.message.contains("video_format"));
+        assert!(!err.retryable);
+    }
+}
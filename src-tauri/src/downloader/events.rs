@@ -3,12 +3,36 @@
 //! Provides generic helpers for emitting download-related events to both
 //! the Tauri frontend and remote control connections.
 
-use serde_json::json;
-use tauri::{Emitter, Manager};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
 
 use crate::events::*;
 use crate::logging::{log_error_simple, ErrorCategory};
-use crate::remote_control::broadcast_remote_event;
+use crate::remote_control::{broadcast_if_active, broadcast_remote_event};
+
+/// Destination for per-download events. The app sends them to the webview and any remote
+/// clients; tests substitute a collector so the progress logic runs without a window.
+pub trait DownloadEventSink {
+    fn emit_event(&self, event: &str, payload: Value);
+}
+
+impl DownloadEventSink for WebviewWindow {
+    fn emit_event(&self, event: &str, payload: Value) {
+        if let Err(e) = self.emit(event, &payload) {
+            eprintln!("Failed to emit {}: {}", event, e);
+        }
+        broadcast_if_active(event, payload);
+    }
+}
+
+impl DownloadEventSink for AppHandle {
+    fn emit_event(&self, event: &str, payload: Value) {
+        if let Err(e) = self.emit(event, &payload) {
+            eprintln!("Failed to emit {}: {}", event, e);
+        }
+        broadcast_if_active(event, payload);
+    }
+}
 
 /// Generic helper to emit download errors for any window type that implements Emitter + Manager.
 /// This eliminates duplication between Window and WebviewWindow error handlers.
@@ -34,3 +58,25 @@ where
     broadcast_remote_event(EVT_DOWNLOAD_ERROR, json!(media_idx));
     broadcast_remote_event(EVT_DOWNLOAD_ERROR_DETAIL, json!([media_idx, reason]));
 }
+
+/// Records emitted events in order instead of sending them to a window
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct EventCollector {
+    events: std::sync::Mutex<Vec<(String, Value)>>,
+}
+
+#[cfg(test)]
+impl DownloadEventSink for EventCollector {
+    fn emit_event(&self, event: &str, payload: Value) {
+        self.events.lock().unwrap().push((event.to_string(), payload));
+    }
+}
+
+#[cfg(test)]
+impl EventCollector {
+    /// Events recorded so far, leaving the collector empty
+    pub(crate) fn take(&self) -> Vec<(String, Value)> {
+        std::mem::take(&mut self.events.lock().unwrap())
+    }
+}
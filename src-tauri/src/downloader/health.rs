@@ -0,0 +1,192 @@
+//! Download subsystem health reporting.
+//!
+//! Assembles a single diagnostic snapshot from the queue pump state, a yt-dlp
+//! probe, a config directory write test, and the download queue. Also probes
+//! whether a download host answers at all, before starting a batch.
+
+use std::fs;
+use std::future::Future;
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// File name used for the config directory write probe
+const WRITE_PROBE_FILE: &str = ".remedia-write-probe";
+
+/// A host that takes longer than this to answer counts as unreachable
+const REACH_TIMEOUT: Duration = Duration::from_secs(5);
+
+static REACH_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .user_agent("remedia-reach-check/0.1.0")
+        .timeout(REACH_TIMEOUT)
+        .build()
+        .expect("Failed to build reqwest client")
+});
+
+/// Whether a host answered a probe request, and how quickly
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReachResult {
+    pub reachable: bool,
+    /// Round trip of the probe; None when the host did not answer
+    pub latency_ms: Option<u64>,
+}
+
+/// Health snapshot of the download subsystem
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    /// Whether the queue pump worker is running (false once restarts are exhausted)
+    pub queue_pump_alive: bool,
+    /// Whether yt-dlp could be executed
+    pub ytdlp_available: bool,
+    /// yt-dlp version reported by `--version`, if available
+    pub ytdlp_version: Option<String>,
+    /// Whether the app config directory (logs, settings) is writable
+    pub config_writable: bool,
+    /// Number of downloads currently running
+    pub active_downloads: usize,
+}
+
+/// Build a health report from already-collected probe results
+pub fn assemble_health_report(
+    queue_pump_alive: bool,
+    ytdlp_version: Option<String>,
+    config_writable: bool,
+    active_downloads: usize,
+) -> HealthReport {
+    HealthReport {
+        queue_pump_alive,
+        ytdlp_available: ytdlp_version.is_some(),
+        ytdlp_version,
+        config_writable,
+        active_downloads,
+    }
+}
+
+/// Check that a directory can be created and written to by writing and removing a probe file
+pub fn probe_dir_writable(dir: &Path) -> bool {
+    if fs::create_dir_all(dir).is_err() {
+        return false;
+    }
+
+    let probe = dir.join(WRITE_PROBE_FILE);
+    let written = fs::write(&probe, b"ok").is_ok();
+    let _ = fs::remove_file(&probe);
+    written
+}
+
+/// Root URL of the host a media URL points at (scheme, host and port only)
+pub fn reach_target(url: &str) -> Result<String, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(format!("URL has no http(s) host: {}", url));
+    }
+    Ok(format!("{}/", parsed.origin().ascii_serialization()))
+}
+
+/// Build a reach result from the probe's round trip (None if it failed)
+pub fn assemble_reach_result(latency: Option<Duration>) -> ReachResult {
+    ReachResult { reachable: latency.is_some(), latency_ms: latency.map(|d| d.as_millis() as u64) }
+}
+
+/// Time `request` against `target`. Any answer counts as reachable, even an HTTP error status.
+pub async fn probe_reachability<F, Fut>(target: String, request: F) -> ReachResult
+where
+    F: FnOnce(String) -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    let started = Instant::now();
+    let latency = request(target).await.ok().map(|()| started.elapsed());
+    assemble_reach_result(latency)
+}
+
+/// Send a HEAD request with the reach-check client
+pub async fn head_request(target: String) -> Result<(), String> {
+    REACH_CLIENT.head(&target).send().await.map(|_| ()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn test_reach_target_keeps_only_origin() {
+        assert_eq!(reach_target("https://www.youtube.com/watch?v=abc").unwrap(), "https://www.youtube.com/");
+        assert_eq!(reach_target("http://Example.COM:8080/a/b#frag").unwrap(), "http://example.com:8080/");
+        assert!(reach_target("ftp://example.com/file").is_err());
+        assert!(reach_target("not a url").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_probe_reachability_with_mock_client() {
+        let result = probe_reachability("https://example.com/".to_string(), |target| async move {
+            assert_eq!(target, "https://example.com/");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok(())
+        })
+        .await;
+        assert!(result.reachable);
+        assert!(result.latency_ms.is_some_and(|ms| ms >= 20));
+
+        let result = probe_reachability("https://down.test/".to_string(), |_| async { Err("timed out".into()) }).await;
+        assert_eq!(result, ReachResult { reachable: false, latency_ms: None });
+        assert_eq!(serde_json::to_value(&result).unwrap(), serde_json::json!({"reachable": false, "latencyMs": null}));
+    }
+
+    #[test]
+    fn test_assemble_health_report_all_healthy() {
+        let report = assemble_health_report(true, Some("2025.07.21".to_string()), true, 2);
+
+        assert!(report.queue_pump_alive);
+        assert!(report.ytdlp_available);
+        assert_eq!(report.ytdlp_version.as_deref(), Some("2025.07.21"));
+        assert!(report.config_writable);
+        assert_eq!(report.active_downloads, 2);
+    }
+
+    #[test]
+    fn test_assemble_health_report_missing_ytdlp_and_dead_pump() {
+        let report = assemble_health_report(false, None, false, 0);
+
+        assert!(!report.queue_pump_alive);
+        assert!(!report.ytdlp_available);
+        assert!(report.ytdlp_version.is_none());
+        assert!(!report.config_writable);
+    }
+
+    #[test]
+    fn test_health_report_serializes_camel_case() {
+        let report = assemble_health_report(true, None, true, 1);
+        let json = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(json["queuePumpAlive"], true);
+        assert_eq!(json["ytdlpAvailable"], false);
+        assert_eq!(json["configWritable"], true);
+        assert_eq!(json["activeDownloads"], 1);
+    }
+
+    #[test]
+    fn test_probe_dir_writable_creates_and_cleans_up() {
+        let scratch = TestDir::new("health");
+        let dir = scratch.join("config");
+
+        assert!(probe_dir_writable(&dir));
+        assert!(dir.exists());
+        assert!(!dir.join(WRITE_PROBE_FILE).exists(), "probe file should be removed");
+    }
+
+    #[test]
+    fn test_probe_dir_writable_rejects_file_path() {
+        let dir = TestDir::new("health-file");
+        let file = dir.join("config");
+        fs::write(&file, b"x").unwrap();
+
+        // A regular file cannot be used as a directory
+        assert!(!probe_dir_writable(&file));
+    }
+}
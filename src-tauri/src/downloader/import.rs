@@ -0,0 +1,159 @@
+//! Importing and exporting URL lists maintained outside the app (plain text or CSV).
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::settings::validate_url;
+
+/// Result of importing a URL list
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlImport {
+    /// URLs that passed validation, in file order
+    pub urls: Vec<String>,
+    /// Number of non-comment entries rejected by URL validation
+    pub skipped: usize,
+}
+
+/// Parse a URL list: one URL per line, `#` comments and blank lines ignored.
+/// For CSV rows the first column is used (see `first_csv_column`).
+pub fn parse_url_list(contents: &str) -> UrlImport {
+    let mut urls = Vec::new();
+    let mut skipped = 0;
+
+    for line in contents.lines() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let first_column = first_csv_column(line).trim();
+        if validate_url(first_column).is_ok() {
+            urls.push(first_column.to_string());
+        } else {
+            skipped += 1;
+        }
+    }
+
+    UrlImport { urls, skipped }
+}
+
+/// First column of a CSV row. A quoted field runs to its closing quote, commas included.
+/// URLs may contain commas themselves (`?v=a,b`), so an unquoted field only ends at a comma whose
+/// next column could not continue a URL: empty, quoted, or containing whitespace.
+fn first_csv_column(line: &str) -> &str {
+    if let Some(quoted) = line.strip_prefix('"') {
+        return quoted.split_once('"').map_or(quoted, |(field, _)| field);
+    }
+
+    let end = line
+        .match_indices(',')
+        .map(|(i, _)| i)
+        .find(|&i| {
+            let next = line[i + 1..].split(',').next().unwrap_or_default();
+            next.is_empty() || next.starts_with('"') || next.contains(char::is_whitespace)
+        })
+        .unwrap_or(line.len());
+    &line[..end]
+}
+
+/// Read and parse a URL list file
+pub fn read_url_list(path: &Path) -> std::io::Result<UrlImport> {
+    Ok(parse_url_list(&std::fs::read_to_string(path)?))
+}
+
+/// Write URLs to a newline-delimited file, returning how many were written
+pub fn write_url_list(path: &Path, urls: &[String]) -> std::io::Result<usize> {
+    let mut contents = urls.join("\n");
+    if !contents.is_empty() {
+        contents.push('\n');
+    }
+    std::fs::write(path, contents)?;
+    Ok(urls.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn test_parse_url_list_with_comments_and_invalid_line() {
+        let contents = "\
+# Favourite channels
+https://www.youtube.com/watch?v=abc123
+
+   # indented comment
+https://vimeo.com/12345
+not a url
+ftp://example.com/file.mp4
+";
+        let import = parse_url_list(contents);
+        assert_eq!(import.urls, vec!["https://www.youtube.com/watch?v=abc123", "https://vimeo.com/12345"]);
+        assert_eq!(import.skipped, 2);
+    }
+
+    #[test]
+    fn test_read_url_list_from_file() {
+        let dir = TestDir::new("urls");
+        let path = dir.join("urls.txt");
+        std::fs::write(&path, "# list\nhttps://example.com/video\njavascript:alert(1)\n").unwrap();
+
+        let import = read_url_list(&path).unwrap();
+        assert_eq!(import.urls, vec!["https://example.com/video"]);
+        assert_eq!(import.skipped, 1);
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(read_url_list(&path).is_err());
+    }
+
+    #[test]
+    fn test_write_url_list_round_trip() {
+        let dir = TestDir::new("export");
+        let path = dir.join("urls.txt");
+        let urls = vec!["https://example.com/1".to_string(), "https://example.com/2".to_string()];
+
+        assert_eq!(write_url_list(&path, &urls).unwrap(), 2);
+        let import = read_url_list(&path).unwrap();
+        assert_eq!(import.urls, urls);
+        assert_eq!(import.skipped, 0);
+
+        assert_eq!(write_url_list(&path, &[]).unwrap(), 0);
+        assert!(read_url_list(&path).unwrap().urls.is_empty());
+    }
+
+    #[test]
+    fn test_parse_url_list_csv_first_column() {
+        let contents = "\"https://example.com/a\",Title A\nhttps://example.com/b, Title B\n";
+        let import = parse_url_list(contents);
+        assert_eq!(import.urls, vec!["https://example.com/a", "https://example.com/b"]);
+        assert_eq!(import.skipped, 0);
+    }
+
+    #[test]
+    fn test_parse_url_list_keeps_commas_inside_urls() {
+        let contents = "\
+https://example.com/watch?v=a,b
+\"https://example.com/?ids=1,2\",title
+https://example.com/?ids=3,4,Some Title
+https://example.com/?ids=5,6,,
+";
+        let import = parse_url_list(contents);
+        assert_eq!(
+            import.urls,
+            vec![
+                "https://example.com/watch?v=a,b",
+                "https://example.com/?ids=1,2",
+                "https://example.com/?ids=3,4",
+                "https://example.com/?ids=5,6",
+            ]
+        );
+        assert_eq!(import.skipped, 0);
+    }
+
+    #[test]
+    fn test_parse_url_list_empty() {
+        assert_eq!(parse_url_list(""), UrlImport { urls: Vec::new(), skipped: 0 });
+    }
+}
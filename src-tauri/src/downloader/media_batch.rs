@@ -0,0 +1,120 @@
+//! Registry of in-flight `get_media_info_batch` probes, so a whole batch can be cancelled.
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+use serde::Deserialize;
+use tokio_util::sync::CancellationToken;
+
+/// How many probes of one batch run at the same time
+pub const MEDIA_INFO_BATCH_CONCURRENCY: usize = 4;
+
+static MEDIA_INFO_BATCHES: LazyLock<Mutex<MediaInfoBatches>> =
+    LazyLock::new(|| Mutex::new(MediaInfoBatches::default()));
+
+/// One row to probe in a media-info batch
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaInfoRequest {
+    pub media_idx: i32,
+    pub media_source_url: String,
+}
+
+/// Cancellation tokens for every probe that has not finished yet, grouped by batch id.
+/// A probe hands its token to `run_yt_dlp`, which kills yt-dlp when the token fires.
+/// Each registration gets its own id, so a probe of a cancelled batch finishing late can't
+/// drop the entry of a new batch reusing the same batch id and row.
+#[derive(Debug, Default)]
+pub struct MediaInfoBatches {
+    batches: HashMap<String, HashMap<i32, (u64, CancellationToken)>>,
+    next_id: u64,
+}
+
+impl MediaInfoBatches {
+    pub fn contains(&self, batch_id: &str) -> bool {
+        self.batches.contains_key(batch_id)
+    }
+
+    /// Track a probe for `media_idx` under `batch_id`; returns its registration id and cancellation token
+    pub fn register(&mut self, batch_id: &str, media_idx: i32) -> (u64, CancellationToken) {
+        let (id, token) = (self.next_id, CancellationToken::new());
+        self.next_id += 1;
+        self.batches.entry(batch_id.to_string()).or_default().insert(media_idx, (id, token.clone()));
+        (id, token)
+    }
+
+    /// Stop tracking a probe once it has finished; the batch is dropped with its last probe.
+    /// A no-op unless `id` is still the registration tracked for that row.
+    pub fn finish(&mut self, batch_id: &str, media_idx: i32, id: u64) {
+        if let Some(probes) = self.batches.get_mut(batch_id) {
+            if probes.get(&media_idx).is_some_and(|(current, _)| *current == id) {
+                probes.remove(&media_idx);
+            }
+            if probes.is_empty() {
+                self.batches.remove(batch_id);
+            }
+        }
+    }
+
+    /// Cancel every unfinished probe of `batch_id`.
+    /// Returns how many were cancelled (0 for an unknown or already finished batch).
+    pub fn cancel(&mut self, batch_id: &str) -> usize {
+        let Some(probes) = self.batches.remove(batch_id) else {
+            return 0;
+        };
+        for (_, token) in probes.values() {
+            token.cancel();
+        }
+        probes.len()
+    }
+}
+
+pub fn with_media_info_batches<T>(f: impl FnOnce(&mut MediaInfoBatches) -> T) -> T {
+    let mut batches = MEDIA_INFO_BATCHES.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut batches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_counts_only_unfinished_probes() {
+        let mut batches = MediaInfoBatches::default();
+        let (_, first) = batches.register("b1", 0);
+        let (second_id, second) = batches.register("b1", 1);
+        let (_, third) = batches.register("b1", 2);
+        let (other_id, other) = batches.register("b2", 7);
+
+        batches.finish("b1", 1, second_id);
+        assert_eq!(batches.cancel("b1"), 2);
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled(), "finished probe is not cancelled");
+        assert!(third.is_cancelled());
+
+        // Cancelling again, or an unknown batch, is a no-op
+        assert_eq!(batches.cancel("b1"), 0);
+        assert_eq!(batches.cancel("missing"), 0);
+
+        // Other batches are untouched
+        assert!(!other.is_cancelled());
+        assert!(batches.contains("b2"));
+        batches.finish("b2", 7, other_id);
+        assert!(!batches.contains("b2"));
+    }
+
+    #[test]
+    fn test_late_finish_of_a_cancelled_batch_keeps_the_new_batch() {
+        let mut batches = MediaInfoBatches::default();
+        let (old_id, _) = batches.register("b1", 0);
+        batches.cancel("b1");
+
+        // The batch id is reused before the cancelled probe gets to finish
+        let (_, new) = batches.register("b1", 0);
+        batches.finish("b1", 0, old_id);
+        assert!(batches.contains("b1"));
+
+        assert_eq!(batches.cancel("b1"), 1);
+        assert!(new.is_cancelled());
+    }
+}
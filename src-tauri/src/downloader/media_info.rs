@@ -1,16 +1,38 @@
 //! Media info extraction from yt-dlp JSON output.
 
+use std::collections::BTreeMap;
+use std::sync::{LazyLock, Mutex};
+
+use serde::Serialize;
 use serde_json::Value;
 use tauri::AppHandle;
 
 use crate::logging::{append_yt_dlp_log, log_error_simple, log_warning_simple, ErrorCategory};
 use crate::redgifs::fetch_redgifs_thumbnail;
-use crate::thumbnail::resolve_thumbnail;
+use crate::thumbnail::{resolve_thumbnail, ThumbnailQuality};
 
 use super::playlist::sanitize_folder_name;
 
 /// Media info extracted from yt-dlp JSON
 pub struct ExtractedMediaInfo {
+    pub title: String,
+    pub thumbnail: String,
+    pub preview_url: String,
+    pub uploader: Option<String>,
+    /// The entry's own page (`webpage_url`), when yt-dlp reports one
+    pub webpage_url: Option<String>,
+    pub collection_id: Option<String>,
+    pub collection_kind: Option<String>,
+    pub collection_name: Option<String>,
+    pub folder_slug: Option<String>,
+}
+
+/// Payload for `EVT_UPDATE_MEDIA_INFO`: the metadata that fills the requesting row
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaInfoPayload {
+    pub media_idx: i32,
+    pub media_source_url: String,
     pub title: String,
     pub thumbnail: String,
     pub preview_url: String,
@@ -21,6 +43,170 @@ pub struct ExtractedMediaInfo {
     pub folder_slug: Option<String>,
 }
 
+impl MediaInfoPayload {
+    pub fn new(media_idx: i32, media_source_url: &str, info: &ExtractedMediaInfo) -> Self {
+        Self {
+            media_idx,
+            media_source_url: media_source_url.to_string(),
+            title: info.title.clone(),
+            thumbnail: info.thumbnail.clone(),
+            preview_url: info.preview_url.clone(),
+            uploader: info.uploader.clone(),
+            collection_id: info.collection_id.clone(),
+            collection_kind: info.collection_kind.clone(),
+            collection_name: info.collection_name.clone(),
+            folder_slug: info.folder_slug.clone(),
+        }
+    }
+}
+
+/// Latest `EVT_UPDATE_MEDIA_INFO` payload per row, replayed by `resync_media_info` after a webview reload
+static MEDIA_INFO_CACHE: LazyLock<Mutex<BTreeMap<i32, MediaInfoPayload>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// Remember the media info last sent for a row, replacing any earlier entry
+pub fn cache_media_info(payload: &MediaInfoPayload) {
+    MEDIA_INFO_CACHE.lock().unwrap_or_else(|e| e.into_inner()).insert(payload.media_idx, payload.clone());
+}
+
+/// Every cached media info payload, by row index
+pub fn cached_media_info() -> Vec<MediaInfoPayload> {
+    MEDIA_INFO_CACHE.lock().unwrap_or_else(|e| e.into_inner()).values().cloned().collect()
+}
+
+/// Forget the cached media info of removed rows, or of every row when `media_indices` is None.
+/// Returns how many entries were dropped.
+pub fn evict_media_info(media_indices: Option<&[i32]>) -> usize {
+    let mut cache = MEDIA_INFO_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    match media_indices {
+        Some(indices) => indices.iter().filter(|idx| cache.remove(idx).is_some()).count(),
+        None => {
+            let evicted = cache.len();
+            cache.clear();
+            evicted
+        }
+    }
+}
+
+/// One media entry within a multi-item result (e.g. a Twitter/X thread with several videos)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaInfoItem {
+    /// Position of this entry within the source URL's results (0 = the requesting row)
+    pub sub_index: usize,
+    /// URL to download this entry from: its `webpage_url`, or the source URL when yt-dlp gave none
+    pub url: String,
+    pub title: String,
+    pub thumbnail: String,
+    pub preview_url: String,
+    pub uploader: Option<String>,
+}
+
+/// Payload for `EVT_MEDIA_INFO_MULTI`, emitted when a single URL yields more than one media entry.
+///
+/// Contract: `EVT_UPDATE_MEDIA_INFO` still fills the requesting row (`media_idx`) with the
+/// first entry. This payload then lists every entry so the frontend can add rows for
+/// `sub_index >= 1` instead of overwriting the same row.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaInfoMultiPayload {
+    pub media_idx: i32,
+    pub media_source_url: String,
+    pub items: Vec<MediaInfoItem>,
+}
+
+/// Build the multi-item payload, assigning each entry its sub-index in output order
+pub fn build_media_info_multi_payload<'a>(
+    media_idx: i32,
+    media_source_url: &str,
+    infos: impl IntoIterator<Item = &'a ExtractedMediaInfo>,
+) -> MediaInfoMultiPayload {
+    let items = infos
+        .into_iter()
+        .enumerate()
+        .map(|(sub_index, info)| MediaInfoItem {
+            sub_index,
+            url: info.webpage_url.clone().unwrap_or_else(|| media_source_url.to_string()),
+            title: info.title.clone(),
+            thumbnail: info.thumbnail.clone(),
+            preview_url: info.preview_url.clone(),
+            uploader: info.uploader.clone(),
+        })
+        .collect();
+
+    MediaInfoMultiPayload {
+        media_idx,
+        media_source_url: media_source_url.to_string(),
+        items,
+    }
+}
+
+/// Maximum lines buffered while waiting for a split JSON object to complete
+const MAX_JSON_ACCUMULATED_LINES: usize = 256;
+
+/// Parse yt-dlp `-j` output into JSON values.
+///
+/// Normally each line is one object, but a leading BOM is stripped and lines are
+/// accumulated until they form valid JSON, so objects split across lines (or
+/// pretty-printed) still parse. A buffer that hits a syntax error, or grows past
+/// `MAX_JSON_ACCUMULATED_LINES` without completing, is dropped as garbage.
+pub fn parse_ytdlp_json_lines(output: &str) -> Vec<Value> {
+    let mut values = Vec::new();
+    let mut buffer = String::new();
+    let mut buffered_lines = 0;
+
+    for line in output.lines() {
+        let line = line.trim_start_matches('\u{feff}');
+        if buffer.is_empty() && line.trim().is_empty() {
+            continue;
+        }
+
+        buffer.push_str(line);
+        buffer.push('\n');
+        buffered_lines += 1;
+
+        match serde_json::from_str::<Value>(buffer.trim()) {
+            Ok(v) => {
+                values.push(v);
+                buffer.clear();
+                buffered_lines = 0;
+            }
+            // Incomplete object: keep accumulating, within bounds
+            Err(e) if e.is_eof() && buffered_lines < MAX_JSON_ACCUMULATED_LINES => {}
+            Err(e) => {
+                println!("Failed to parse yt-dlp output as JSON in get_media_info: {}: {}", e, buffer.trim());
+                buffer.clear();
+                buffered_lines = 0;
+            }
+        }
+    }
+
+    if !buffer.trim().is_empty() {
+        println!("Discarding incomplete JSON at end of yt-dlp output: {}", buffer.trim());
+    }
+
+    values
+}
+
+/// Parse yt-dlp `-j` output into media entries.
+/// Output that is not valid JSON or yields no media info is skipped.
+pub fn parse_media_info_lines(
+    output: &str,
+    media_source_url: &str,
+    thumbnail_quality: ThumbnailQuality,
+) -> Vec<(Value, ExtractedMediaInfo)> {
+    let mut items = Vec::new();
+
+    for v in parse_ytdlp_json_lines(output) {
+        match extract_media_info_from_value(&v, media_source_url, thumbnail_quality) {
+            Some(info) => items.push((v, info)),
+            None => println!("Failed to extract media info from yt-dlp JSON: {v}"),
+        }
+    }
+
+    items
+}
+
 /// Extract the best direct URL for preview from formats array
 fn extract_preview_url(v: &Value) -> Option<String> {
     // Try top-level url first (some extractors put it here)
@@ -74,10 +260,14 @@ fn extract_preview_url(v: &Value) -> Option<String> {
 }
 
 /// Extract title, thumbnail, preview URL, and uploader from an already-parsed yt-dlp JSON value
-pub fn extract_media_info_from_value(v: &Value, media_source_url: &str) -> Option<ExtractedMediaInfo> {
+pub fn extract_media_info_from_value(
+    v: &Value,
+    media_source_url: &str,
+    thumbnail_quality: ThumbnailQuality,
+) -> Option<ExtractedMediaInfo> {
     let title = v.get("title").and_then(|t| t.as_str()).filter(|s| !s.is_empty()).unwrap_or(media_source_url).to_string();
 
-    let thumbnail = resolve_thumbnail(v).unwrap_or_default();
+    let thumbnail = resolve_thumbnail(v, thumbnail_quality).unwrap_or_default();
     let preview_url = extract_preview_url(v).unwrap_or_default();
 
     // Extract uploader/channel for display purposes only (not for folder naming)
@@ -90,6 +280,9 @@ pub fn extract_media_info_from_value(v: &Value, media_source_url: &str) -> Optio
         .filter(|s| !s.is_empty())
         .map(sanitize_folder_name);
 
+    let webpage_url =
+        v.get("webpage_url").and_then(|u| u.as_str()).filter(|u| u.starts_with("http")).map(|u| u.to_string());
+
     // Single videos should NOT have collection/folder info - they download to the configured output folder
     // Collection info is only set by parse_playlist_expansion when the URL is detected as a playlist/channel
     Some(ExtractedMediaInfo {
@@ -97,6 +290,7 @@ pub fn extract_media_info_from_value(v: &Value, media_source_url: &str) -> Optio
         thumbnail,
         preview_url,
         uploader,
+        webpage_url,
         collection_id: None,
         collection_kind: None,
         collection_name: None,
@@ -168,6 +362,38 @@ pub async fn apply_provider_overrides(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_media_info_payload_serializes_camel_case_object() {
+        let info = ExtractedMediaInfo {
+            title: "Title".to_string(),
+            thumbnail: "thumb.jpg".to_string(),
+            preview_url: String::new(),
+            uploader: Some("Uploader".to_string()),
+            webpage_url: None,
+            collection_id: Some("playlist:abc".to_string()),
+            collection_kind: Some("playlist".to_string()),
+            collection_name: Some("Mix".to_string()),
+            folder_slug: Some("Mix".to_string()),
+        };
+
+        let value = serde_json::to_value(MediaInfoPayload::new(4, "https://example.com/v", &info)).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "mediaIdx": 4,
+                "mediaSourceUrl": "https://example.com/v",
+                "title": "Title",
+                "thumbnail": "thumb.jpg",
+                "previewUrl": "",
+                "uploader": "Uploader",
+                "collectionId": "playlist:abc",
+                "collectionKind": "playlist",
+                "collectionName": "Mix",
+                "folderSlug": "Mix",
+            })
+        );
+    }
+
     #[test]
     fn test_redgifs_thumbnail_fallback_from_id() {
         let json = r#"{
@@ -178,7 +404,8 @@ mod tests {
 
         let v: serde_json::Value = serde_json::from_str(json).expect("valid redgifs json");
         let source_url = "https://www.redgifs.com/watch/unrulygleamingalaskanmalamute";
-        let info = extract_media_info_from_value(&v, source_url).expect("should parse redgifs json");
+        let info =
+            extract_media_info_from_value(&v, source_url, ThumbnailQuality::Best).expect("should parse redgifs json");
 
         assert!(!info.thumbnail.is_empty(), "Thumbnail should be constructed");
         assert!(info.thumbnail.contains("UnrulyGleamingAlaskanmalamute"));
@@ -198,7 +425,8 @@ mod tests {
 
         let source_url = "https://example.com/watch?v=123";
         let v: serde_json::Value = serde_json::from_str(json).expect("valid media json");
-        let info = extract_media_info_from_value(&v, source_url).expect("should parse media json");
+        let info =
+            extract_media_info_from_value(&v, source_url, ThumbnailQuality::Best).expect("should parse media json");
 
         assert_eq!(info.title, "Some Video");
         assert_eq!(info.uploader.as_deref(), Some("Some Channel"));
@@ -209,6 +437,81 @@ mod tests {
         assert!(info.collection_id.is_none());
     }
 
+    #[test]
+    fn test_parse_media_info_lines_multiple_entries_are_distinct() {
+        let output = concat!(
+            r#"{"id":"1","title":"First clip","thumbnail":"https://example.com/1.jpg","#,
+            r#""webpage_url":"https://x.com/user/status/123/video/1"}"#,
+            "\n",
+            r#"{"id":"2","title":"Second clip","thumbnail":"https://example.com/2.jpg"}"#,
+            "\n"
+        );
+        let source_url = "https://x.com/user/status/123";
+
+        let items = parse_media_info_lines(output, source_url, ThumbnailQuality::Best);
+        assert_eq!(items.len(), 2);
+
+        let payload = build_media_info_multi_payload(7, source_url, items.iter().map(|(_, info)| info));
+        assert_eq!(payload.media_idx, 7);
+        assert_eq!(payload.items.len(), 2);
+        assert_eq!(payload.items[0].sub_index, 0);
+        assert_eq!(payload.items[0].title, "First clip");
+        assert_eq!(payload.items[1].sub_index, 1);
+        assert_eq!(payload.items[1].title, "Second clip");
+        assert_eq!(payload.items[0].url, "https://x.com/user/status/123/video/1");
+        assert_eq!(payload.items[1].url, source_url, "no webpage_url falls back to the source URL");
+        assert_ne!(payload.items[0].thumbnail, payload.items[1].thumbnail);
+
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["mediaSourceUrl"], source_url);
+        assert_eq!(json["items"][1]["subIndex"], 1);
+        assert_eq!(json["items"][0]["url"], "https://x.com/user/status/123/video/1");
+        assert_eq!(json["items"][1]["previewUrl"], "");
+    }
+
+    #[test]
+    fn test_parse_ytdlp_json_lines_strips_bom() {
+        let output = "\u{feff}{\"title\":\"First\"}\n{\"title\":\"Second\"}\n";
+        let values = parse_ytdlp_json_lines(output);
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0]["title"], "First");
+        assert_eq!(values[1]["title"], "Second");
+    }
+
+    #[test]
+    fn test_parse_ytdlp_json_lines_skips_blank_lines() {
+        let output = "\n  \n{\"title\":\"Only\"}\n\n\r\n";
+        let values = parse_ytdlp_json_lines(output);
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0]["title"], "Only");
+    }
+
+    #[test]
+    fn test_parse_ytdlp_json_lines_pretty_printed_object() {
+        let output = "{\n  \"title\": \"Pretty\",\n  \"tags\": [\n    \"a\"\n  ]\n}\n{\"title\":\"Next\"}\n";
+        let values = parse_ytdlp_json_lines(output);
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0]["title"], "Pretty");
+        assert_eq!(values[0]["tags"][0], "a");
+        assert_eq!(values[1]["title"], "Next");
+    }
+
+    #[test]
+    fn test_parse_ytdlp_json_lines_drops_garbage() {
+        let output = "WARNING: something\n{\"title\": oops}\n{\"title\":\"Good\"}\n{\"title\":";
+        let values = parse_ytdlp_json_lines(output);
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0]["title"], "Good");
+    }
+
+    #[test]
+    fn test_parse_media_info_lines_skips_invalid_lines() {
+        let output = "not json\n\n{\"title\":\"Only\"}\n";
+        let items = parse_media_info_lines(output, "https://example.com/v", ThumbnailQuality::Best);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].1.title, "Only");
+    }
+
     #[test]
     fn test_extract_media_info_without_uploader_has_no_collection_and_uses_fallback_title() {
         let json = r#"{
@@ -217,7 +520,8 @@ mod tests {
 
         let source_url = "https://example.com/video";
         let v: serde_json::Value = serde_json::from_str(json).expect("valid minimal json");
-        let info = extract_media_info_from_value(&v, source_url).expect("should parse minimal json");
+        let info =
+            extract_media_info_from_value(&v, source_url, ThumbnailQuality::Best).expect("should parse minimal json");
 
         // Title should fall back to source URL when missing in JSON
         assert_eq!(info.title, source_url);
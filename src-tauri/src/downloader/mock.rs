@@ -0,0 +1,82 @@
+//! Offline mock mode for frontend development without yt-dlp.
+//!
+//! With `REMEDIA_MOCK=1`, media info and downloads produce synthetic data and
+//! simulated progress instead of spawning any process.
+
+use std::time::Duration;
+
+use crate::env_flag::env_flag_enabled;
+
+use super::media_info::ExtractedMediaInfo;
+
+/// Environment variable enabling mock mode ("1" or "true")
+pub const MOCK_ENV_VAR: &str = "REMEDIA_MOCK";
+
+/// How long a simulated download takes from 0% to 100%
+pub const MOCK_DOWNLOAD_DURATION: Duration = Duration::from_secs(3);
+
+/// Number of progress increments emitted by a simulated download
+pub const MOCK_PROGRESS_STEPS: u32 = 20;
+
+/// Inline SVG placeholder so mock thumbnails render offline
+const MOCK_THUMBNAIL: &str = "data:image/svg+xml;utf8,<svg xmlns='http://www.w3.org/2000/svg' width='320' \
+height='180'><rect width='100%' height='100%' fill='%23444'/></svg>";
+
+/// Whether mock mode is enabled (read once per process)
+pub fn is_mock_mode() -> bool {
+    env_flag_enabled(MOCK_ENV_VAR)
+}
+
+/// Evenly spaced progress percentages from 0 to 100 inclusive (`steps + 1` values)
+pub fn synthetic_progress(steps: u32) -> Vec<f64> {
+    let steps = steps.max(1);
+    (0..=steps).map(|i| f64::from(i) * 100.0 / f64::from(steps)).collect()
+}
+
+/// Delay between simulated progress updates
+pub fn mock_step_interval(steps: u32) -> Duration {
+    MOCK_DOWNLOAD_DURATION / steps.max(1)
+}
+
+/// Synthetic media info for a URL
+pub fn mock_media_info(media_source_url: &str) -> ExtractedMediaInfo {
+    ExtractedMediaInfo {
+        title: format!("Mock media ({})", media_source_url),
+        thumbnail: MOCK_THUMBNAIL.to_string(),
+        preview_url: String::new(),
+        uploader: Some("ReMedia Mock".to_string()),
+        webpage_url: None,
+        collection_id: None,
+        collection_kind: None,
+        collection_name: None,
+        folder_slug: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_progress_runs_zero_to_hundred() {
+        let progress = synthetic_progress(4);
+        assert_eq!(progress, vec![0.0, 25.0, 50.0, 75.0, 100.0]);
+
+        let progress = synthetic_progress(MOCK_PROGRESS_STEPS);
+        assert_eq!(progress.len(), MOCK_PROGRESS_STEPS as usize + 1);
+        assert_eq!(progress.first(), Some(&0.0));
+        assert_eq!(progress.last(), Some(&100.0));
+        assert!(progress.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_synthetic_progress_zero_steps() {
+        assert_eq!(synthetic_progress(0), vec![0.0, 100.0]);
+        assert_eq!(mock_step_interval(0), MOCK_DOWNLOAD_DURATION);
+    }
+
+    #[test]
+    fn test_mock_step_interval_spans_duration() {
+        assert_eq!(mock_step_interval(MOCK_PROGRESS_STEPS) * MOCK_PROGRESS_STEPS, MOCK_DOWNLOAD_DURATION);
+    }
+}
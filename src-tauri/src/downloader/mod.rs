@@ -4,46 +4,121 @@
 //! Tauri commands for the frontend.
 //!
 //! ## Module Structure
+//! - `archive` - Download archive reading for playlist resume
+//! - `autotune` - Experimental throughput-based concurrency tuning
 //! - `commands` - Tauri command handlers
 //! - `events` - Event emission helpers
+//! - `health` - Download subsystem health reporting
+//! - `import` - URL list import from text/CSV files
 //! - `media_info` - Media metadata extraction
+//! - `mock` - Offline mock mode (`REMEDIA_MOCK`) for frontend development
+//! - `orphans` - Leftover yt-dlp process cleanup (`REMEDIA_ORPHAN_CLEANUP`)
+//! - `paths` - Platform path normalization (WSL, `~`)
 //! - `playlist` - Playlist/channel URL expansion
 //! - `progress` - Progress message parsing
+//! - `queue_flush` - Debounce for queue persistence writes
+//! - `resume` - Resumable partial download detection
 //! - `settings` - Download settings validation
 //! - `subprocess` - yt-dlp process management
+//! - `verify` - Optional post-download verification
 //! - `ytdlp` - Low-level yt-dlp execution
 
 // Public modules for Tauri command re-exports (macros generate __cmd__ functions)
 pub mod commands;
 
+mod archive;
+mod autotune;
 mod events;
+mod health;
+mod import;
+mod media_batch;
 mod media_info;
+mod mock;
+mod orphans;
+mod paths;
 mod playlist;
+mod preferences;
 mod progress;
+mod queue_flush;
+mod resume;
 mod settings;
 mod subprocess;
+mod verify;
 mod ytdlp;
 
 // Re-exports for external consumers
-pub use playlist::{PlaylistExpansion, PlaylistItem};
-pub use settings::DownloadSettings;
+pub use playlist::{CollectionMeta, PlaylistExpansion, PlaylistItem};
+pub use preferences::default_output_location;
+pub use progress::ProgressSample;
+pub use health::{HealthReport, ReachResult};
+pub use import::UrlImport;
+pub use settings::{BatchItem, BatchValidation, DownloadSettings, SettingsSummary};
+pub use subprocess::{registered_downloads, shutdown_downloads, CancelReason};
+pub use ytdlp::UpdateStatus;
 
 use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use serde_json::json;
 use tauri::async_runtime::spawn;
-use tauri::{AppHandle, Emitter, Manager};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
 
 use crate::download_queue::with_queue;
 use crate::events::*;
-use crate::logging::{ErrorCategory, log_error_simple, log_error_with_context, log_info_simple};
+use crate::logging::{ErrorCategory, log_error_simple, log_error_with_context, log_info_simple, log_warning_simple};
 use crate::remote_control::broadcast_remote_event;
 
+use mock::is_mock_mode;
+use paths::prepare_output_dir;
 use subprocess::execute_download;
 
 // Queue pump infrastructure
 static QUEUE_NOTIFY: std::sync::LazyLock<tokio::sync::Notify> = std::sync::LazyLock::new(tokio::sync::Notify::new);
 static QUEUE_APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+static QUEUE_PUMP_ALIVE: AtomicBool = AtomicBool::new(false);
+
+/// Whether the queue pump worker is currently running.
+/// Becomes false while the supervisor is restarting the worker and stays false
+/// once the maximum restart attempts are exhausted (degraded mode).
+pub fn is_queue_pump_alive() -> bool {
+    QUEUE_PUMP_ALIVE.load(Ordering::Relaxed)
+}
+
+/// Assume ffmpeg is present until the startup probe says otherwise
+static FFMPEG_AVAILABLE: AtomicBool = AtomicBool::new(true);
+
+/// Whether ffmpeg was found on PATH at startup.
+/// When false, downloads run as if `avoid_postprocessing` were set.
+pub fn is_ffmpeg_available() -> bool {
+    FFMPEG_AVAILABLE.load(Ordering::Relaxed)
+}
+
+/// Probe for ffmpeg in the background and emit `EVT_FFMPEG_MISSING` if it is absent.
+pub fn start_ffmpeg_probe(app: AppHandle) {
+    spawn(async move {
+        match ytdlp::probe_ffmpeg().await {
+            Some(version) => {
+                FFMPEG_AVAILABLE.store(true, Ordering::Relaxed);
+                log_info_simple(&app, ErrorCategory::System, &format!("ffmpeg {} found", version));
+            }
+            None => {
+                FFMPEG_AVAILABLE.store(false, Ordering::Relaxed);
+                log_warning_simple(
+                    &app,
+                    ErrorCategory::System,
+                    "ffmpeg not found on PATH; merging, remuxing and embedding are disabled",
+                );
+                if let Err(e) = app.emit(EVT_FFMPEG_MISSING, ()) {
+                    eprintln!("Failed to emit ffmpeg-missing: {}", e);
+                }
+                broadcast_remote_event(EVT_FFMPEG_MISSING, json!(null));
+            }
+        }
+    });
+}
+
+/// How long to wait for active downloads to stop when the app exits
+pub const SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(2);
 
 /// Signal the queue pump to check for available work.
 /// Call this after enqueue, capacity change, or download completion.
@@ -74,6 +149,7 @@ pub fn start_queue_pump(app: AppHandle) -> Result<(), String> {
             // Worker task runs the actual pump loop
             let app_clone = supervisor_app.clone();
             let worker = spawn(async move {
+                QUEUE_PUMP_ALIVE.store(true, Ordering::Relaxed);
                 log_info_simple(&app_clone, ErrorCategory::Unknown, "Download queue pump running");
                 loop {
                     QUEUE_NOTIFY.notified().await;
@@ -82,7 +158,9 @@ pub fn start_queue_pump(app: AppHandle) -> Result<(), String> {
             });
 
             // Await worker termination
-            match worker.await {
+            let worker_result = worker.await;
+            QUEUE_PUMP_ALIVE.store(false, Ordering::Relaxed);
+            match worker_result {
                 Ok(()) => {
                     // Worker returned normally (unexpected)
                     log_error_with_context(
@@ -141,23 +219,66 @@ pub fn start_queue_pump(app: AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+/// Label of the window that normally receives download events
+const MAIN_WINDOW_LABEL: &str = "main";
+
+/// Label prefix of auxiliary preview windows, which never host the download list
+const PREVIEW_WINDOW_PREFIX: &str = "preview";
+
+/// Pick the window label to emit download events to.
+///
+/// Fallback order:
+/// 1. `main`
+/// 2. the alphabetically first non-preview window (e.g. main recreated under another label)
+/// 3. the alphabetically first window of any kind
+fn select_emit_window_label<'a>(labels: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let mut labels: Vec<&str> = labels.into_iter().collect();
+    labels.sort_unstable();
+
+    if labels.contains(&MAIN_WINDOW_LABEL) {
+        return Some(MAIN_WINDOW_LABEL);
+    }
+
+    labels.iter().find(|label| !label.starts_with(PREVIEW_WINDOW_PREFIX)).or(labels.first()).copied()
+}
+
+/// Resolve the webview window that download events are emitted to (see `select_emit_window_label`).
+/// Logs a warning when falling back to a window other than `main`.
+pub fn resolve_emit_window(app: &AppHandle) -> Option<WebviewWindow> {
+    let windows = app.webview_windows();
+    let label = select_emit_window_label(windows.keys().map(String::as_str))?;
+
+    if label != MAIN_WINDOW_LABEL {
+        log_warning_simple(
+            app,
+            ErrorCategory::System,
+            &format!("Queue pump: main window not found, falling back to window '{}'", label),
+        );
+    }
+
+    windows.get(label).cloned()
+}
+
 /// Process queue until no more capacity or items available.
 async fn pump_queue_once(app: &AppHandle) {
-    let Some(window) = app.get_webview_window("main") else {
-        log_error_simple(app, ErrorCategory::System, "Queue pump: main window not found", None);
+    // With no window at all, leave the queue untouched; the next notify retries
+    let Some(window) = resolve_emit_window(app) else {
+        log_error_simple(app, ErrorCategory::System, "Queue pump: no webview window available", None);
         return;
     };
 
     loop {
         // Pull the next download to start, if any capacity available
-        let maybe_download = with_queue(|queue| queue.next_to_start());
+        // Indices whose previous task is still winding down (e.g. a paused download) are skipped
+        let running = subprocess::registered_downloads();
+        let maybe_download = with_queue(|queue| queue.next_to_start_except(&running));
 
         let Some(queued_download) = maybe_download else {
             break; // No more capacity or no queued items
         };
 
         // Deserialize settings from JSON
-        let settings: DownloadSettings = match serde_json::from_str(&queued_download.settings) {
+        let mut settings: DownloadSettings = match serde_json::from_str(&queued_download.settings) {
             Ok(s) => s,
             Err(e) => {
                 log_error_with_context(
@@ -185,25 +306,49 @@ async fn pump_queue_once(app: &AppHandle) {
             }
         };
 
-        // Emit download-started event
-        if let Err(e) = window.emit(EVT_DOWNLOAD_STARTED, queued_download.media_idx) {
+        // Share the app-wide fragment budget among the downloads now running
+        if let Some(allowance) = with_queue(|queue| queue.fragment_allowance()) {
+            settings.cap_concurrent_fragments(allowance);
+        }
+
+        // Create the subfolder up front (none when flattening); if that fails the file lands in the
+        // output location, so say so
+        let media_idx = queued_download.media_idx;
+        let subfolder = settings.effective_subfolder(queued_download.subfolder.as_deref());
+        let (output_dir, fallback) = prepare_output_dir(&queued_download.output_location, subfolder, |dir| {
+            if is_mock_mode() { Ok(()) } else { std::fs::create_dir_all(dir) }
+        });
+        if let Some(fallback) = fallback {
+            let message = format!("Failed to create subfolder {}: {}", fallback.path, fallback.reason);
+            log_warning_simple(app, ErrorCategory::System, &message);
+            if let Err(e) = window.emit(EVT_SUBFOLDER_FALLBACK, (media_idx, &fallback)) {
+                log_error_simple(app, ErrorCategory::System, "Failed to emit subfolder-fallback", Some(&e.to_string()));
+            }
+            broadcast_remote_event(EVT_SUBFOLDER_FALLBACK, json!([media_idx, fallback]));
+        }
+
+        // Emit download-started event with the settings in effect and where the file goes,
+        // so the UI can reconcile
+        let summary = settings.summary();
+        if let Err(e) = window.emit(EVT_DOWNLOAD_STARTED, (media_idx, &summary, &output_dir)) {
             log_error_simple(app, ErrorCategory::System, "Failed to emit download-started", Some(&e.to_string()));
         }
-        broadcast_remote_event(EVT_DOWNLOAD_STARTED, json!(queued_download.media_idx));
+        broadcast_remote_event(EVT_DOWNLOAD_STARTED, json!([media_idx, summary, output_dir]));
         broadcast_remote_event(
             EVT_DOWNLOAD_EXEC,
             json!([queued_download.media_idx, queued_download.url, queued_download.output_location]),
         );
 
         // Start the download
-        execute_download(
-            window.clone(),
-            queued_download.media_idx,
-            queued_download.url,
-            queued_download.output_location,
-            queued_download.subfolder,
-            settings,
-        );
+        execute_download(window.clone(), media_idx, queued_download.url, output_dir, settings);
+    }
+
+    // Report batch outcomes once the queue has fully drained
+    if let Some(summary) = with_queue(|queue| queue.take_batch_summary()) {
+        if let Err(e) = window.emit(EVT_BATCH_SUMMARY, &summary) {
+            log_error_simple(app, ErrorCategory::System, "Failed to emit batch-summary", Some(&e.to_string()));
+        }
+        broadcast_remote_event(EVT_BATCH_SUMMARY, json!(summary));
     }
 }
 
@@ -213,6 +358,23 @@ mod tests {
     use std::process::Stdio;
     use tokio::process::Command;
 
+    #[test]
+    fn test_select_emit_window_label_prefers_main() {
+        assert_eq!(select_emit_window_label(["preview-win", "main", "settings"]), Some("main"));
+    }
+
+    #[test]
+    fn test_select_emit_window_label_falls_back_to_non_preview() {
+        assert_eq!(select_emit_window_label(["preview-win", "main-2", "another"]), Some("another"));
+        assert_eq!(select_emit_window_label(["preview-win", "main-2"]), Some("main-2"));
+    }
+
+    #[test]
+    fn test_select_emit_window_label_preview_only_or_empty() {
+        assert_eq!(select_emit_window_label(["preview-win"]), Some("preview-win"));
+        assert_eq!(select_emit_window_label(Vec::<&str>::new()), None);
+    }
+
     #[tokio::test]
     #[ignore = "Requires network access and yt-dlp installed"]
     async fn test_redgifs_integration() {
@@ -229,7 +391,7 @@ mod tests {
         #[cfg(windows)]
         cmd.creation_flags(0x08000000);
 
-        let (output, errors) = ytdlp::run_yt_dlp(&mut cmd).await.expect("Failed to run yt-dlp");
+        let (output, errors) = ytdlp::run_yt_dlp(&mut cmd, None).await.expect("Failed to run yt-dlp");
 
         if !errors.is_empty() {
             println!("yt-dlp stderr: {}", errors);
@@ -253,7 +415,8 @@ mod tests {
                 }
             };
 
-            if let Some(info) = media_info::extract_media_info_from_value(&v, url) {
+            let quality = crate::thumbnail::ThumbnailQuality::Best;
+            if let Some(info) = media_info::extract_media_info_from_value(&v, url, quality) {
                 println!(
                     "Found media: Title='{}', Thumbnail='{}', PreviewUrl='{}'",
                     info.title, info.thumbnail, info.preview_url
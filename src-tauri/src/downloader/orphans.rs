@@ -0,0 +1,182 @@
+//! Detection and cleanup of yt-dlp processes left behind by a crashed session.
+//!
+//! Disabled unless `REMEDIA_ORPHAN_CLEANUP=1`, since killing processes by name can
+//! reach ones the app didn't start. Enumeration is best-effort: `ps` on Unix, a
+//! `Win32_Process` query on Windows. Both give command lines, so only the app's own
+//! downloads are considered; a process whose command line can't be read is left alone.
+
+use std::collections::{HashMap, HashSet};
+use std::process::Stdio;
+
+use tokio::process::Command;
+
+use crate::env_flag::env_flag_enabled;
+
+/// Environment variable enabling orphan detection and cleanup ("1" or "true")
+pub const ORPHAN_CLEANUP_ENV_VAR: &str = "REMEDIA_ORPHAN_CLEANUP";
+
+/// Start of the `--progress-template` every download passes; marks a yt-dlp command line as ours
+const DOWNLOAD_ARG_MARKER: &str = "download:remedia-";
+
+/// Whether orphan cleanup is enabled (read once per process)
+pub fn is_orphan_cleanup_enabled() -> bool {
+    env_flag_enabled(ORPHAN_CLEANUP_ENV_VAR)
+}
+
+/// Windows process query printing `pid ppid command-line` rows for every yt-dlp.exe
+#[cfg(windows)]
+const WINDOWS_PROCESS_QUERY: &str = "Get-CimInstance Win32_Process | Where-Object Name -eq 'yt-dlp.exe' | \
+ForEach-Object { '{0} {1} {2}' -f $_.ProcessId, $_.ParentProcessId, $_.CommandLine }";
+
+/// One `pid ppid args` row of the process table
+struct ProcessRow<'a> {
+    pid: u32,
+    ppid: u32,
+    args: &'a str,
+}
+
+fn parse_process_row(line: &str) -> Option<ProcessRow<'_>> {
+    let (pid, rest) = line.trim().split_once(char::is_whitespace)?;
+    let rest = rest.trim_start();
+    let (ppid, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    Some(ProcessRow { pid: pid.parse().ok()?, ppid: ppid.parse().ok()?, args: args.trim() })
+}
+
+/// Orphaned download PIDs in `pid ppid args` rows (`ps -eo pid=,ppid=,args=`, or the Windows query).
+/// Only command lines carrying the app's progress template count, so yt-dlp runs started outside
+/// the app, and this session's media-info probes, are left alone. `running` downloads and their
+/// descendants are excluded: PyInstaller builds of yt-dlp run the download in a child of the
+/// tracked bootloader process, with the same command line.
+pub fn parse_orphan_pids(output: &str, running: &HashSet<u32>) -> Vec<u32> {
+    let rows: Vec<ProcessRow> = output.lines().filter_map(parse_process_row).collect();
+    let parents: HashMap<u32, u32> = rows.iter().map(|row| (row.pid, row.ppid)).collect();
+
+    rows.iter()
+        .filter(|row| runs_ytdlp(row.args) && row.args.contains(DOWNLOAD_ARG_MARKER))
+        .filter(|row| !descends_from_running(row.pid, &parents, running))
+        .map(|row| row.pid)
+        .collect()
+}
+
+/// Whether `pid` is in `running` or has an ancestor there. The walk is bounded by the table size
+/// so a PID reused as its own ancestor can't loop.
+fn descends_from_running(pid: u32, parents: &HashMap<u32, u32>, running: &HashSet<u32>) -> bool {
+    let mut current = pid;
+    for _ in 0..=parents.len() {
+        if running.contains(&current) {
+            return true;
+        }
+        match parents.get(&current) {
+            Some(&parent) if parent != current => current = parent,
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// Whether a command line runs yt-dlp, directly, as `yt-dlp.exe` (possibly quoted), or through
+/// its interpreter (`python3 /usr/bin/yt-dlp`)
+fn runs_ytdlp(args: &str) -> bool {
+    args.split_whitespace().take(2).any(|arg| {
+        let name = arg.trim_matches('"').rsplit(['/', '\\']).next().unwrap_or_default();
+        name == "yt-dlp" || name.eq_ignore_ascii_case("yt-dlp.exe")
+    })
+}
+
+/// The platform's process table as `pid ppid args` rows, or None if it could not be read
+async fn list_processes() -> Option<String> {
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut cmd = Command::new("powershell");
+        cmd.args(["-NoProfile", "-NonInteractive", "-Command", WINDOWS_PROCESS_QUERY]);
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        cmd
+    };
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut cmd = Command::new("ps");
+        cmd.args(["-eo", "pid=,ppid=,args="]);
+        cmd
+    };
+
+    let output = cmd.stdin(Stdio::null()).stderr(Stdio::null()).output().await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// yt-dlp downloads not owned by one running in this session, sorted by PID
+pub async fn find_orphans(running: &HashSet<u32>) -> Vec<u32> {
+    let Some(table) = list_processes().await else {
+        return Vec::new();
+    };
+    let mut orphans = parse_orphan_pids(&table, running);
+    orphans.sort_unstable();
+    orphans.dedup();
+    orphans
+}
+
+/// Forcefully end one process; returns whether the kill command succeeded
+pub async fn kill_process(pid: u32) -> bool {
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut cmd = Command::new("taskkill");
+        cmd.args(["/PID", &pid.to_string(), "/F"]);
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        cmd
+    };
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut cmd = Command::new("kill");
+        cmd.args(["-9", &pid.to_string()]);
+        cmd
+    };
+
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_orphan_pids_keeps_only_app_downloads() {
+        let output = "    1     0 /sbin/init
+  812     1 /usr/bin/python3 /usr/local/bin/yt-dlp https://example.com/a --progress-template \
+download:remedia-%(progress._percent_str)s-%(progress.eta)s-%(progress.speed)s --newline
+  940     1 yt-dlp -j https://example.com/b
+ 1021     1 yt-dlp https://example.com/c --progress-template download:remedia-%(progress._percent_str)s
+ 1100     1 vim notes-about-yt-dlp download:remedia-
+abc 1 yt-dlp download:remedia-
+";
+        assert_eq!(parse_orphan_pids(output, &HashSet::new()), vec![812, 1021]);
+        assert!(parse_orphan_pids("", &HashSet::new()).is_empty());
+    }
+
+    #[test]
+    fn test_parse_orphan_pids_skips_children_of_running_downloads() {
+        // PyInstaller build: bootloader 500 (tracked) runs the download in child 501
+        let output = "  500     1 /opt/yt-dlp https://example.com/a --progress-template download:remedia-%(eta)s
+  501   500 /opt/yt-dlp https://example.com/a --progress-template download:remedia-%(eta)s
+  600     1 /opt/yt-dlp https://example.com/b --progress-template download:remedia-%(eta)s
+  601   600 /opt/yt-dlp https://example.com/b --progress-template download:remedia-%(eta)s
+";
+        assert_eq!(parse_orphan_pids(output, &HashSet::from([500])), vec![600, 601]);
+    }
+
+    #[test]
+    fn test_parse_orphan_pids_windows_query_rows() {
+        let output = "4312 4100 \"C:\\Tools\\yt-dlp.exe\" https://example.com/a --progress-template download:remedia-\r
+4400 4100 \"C:\\Tools\\yt-dlp.exe\" -j https://example.com/b\r
+5120 4100 \r
+";
+        // The media-info probe (4400) and the row without a readable command line (5120) are left alone
+        assert_eq!(parse_orphan_pids(output, &HashSet::new()), vec![4312]);
+    }
+}
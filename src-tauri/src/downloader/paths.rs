@@ -0,0 +1,166 @@
+//! Platform-aware handling of user-supplied paths: WSL drive translation, `~` expansion and validation,
+//! plus resolving the directory a download writes into.
+
+use std::io;
+use std::path::{MAIN_SEPARATOR, Path};
+
+use serde::Serialize;
+
+use crate::error::DownloaderError;
+
+use super::settings::validate_output_location;
+
+/// Translate a Windows drive path (`C:\Users\me` or `C:/Users/me`) to its WSL mount (`/mnt/c/Users/me`).
+/// Anything else is returned unchanged.
+pub fn translate_wsl_path(path: &str) -> String {
+    let mut chars = path.chars();
+    let (Some(drive), Some(':')) = (chars.next(), chars.next()) else {
+        return path.to_string();
+    };
+    let rest = chars.as_str();
+    if !drive.is_ascii_alphabetic() || !(rest.is_empty() || rest.starts_with(['\\', '/'])) {
+        return path.to_string();
+    }
+
+    let rest = rest.replace('\\', "/");
+    format!("/mnt/{}{}", drive.to_ascii_lowercase(), rest.trim_end_matches('/'))
+}
+
+/// Expand a leading `~` (alone or followed by a separator) to `home`.
+/// `~user` forms and paths without a known home are left as-is.
+pub fn expand_home(path: &str, home: Option<&str>) -> String {
+    let (Some(home), Some(rest)) = (home, path.strip_prefix('~')) else {
+        return path.to_string();
+    };
+    if rest.is_empty() || rest.starts_with(['/', '\\']) {
+        format!("{}{}", home.trim_end_matches(['/', '\\']), rest)
+    } else {
+        path.to_string()
+    }
+}
+
+fn home_dir() -> Option<String> {
+    std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")).ok().filter(|home| !home.is_empty())
+}
+
+/// Normalize a path for the current platform: translate Windows drive paths under WSL,
+/// expand `~`, then validate it like an output location
+pub fn normalize_path(path: &str) -> Result<String, DownloaderError> {
+    normalize_path_for(path, is_wsl::is_wsl(), home_dir().as_deref())
+}
+
+fn normalize_path_for(path: &str, wsl: bool, home: Option<&str>) -> Result<String, DownloaderError> {
+    let path = path.trim();
+    let translated = if wsl { translate_wsl_path(path) } else { path.to_string() };
+    let expanded = expand_home(&translated, home);
+    validate_output_location(&expanded)?;
+    Ok(expanded)
+}
+
+/// Build the directory a download writes into (output location plus optional subfolder)
+pub fn resolve_output_dir(output_location: &str, subfolder: Option<&str>) -> String {
+    match subfolder {
+        Some(folder) if !folder.is_empty() => format!("{}{}{}", output_location, MAIN_SEPARATOR, folder),
+        _ => output_location.to_string(),
+    }
+}
+
+/// A subfolder that could not be created, so the download writes to the output location instead
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SubfolderFallback {
+    pub path: String,
+    pub reason: String,
+}
+
+/// Resolve and create the directory a download writes into. When the subfolder can't be created
+/// the download falls back to `output_location`, and the failure is returned so it can be surfaced.
+pub fn prepare_output_dir(
+    output_location: &str,
+    subfolder: Option<&str>,
+    create_dir: impl FnOnce(&Path) -> io::Result<()>,
+) -> (String, Option<SubfolderFallback>) {
+    let output_dir = resolve_output_dir(output_location, subfolder);
+    if output_dir == output_location {
+        return (output_dir, None);
+    }
+
+    match create_dir(Path::new(&output_dir)) {
+        Ok(()) => (output_dir, None),
+        Err(e) => (output_location.to_string(), Some(SubfolderFallback { path: output_dir, reason: e.to_string() })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::downloader::settings::DownloadSettings;
+
+    #[test]
+    fn test_prepare_output_dir_reports_subfolder_fallback() {
+        let sub = format!("/downloads{}Mix", MAIN_SEPARATOR);
+
+        // No subfolder: nothing to create
+        let (dir, fallback) = prepare_output_dir("/downloads", None, |_| panic!("nothing to create"));
+        assert_eq!((dir.as_str(), fallback), ("/downloads", None));
+
+        let (dir, fallback) = prepare_output_dir("/downloads", Some("Mix"), |_| Ok(()));
+        assert_eq!((dir, fallback), (sub.clone(), None));
+
+        let denied = |_: &Path| Err(io::Error::new(io::ErrorKind::PermissionDenied, "permission denied"));
+        let (dir, fallback) = prepare_output_dir("/downloads", Some("Mix"), denied);
+        assert_eq!(dir, "/downloads");
+        assert_eq!(fallback, Some(SubfolderFallback { path: sub, reason: "permission denied".to_string() }));
+    }
+
+    #[test]
+    fn test_flatten_output_writes_to_output_location() {
+        let mut settings = DownloadSettings::remote_defaults();
+        settings.flatten_output = true;
+
+        let subfolder = settings.effective_subfolder(Some("Mix"));
+        let (dir, fallback) = prepare_output_dir("/downloads", subfolder, |_| panic!("nothing to create"));
+        assert_eq!((dir.as_str(), fallback), ("/downloads", None));
+
+        settings.flatten_output = false;
+        assert_eq!(settings.effective_subfolder(Some("Mix")), Some("Mix"));
+    }
+
+    #[test]
+    fn test_resolve_output_dir() {
+        assert_eq!(resolve_output_dir("/downloads", None), "/downloads");
+        assert_eq!(resolve_output_dir("/downloads", Some("")), "/downloads");
+        assert_eq!(
+            resolve_output_dir("/downloads", Some("My Playlist")),
+            format!("/downloads{}My Playlist", MAIN_SEPARATOR)
+        );
+    }
+
+    #[test]
+    fn test_expand_home() {
+        assert_eq!(expand_home("~", Some("/home/me")), "/home/me");
+        assert_eq!(expand_home("~/Videos", Some("/home/me/")), "/home/me/Videos");
+        assert_eq!(expand_home("~\\Videos", Some("C:\\Users\\me")), "C:\\Users\\me\\Videos");
+        assert_eq!(expand_home("~other/Videos", Some("/home/me")), "~other/Videos");
+        assert_eq!(expand_home("/srv/~/x", Some("/home/me")), "/srv/~/x");
+        assert_eq!(expand_home("~/Videos", None), "~/Videos");
+    }
+
+    #[test]
+    fn test_translate_wsl_path() {
+        assert_eq!(translate_wsl_path("C:\\Users\\me\\Videos"), "/mnt/c/Users/me/Videos");
+        assert_eq!(translate_wsl_path("d:/Media/"), "/mnt/d/Media");
+        assert_eq!(translate_wsl_path("E:"), "/mnt/e");
+        assert_eq!(translate_wsl_path("/home/me"), "/home/me");
+        assert_eq!(translate_wsl_path("C:relative"), "C:relative");
+        assert_eq!(translate_wsl_path("\\\\server\\share"), "\\\\server\\share");
+    }
+
+    #[test]
+    fn test_normalize_path_for_platform() {
+        assert_eq!(normalize_path_for(" C:\\Users\\me ", true, None).unwrap(), "/mnt/c/Users/me");
+        assert_eq!(normalize_path_for("C:\\Users\\me", false, None).unwrap(), "C:\\Users\\me");
+        assert_eq!(normalize_path_for("~/Videos", false, Some("/home/me")).unwrap(), "/home/me/Videos");
+        assert!(normalize_path_for("", false, None).is_err());
+        assert!(normalize_path_for("/tmp/bad\0name", false, None).is_err());
+    }
+}
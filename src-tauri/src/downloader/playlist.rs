@@ -1,13 +1,114 @@
 //! Playlist expansion and parsing for yt-dlp output.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio_util::sync::CancellationToken;
 
 /// Safety cap for playlist expansion to avoid unbounded queue growth
 pub const MAX_PLAYLIST_ITEMS: usize = 500;
 
+/// Prefix of the `--print` line yt-dlp writes per flat entry, ahead of the final `-J` document
+pub const EXPAND_ENTRY_MARKER: &str = "remedia-entry:";
+
+/// Fields printed as JSON after each entry marker: enough to build the item, plus the playlist's
+/// name and uploader for when `--break-on-reject` stops yt-dlp before its `-J` document
+pub const EXPAND_ENTRY_FIELDS: &str = "%(.{id,url,webpage_url,title,extractor,playlist_title,playlist_uploader})j";
+
+/// Minimum gap between `playlist-expand-progress` events
+pub const EXPAND_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Environment variable overriding how many playlist expansions may run at once
+pub const MAX_PLAYLIST_EXPANSIONS_ENV_VAR: &str = "REMEDIA_MAX_PLAYLIST_EXPANSIONS";
+
+/// Concurrent playlist expansions allowed when the env var is unset or invalid
+pub const DEFAULT_MAX_PLAYLIST_EXPANSIONS: usize = 2;
+
+static EXPANSION_PERMITS: LazyLock<Semaphore> = LazyLock::new(|| {
+    let limit = parse_max_playlist_expansions(std::env::var(MAX_PLAYLIST_EXPANSIONS_ENV_VAR).ok().as_deref());
+    Semaphore::new(limit)
+});
+
+/// Cancellation tokens of running expansions by source URL, each tagged with its registration id
+static EXPANSION_TOKENS: LazyLock<Mutex<HashMap<String, (u64, CancellationToken)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_EXPANSION_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Track a running expansion of `url` and return its registration id and cancellation token.
+/// A second expansion of the same URL takes over the entry.
+pub fn register_expansion(url: &str) -> (u64, CancellationToken) {
+    let id = NEXT_EXPANSION_ID.fetch_add(1, Ordering::Relaxed);
+    let token = CancellationToken::new();
+    EXPANSION_TOKENS.lock().unwrap_or_else(|e| e.into_inner()).insert(url.to_string(), (id, token.clone()));
+    (id, token)
+}
+
+/// Stop tracking a finished expansion, unless a newer one of the same URL has taken its place
+pub fn finish_expansion(url: &str, id: u64) {
+    let mut tokens = EXPANSION_TOKENS.lock().unwrap_or_else(|e| e.into_inner());
+    if tokens.get(url).is_some_and(|(current, _)| *current == id) {
+        tokens.remove(url);
+    }
+}
+
+/// Cancel the running expansion of `url`; returns false if none was running
+pub fn cancel_expansion(url: &str) -> bool {
+    let entry = EXPANSION_TOKENS.lock().unwrap_or_else(|e| e.into_inner()).remove(url);
+    entry.map(|(_, token)| token.cancel()).is_some()
+}
+
+/// Parse the expansion limit, falling back to the default for missing, zero or non-numeric values
+pub fn parse_max_playlist_expansions(value: Option<&str>) -> usize {
+    value
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_MAX_PLAYLIST_EXPANSIONS)
+}
+
+/// Wait for a free playlist expansion slot; the slot is released when the permit drops
+pub async fn acquire_expansion_permit() -> SemaphorePermit<'static> {
+    EXPANSION_PERMITS.acquire().await.expect("expansion semaphore is never closed")
+}
+
+/// Counts entry marker lines while a playlist is expanding and throttles progress reports
+#[derive(Debug, Default)]
+pub struct ExpansionProgress {
+    entries_seen: usize,
+    last_emit: Option<Instant>,
+}
+
+impl ExpansionProgress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count `line` if it is an entry marker.
+    /// Returns the running total when a progress event is due (the first entry, then at most every interval).
+    pub fn observe(&mut self, line: &str, now: Instant) -> Option<usize> {
+        if !line.starts_with(EXPAND_ENTRY_MARKER) {
+            return None;
+        }
+        self.entries_seen += 1;
+
+        let due = self.last_emit.is_none_or(|last| now.duration_since(last) >= EXPAND_PROGRESS_INTERVAL);
+        if !due {
+            return None;
+        }
+        self.last_emit = Some(now);
+        Some(self.entries_seen)
+    }
+
+    pub fn entries_seen(&self) -> usize {
+        self.entries_seen
+    }
+}
+
 /// A single item in a playlist
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -15,6 +116,9 @@ pub struct PlaylistItem {
     pub url: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    /// Extractor video ID, used to match download archive entries
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
 }
 
 /// Result from expanding a playlist URL
@@ -35,9 +139,73 @@ pub struct PlaylistExpansion {
     pub collection_name: Option<String>,
     /// Filesystem-friendly folder slug
     pub folder_slug: Option<String>,
+    /// Total items reported by yt-dlp (`playlist_count`), which may exceed `entries`
+    /// when the listing was capped at `MAX_PLAYLIST_ITEMS`
+    pub total_count: Option<usize>,
+    /// The URL resolved to a single video (yt-dlp JSON had no `entries` key);
+    /// the frontend should fall back to `get_media_info` instead
+    #[serde(default)]
+    pub single_video: bool,
+}
+
+impl PlaylistExpansion {
+    /// Whether the URL was a single video rather than a (possibly empty) playlist
+    pub fn is_single_video(&self) -> bool {
+        self.single_video && self.entries.is_empty()
+    }
+}
+
+/// Collection naming metadata shared by playlist expansion and folder previews
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionMeta {
+    /// Canonical collection identifier (e.g. "playlist:My Playlist")
+    pub collection_id: Option<String>,
+    /// Collection kind: "playlist" or "channel"
+    pub collection_kind: Option<String>,
+    /// Human-readable collection name
+    pub collection_name: Option<String>,
+    /// Filesystem-friendly folder slug
+    pub folder_slug: Option<String>,
 }
 
-/// Sanitize a string for use as a folder name (Windows-safe)
+/// Build collection metadata from already-sanitized names.
+/// Prefers the playlist name and falls back to the uploader (channel).
+pub fn build_collection_meta(playlist_name: Option<&str>, uploader: Option<&str>) -> CollectionMeta {
+    let (kind, name) = match (playlist_name, uploader) {
+        (Some(name), _) => ("playlist", name),
+        (None, Some(name)) => ("channel", name),
+        (None, None) => return CollectionMeta::default(),
+    };
+
+    CollectionMeta {
+        collection_id: Some(format!("{}:{}", kind, name)),
+        collection_kind: Some(kind.to_string()),
+        collection_name: Some(name.to_string()),
+        folder_slug: Some(name.to_string()),
+    }
+}
+
+/// Compute collection metadata for a raw collection name and kind ("playlist" or "channel").
+/// Lets the frontend precompute the same folder naming used by playlist expansion.
+pub fn compute_collection_meta(name: &str, kind: &str) -> Result<CollectionMeta, String> {
+    let sanitized = sanitize_folder_name(name);
+    if sanitized.is_empty() {
+        return Err("Collection name cannot be empty".to_string());
+    }
+
+    match kind {
+        "playlist" => Ok(build_collection_meta(Some(&sanitized), None)),
+        "channel" => Ok(build_collection_meta(None, Some(&sanitized))),
+        _ => Err(format!("Invalid collection kind: {}", kind)),
+    }
+}
+
+/// Sanitize a string for use as a folder name (Windows-safe).
+///
+/// Characters Windows forbids in paths (`/ \ : * ? " < > |`) become `_`, and surrounding
+/// whitespace is trimmed. Playlist and channel folders are named with this, and the
+/// `preview_folder_name` command exposes it so the UI shows the exact folder name.
 pub fn sanitize_folder_name(name: &str) -> String {
     name.chars()
         .map(|c| match c {
@@ -80,7 +248,15 @@ fn normalize_playlist_entry(entry: &Value) -> Option<PlaylistItem> {
 
     let title = entry.get("title").and_then(|t| t.as_str()).filter(|s| !s.is_empty()).map(|s| s.to_string());
 
-    Some(PlaylistItem { url, title })
+    let id = entry.get("id").and_then(|v| v.as_str()).filter(|s| !s.is_empty()).map(|s| s.to_string());
+
+    Some(PlaylistItem { url, title, id })
+}
+
+/// Whether yt-dlp `-J --flat-playlist` JSON describes a playlist (has an `entries` array)
+pub fn json_is_playlist(json_str: &str) -> Result<bool, String> {
+    let v: Value = serde_json::from_str(json_str).map_err(|e| format!("Failed to parse yt-dlp JSON: {}", e))?;
+    Ok(v.get("entries").is_some_and(Value::is_array))
 }
 
 /// Parse yt-dlp `-J --flat-playlist` JSON into playlist expansion with metadata
@@ -103,6 +279,8 @@ pub fn parse_playlist_expansion(json_str: &str) -> Result<PlaylistExpansion, Str
         .filter(|s| !s.is_empty())
         .map(sanitize_folder_name);
 
+    let total_count = v.get("playlist_count").and_then(|c| c.as_u64()).map(|c| c as usize);
+
     let entries = match v.get("entries").and_then(|e| e.as_array()) {
         Some(e) => e,
         None => {
@@ -115,10 +293,38 @@ pub fn parse_playlist_expansion(json_str: &str) -> Result<PlaylistExpansion, Str
                 collection_kind: None,
                 collection_name: None,
                 folder_slug: None,
+                total_count,
+                single_video: true,
             });
         }
     };
 
+    Ok(playlist_expansion(playlist_name, uploader, collect_playlist_items(entries), total_count))
+}
+
+/// Build an expansion from the entry marker lines alone, for when `--break-on-reject` stopped
+/// yt-dlp at the first item outside the date filter, before it printed the `-J` document.
+/// Lines that aren't entry markers are ignored.
+pub fn parse_expansion_entry_lines<S: AsRef<str>>(lines: &[S]) -> PlaylistExpansion {
+    let entries: Vec<Value> = lines
+        .iter()
+        .filter_map(|line| line.as_ref().strip_prefix(EXPAND_ENTRY_MARKER))
+        .filter_map(|json| serde_json::from_str(json).ok())
+        .collect();
+
+    let first_field = |key: &str| {
+        entries
+            .iter()
+            .find_map(|entry| entry.get(key).and_then(Value::as_str).filter(|s| !s.is_empty()))
+            .map(sanitize_folder_name)
+    };
+    let (playlist_name, uploader) = (first_field("playlist_title"), first_field("playlist_uploader"));
+
+    playlist_expansion(playlist_name, uploader, collect_playlist_items(&entries), None)
+}
+
+/// Normalized entries, deduplicated by URL and capped at `MAX_PLAYLIST_ITEMS`
+fn collect_playlist_items<'a>(entries: impl IntoIterator<Item = &'a Value>) -> Vec<PlaylistItem> {
     let mut seen = HashSet::new();
     let mut items = Vec::new();
 
@@ -136,36 +342,70 @@ pub fn parse_playlist_expansion(json_str: &str) -> Result<PlaylistExpansion, Str
             break;
         }
     }
+    items
+}
 
-    let (collection_kind, collection_name, folder_slug, collection_id) = if let Some(ref name) = playlist_name {
-        let kind = "playlist".to_string();
-        let slug = name.clone();
-        let id = format!("{}:{}", kind, name);
-        (Some(kind), Some(name.clone()), Some(slug), Some(id))
-    } else if let Some(ref name) = uploader {
-        let kind = "channel".to_string();
-        let slug = name.clone();
-        let id = format!("{}:{}", kind, name);
-        (Some(kind), Some(name.clone()), Some(slug), Some(id))
-    } else {
-        (None, None, None, None)
-    };
+fn playlist_expansion(
+    playlist_name: Option<String>,
+    uploader: Option<String>,
+    entries: Vec<PlaylistItem>,
+    total_count: Option<usize>,
+) -> PlaylistExpansion {
+    let meta = build_collection_meta(playlist_name.as_deref(), uploader.as_deref());
 
-    Ok(PlaylistExpansion {
+    PlaylistExpansion {
         playlist_name,
         uploader,
-        entries: items,
-        collection_id,
-        collection_kind,
-        collection_name,
-        folder_slug,
-    })
+        entries,
+        collection_id: meta.collection_id,
+        collection_kind: meta.collection_kind,
+        collection_name: meta.collection_name,
+        folder_slug: meta.folder_slug,
+        total_count,
+        single_video: false,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_expansion_progress_counts_entries_and_throttles() {
+        let start = Instant::now();
+        let mut progress = ExpansionProgress::new();
+
+        // Only marker lines count; the final JSON document does not
+        assert_eq!(progress.observe("{\"_type\":\"playlist\"}", start), None);
+        assert_eq!(progress.observe("remedia-entry:abc", start), Some(1));
+        assert_eq!(progress.observe("remedia-entry:def", start + Duration::from_millis(100)), None);
+        assert_eq!(progress.observe("remedia-entry:ghi", start + Duration::from_millis(200)), None);
+        assert_eq!(progress.observe("remedia-entry:jkl", start + EXPAND_PROGRESS_INTERVAL), Some(4));
+        assert_eq!(progress.entries_seen(), 4);
+    }
+
+    #[test]
+    fn test_parse_expansion_entry_lines_without_final_document() {
+        let lines = [
+            r#"remedia-entry:{"id":"new1","extractor":"Youtube","title":"Newest","playlist_title":"Uploads"}"#,
+            r#"remedia-entry:{"id":"new2","url":"https://www.youtube.com/watch?v=new2","playlist_uploader":"Chan"}"#,
+            r#"remedia-entry:{"id":"new1","extractor":"Youtube"}"#,
+            "remedia-entry:not json",
+            "WARNING: something",
+        ];
+
+        let expansion = parse_expansion_entry_lines(&lines);
+        assert!(!expansion.single_video);
+        assert_eq!(expansion.playlist_name.as_deref(), Some("Uploads"));
+        assert_eq!(expansion.uploader.as_deref(), Some("Chan"));
+        let urls: Vec<&str> = expansion.entries.iter().map(|e| e.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://www.youtube.com/watch?v=new1", "https://www.youtube.com/watch?v=new2"]);
+        assert_eq!(expansion.entries[0].title.as_deref(), Some("Newest"));
+
+        // The date filter rejected the very first entry
+        assert!(parse_expansion_entry_lines::<&str>(&[]).entries.is_empty());
+    }
+
     #[test]
     fn test_parse_playlist_expansion_constructs_urls() {
         let json = r#"{
@@ -267,6 +507,94 @@ mod tests {
         assert_eq!(expansion.entries.len(), MAX_PLAYLIST_ITEMS);
     }
 
+    #[test]
+    fn test_parse_max_playlist_expansions() {
+        assert_eq!(parse_max_playlist_expansions(None), DEFAULT_MAX_PLAYLIST_EXPANSIONS);
+        assert_eq!(parse_max_playlist_expansions(Some(" 5 ")), 5);
+        assert_eq!(parse_max_playlist_expansions(Some("0")), DEFAULT_MAX_PLAYLIST_EXPANSIONS);
+        assert_eq!(parse_max_playlist_expansions(Some("many")), DEFAULT_MAX_PLAYLIST_EXPANSIONS);
+    }
+
+    #[tokio::test]
+    async fn test_expansion_permit_is_released_on_drop() {
+        let available = EXPANSION_PERMITS.available_permits();
+        assert!(available > 0);
+
+        let permit = acquire_expansion_permit().await;
+        assert_eq!(EXPANSION_PERMITS.available_permits(), available - 1);
+
+        drop(permit);
+        assert_eq!(EXPANSION_PERMITS.available_permits(), available);
+    }
+
+    #[test]
+    fn test_cancel_expansion_reaches_only_the_current_registration() {
+        let url = "https://example.com/playlist?list=cancel-test";
+        let (old_id, old_token) = register_expansion(url);
+        let (new_id, new_token) = register_expansion(url);
+
+        // The older expansion finishing must not untrack the newer one
+        finish_expansion(url, old_id);
+        assert!(cancel_expansion(url));
+        assert!(new_token.is_cancelled());
+        assert!(!old_token.is_cancelled());
+
+        assert!(!cancel_expansion(url), "nothing left to cancel");
+        finish_expansion(url, new_id);
+    }
+
+    #[test]
+    fn test_is_single_video_distinguishes_empty_playlist() {
+        let single = parse_playlist_expansion(r#"{"_type":"video","id":"abc","title":"Single Video"}"#).unwrap();
+        assert!(single.is_single_video());
+
+        let empty = parse_playlist_expansion(r#"{"_type":"playlist","title":"P","entries":[]}"#).unwrap();
+        assert!(empty.entries.is_empty());
+        assert!(!empty.is_single_video());
+
+        // Entries that all fail to normalize still come from a playlist
+        let unusable = parse_playlist_expansion(r#"{"_type":"playlist","entries":[{"title":"no url"}]}"#).unwrap();
+        assert!(!unusable.is_single_video());
+    }
+
+    #[test]
+    fn test_json_is_playlist() {
+        let playlist = r#"{"_type":"playlist","title":"P","entries":[{"id":"a","url":"https://example.com/a"}]}"#;
+        assert_eq!(json_is_playlist(playlist), Ok(true));
+
+        let empty_playlist = r#"{"_type":"playlist","entries":[]}"#;
+        assert_eq!(json_is_playlist(empty_playlist), Ok(true));
+
+        let single = r#"{"_type":"video","id":"abc","title":"Single Video"}"#;
+        assert_eq!(json_is_playlist(single), Ok(false));
+
+        assert!(json_is_playlist("not json").is_err());
+    }
+
+    #[test]
+    fn test_parse_playlist_expansion_total_count_from_playlist_count() {
+        let json = r#"{
+            "_type":"playlist",
+            "title":"Big Playlist",
+            "playlist_count":1200,
+            "entries":[{"id":"a","webpage_url":"https://example.com/a"}]
+        }"#;
+
+        let expansion = parse_playlist_expansion(json).expect("should parse playlist JSON");
+        assert_eq!(expansion.entries.len(), 1);
+        assert_eq!(expansion.total_count, Some(1200));
+    }
+
+    #[test]
+    fn test_parse_playlist_expansion_total_count_absent() {
+        let json = r#"{"_type":"playlist","entries":[{"id":"a","webpage_url":"https://example.com/a"}]}"#;
+        let expansion = parse_playlist_expansion(json).expect("should parse playlist JSON");
+        assert_eq!(expansion.total_count, None);
+
+        let single = parse_playlist_expansion(r#"{"title":"Single Video"}"#).unwrap();
+        assert_eq!(single.total_count, None);
+    }
+
     #[test]
     fn test_parse_playlist_expansion_non_playlist_has_no_collection_metadata() {
         let json = r#"{
@@ -278,6 +606,7 @@ mod tests {
 
         // No entries for non-playlist input
         assert_eq!(expansion.entries.len(), 0);
+        assert!(expansion.is_single_video());
         // playlist_name and uploader may be populated, but collection metadata should be None
         assert_eq!(expansion.collection_kind, None);
         assert_eq!(expansion.collection_name, None);
@@ -285,6 +614,48 @@ mod tests {
         assert_eq!(expansion.collection_id, None);
     }
 
+    #[test]
+    fn test_build_collection_meta_matches_parser_expectations() {
+        let meta = build_collection_meta(Some("My Playlist"), Some("TestChannel"));
+        assert_eq!(meta.collection_kind.as_deref(), Some("playlist"));
+        assert_eq!(meta.collection_name.as_deref(), Some("My Playlist"));
+        assert_eq!(meta.folder_slug.as_deref(), Some("My Playlist"));
+        assert_eq!(meta.collection_id.as_deref(), Some("playlist:My Playlist"));
+
+        let meta = build_collection_meta(None, Some("TestChannel"));
+        assert_eq!(meta.collection_kind.as_deref(), Some("channel"));
+        assert_eq!(meta.collection_id.as_deref(), Some("channel:TestChannel"));
+
+        assert_eq!(build_collection_meta(None, None), CollectionMeta::default());
+    }
+
+    #[test]
+    fn test_compute_collection_meta_sanitizes_name() {
+        let meta = compute_collection_meta("Best of 2024 | Top Picks", "playlist").expect("valid playlist");
+        assert_eq!(meta.folder_slug.as_deref(), Some("Best of 2024 _ Top Picks"));
+        assert_eq!(meta.collection_id.as_deref(), Some("playlist:Best of 2024 _ Top Picks"));
+
+        let meta = compute_collection_meta("  Some/Channel ", "channel").expect("valid channel");
+        assert_eq!(meta.collection_kind.as_deref(), Some("channel"));
+        assert_eq!(meta.folder_slug.as_deref(), Some("Some_Channel"));
+    }
+
+    #[test]
+    fn test_compute_collection_meta_matches_expansion() {
+        let json = r#"{"_type":"playlist","title":"Mix: Vol 1","entries":[{"id":"a","extractor":"Youtube"}]}"#;
+        let expansion = parse_playlist_expansion(json).expect("should parse playlist JSON");
+        let meta = compute_collection_meta("Mix: Vol 1", "playlist").expect("valid playlist");
+
+        assert_eq!(meta.collection_id, expansion.collection_id);
+        assert_eq!(meta.folder_slug, expansion.folder_slug);
+    }
+
+    #[test]
+    fn test_compute_collection_meta_rejects_invalid_input() {
+        assert!(compute_collection_meta("   ", "playlist").is_err());
+        assert!(compute_collection_meta("Name", "single").is_err());
+    }
+
     #[test]
     fn test_sanitize_folder_name() {
         assert_eq!(sanitize_folder_name("Normal Name"), "Normal Name");
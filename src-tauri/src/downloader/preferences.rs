@@ -0,0 +1,92 @@
+//! App preferences persisted as JSON under the config directory.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+
+use super::settings::validate_output_location;
+
+/// Preferences file, relative to the app config directory
+pub const PREFERENCES_RELATIVE_PATH: &str = "preferences.json";
+
+/// Persisted preferences. Unknown or missing fields fall back to defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Preferences {
+    /// Used when a download request carries no output location
+    pub default_output_location: Option<String>,
+}
+
+/// Load preferences from `path`. A missing or unreadable file yields the defaults.
+pub fn load_preferences(path: &Path) -> Preferences {
+    std::fs::read_to_string(path).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+/// Write preferences to `path`, creating the parent directory if needed
+pub fn save_preferences(path: &Path, prefs: &Preferences) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(prefs).map_err(std::io::Error::other)?;
+    std::fs::write(path, json)
+}
+
+/// Validate and store `location` as the default output location in the preferences at `path`
+pub fn store_default_output_location(path: &Path, location: &str) -> Result<(), String> {
+    validate_output_location(location).map_err(|e| e.to_string())?;
+
+    let mut prefs = load_preferences(path);
+    prefs.default_output_location = Some(location.to_string());
+    save_preferences(path, &prefs).map_err(|e| format!("Failed to save preferences: {}", e))
+}
+
+pub(crate) fn resolve_preferences_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().resolve(PREFERENCES_RELATIVE_PATH, BaseDirectory::Config).ok()
+}
+
+/// The persisted default output location, if one has been set
+pub fn default_output_location(app: &AppHandle) -> Option<String> {
+    resolve_preferences_path(app).and_then(|path| load_preferences(&path).default_output_location)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn test_default_output_location_round_trip() {
+        let dir = TestDir::new("prefs");
+        let path = dir.join("nested").join(PREFERENCES_RELATIVE_PATH);
+
+        assert_eq!(load_preferences(&path), Preferences::default());
+
+        store_default_output_location(&path, "/home/user/Videos").unwrap();
+        assert_eq!(load_preferences(&path).default_output_location.as_deref(), Some("/home/user/Videos"));
+
+        store_default_output_location(&path, "/mnt/media").unwrap();
+        assert_eq!(load_preferences(&path).default_output_location.as_deref(), Some("/mnt/media"));
+    }
+
+    #[test]
+    fn test_store_default_output_location_rejects_invalid() {
+        let dir = TestDir::new("prefs");
+        let path = dir.join(PREFERENCES_RELATIVE_PATH);
+        store_default_output_location(&path, "/home/user/Videos").unwrap();
+
+        assert!(store_default_output_location(&path, "   ").is_err());
+        assert!(store_default_output_location(&path, &"a".repeat(5000)).is_err());
+        // A rejected value leaves the stored one untouched
+        assert_eq!(load_preferences(&path).default_output_location.as_deref(), Some("/home/user/Videos"));
+    }
+
+    #[test]
+    fn test_load_preferences_ignores_corrupt_file() {
+        let dir = TestDir::new("prefs");
+        let path = dir.join(PREFERENCES_RELATIVE_PATH);
+        std::fs::write(&path, "{not json").unwrap();
+        assert_eq!(load_preferences(&path), Preferences::default());
+    }
+}
@@ -1,5 +1,10 @@
 //! Progress parsing utilities for yt-dlp output.
 
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
 /// Parse progress percentage from yt-dlp progress line.
 /// Returns None if line doesn't contain valid progress.
 ///
@@ -21,17 +26,311 @@ pub fn parse_progress_percent(line: &str) -> Option<f64> {
     percent_str.parse::<f64>().ok().map(|p| p.clamp(0.0, 100.0))
 }
 
+/// Parse download speed (bytes/sec) from a progress line.
+///
+/// The progress template ends with `-%(progress.speed)s`, e.g. "remedia-12.3%-83-1048576.5";
+/// lines without a numeric trailing speed ("NA", older templates) return None.
+pub fn parse_progress_speed(line: &str) -> Option<f64> {
+    const MARKER: &str = "remedia-";
+
+    let idx = line.find(MARKER)?;
+    let after_percent = &line[idx + MARKER.len()..].split_once('%')?.1;
+    let mut fields = after_percent.trim_start_matches('-').split('-');
+    let _eta = fields.next()?;
+    let speed = fields.next()?.trim().parse::<f64>().ok()?;
+    (speed.is_finite() && speed >= 0.0).then_some(speed)
+}
+
+/// Detect a yt-dlp post-processor line and return the phase label the UI should show.
+///
+/// Post-processors print lines like "[Merger] Merging formats into ..." once the
+/// download itself hits 100%; ffmpeg can run for a while after that.
+pub fn detect_postprocess_phase(line: &str) -> Option<String> {
+    const PHASES: [(&str, &str); 3] =
+        [("[Merger]", "merging"), ("[VideoRemuxer]", "remuxing"), ("[ExtractAudio]", "extractingAudio")];
+
+    let line = line.trim_start();
+    PHASES.iter().find(|(tag, _)| line.starts_with(tag)).map(|(_, phase)| phase.to_string())
+}
+
+/// Parse the remaining time in seconds from a progress line.
+///
+/// The ETA field sits between the percentage and the speed ("remedia-12.3%-83-1048576.5");
+/// "NA" (unknown yet) and clock-formatted values from older templates return None.
+pub fn parse_progress_eta(line: &str) -> Option<u64> {
+    const MARKER: &str = "remedia-";
+
+    let idx = line.find(MARKER)?;
+    let after_percent = &line[idx + MARKER.len()..].split_once('%')?.1;
+    let eta = after_percent.trim_start_matches('-').split('-').next()?;
+    eta.trim().parse::<u64>().ok()
+}
+
+/// Extract the file yt-dlp is writing from a destination line. The last match in a run is the
+/// final file: "[download] Destination: ...", "[Merger] Merging formats into \"...\"",
+/// "[download] ... has already been downloaded", or a post-processor's "Destination: ...".
+pub fn detect_output_path(line: &str) -> Option<String> {
+    let line = line.trim();
+    let path = if let Some(rest) = line.strip_prefix("[Merger] Merging formats into ") {
+        rest.trim_matches('"')
+    } else if let Some(rest) = line.strip_suffix(" has already been downloaded") {
+        rest.strip_prefix("[download] ")?
+    } else if line.starts_with('[') {
+        line.split_once("Destination: ")?.1
+    } else {
+        return None;
+    };
+
+    let path = path.trim();
+    (!path.is_empty()).then(|| path.to_string())
+}
+
+/// Transfer statistics for one completed download, sent in `EVT_DOWNLOAD_STATS`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadStatsReport {
+    pub media_idx: i32,
+    pub avg_speed: f64,  // bytes/sec over the whole download
+    pub peak_speed: f64, // highest reported bytes/sec
+    pub duration_secs: f64,
+    pub total_bytes: u64,
+}
+
+/// One point of a download's progress history, for charting speed over time
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressSample {
+    pub timestamp_ms: u64, // Unix epoch milliseconds
+    pub percent: f64,
+    pub speed: Option<f64>, // bytes/sec; None when the line carried no speed
+}
+
+/// Bounded progress samples for one download; the oldest sample is dropped once full
+#[derive(Debug)]
+pub struct ProgressHistory {
+    samples: VecDeque<ProgressSample>,
+    capacity: usize,
+}
+
+impl ProgressHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self { samples: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    pub fn push(&mut self, sample: ProgressSample) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// All kept samples, oldest first
+    pub fn samples(&self) -> Vec<ProgressSample> {
+        self.samples.iter().cloned().collect()
+    }
+}
+
+/// Accumulates speed samples for one download. Bytes are estimated by integrating each
+/// sample over the time since the previous one, since progress lines carry no byte counts.
+#[derive(Debug)]
+pub struct DownloadStats {
+    started: Instant,
+    last_sample: Instant,
+    peak_speed: f64,
+    total_bytes: f64,
+}
+
+impl DownloadStats {
+    pub fn new(started: Instant) -> Self {
+        Self { started, last_sample: started, peak_speed: 0.0, total_bytes: 0.0 }
+    }
+
+    /// Add a speed sample (bytes/sec) taken at `now`
+    pub fn record(&mut self, speed: f64, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_sample).as_secs_f64();
+        self.total_bytes += speed * elapsed;
+        self.last_sample = now;
+        self.peak_speed = self.peak_speed.max(speed);
+    }
+
+    /// Summarize the download as of `now`
+    pub fn report(&self, media_idx: i32, now: Instant) -> DownloadStatsReport {
+        let duration_secs = now.saturating_duration_since(self.started).as_secs_f64();
+        let avg_speed = if duration_secs > 0.0 { self.total_bytes / duration_secs } else { 0.0 };
+        DownloadStatsReport {
+            media_idx,
+            avg_speed,
+            peak_speed: self.peak_speed,
+            duration_secs,
+            total_bytes: self.total_bytes.round() as u64,
+        }
+    }
+}
+
+/// Minimum interval between non-error stderr lines emitted for one download
+pub const STDERR_EMIT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How important a yt-dlp stderr line is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StderrSeverity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// Classify a stderr line by severity (errors and failures, warnings, everything else).
+pub fn classify_stderr(line: &str) -> StderrSeverity {
+    let line_lower = line.to_lowercase();
+    if line_lower.contains("error") || line_lower.contains("failed") {
+        StderrSeverity::Error
+    } else if line_lower.contains("warning") {
+        StderrSeverity::Warning
+    } else {
+        StderrSeverity::Info
+    }
+}
+
 /// Check if a stderr line should be emitted to the frontend.
 /// Filters to only important lines (errors, warnings, failures).
 pub fn should_emit_stderr(line: &str) -> bool {
-    let line_lower = line.to_lowercase();
-    line_lower.contains("error") || line_lower.contains("warning") || line_lower.contains("failed")
+    classify_stderr(line) != StderrSeverity::Info
+}
+
+/// Per-download stderr throttle.
+///
+/// Repeated identical lines are coalesced with a count suffix ("(x12)"), and
+/// non-error lines are emitted at most once per `STDERR_EMIT_INTERVAL`.
+/// New error lines always go through. A held-back count is emitted before a
+/// different line replaces it.
+#[derive(Debug, Default)]
+pub struct StderrThrottle {
+    last_line: Option<String>,
+    repeats: u32,
+    suppressed: bool,
+    last_emit: Option<Instant>,
+}
+
+impl StderrThrottle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a stderr line; returns the texts to emit, in order (possibly none).
+    pub fn push(&mut self, line: &str, now: Instant) -> Vec<String> {
+        let severity = classify_stderr(line);
+        if severity == StderrSeverity::Info {
+            return Vec::new();
+        }
+
+        let mut texts = Vec::new();
+        if self.last_line.as_deref() == Some(line) {
+            self.repeats += 1;
+        } else {
+            // Report the previous line's held-back count before it is replaced
+            texts.extend(self.flush());
+            self.last_line = Some(line.to_string());
+            self.repeats = 1;
+        }
+
+        let fresh_error = severity == StderrSeverity::Error && self.repeats == 1;
+        let interval_elapsed = self.last_emit.is_none_or(|at| now.duration_since(at) >= STDERR_EMIT_INTERVAL);
+        if fresh_error || interval_elapsed {
+            self.last_emit = Some(now);
+            self.suppressed = false;
+            texts.push(self.current_text());
+        } else {
+            self.suppressed = true;
+        }
+        texts
+    }
+
+    /// Text for the last line if it was held back, so the final count isn't lost.
+    pub fn flush(&mut self) -> Option<String> {
+        if !self.suppressed {
+            return None;
+        }
+        self.suppressed = false;
+        self.last_line.as_ref().map(|_| self.current_text())
+    }
+
+    fn current_text(&self) -> String {
+        let line = self.last_line.as_deref().unwrap_or_default();
+        if self.repeats > 1 {
+            format!("{} (x{})", line, self.repeats)
+        } else {
+            line.to_string()
+        }
+    }
+}
+
+/// Check if a stderr line is yt-dlp reporting that the selected format does not exist
+/// (e.g. audio-only links downloaded in video mode).
+pub fn is_format_unavailable_error(line: &str) -> bool {
+    line.to_lowercase().contains("requested format is not available")
+}
+
+/// Check if a line is yt-dlp skipping a file because of `--max-filesize`
+/// ("File is larger than max-filesize (52428800 bytes > 10485760 bytes). Aborting.").
+pub fn is_max_filesize_skip(line: &str) -> bool {
+    line.to_lowercase().contains("file is larger than max-filesize")
+}
+
+/// Whether a failed video download should be retried once with `-f best`.
+pub fn should_fallback_to_best(fallback_enabled: bool, download_mode: &str, format_unavailable: bool) -> bool {
+    fallback_enabled && download_mode == "video" && format_unavailable
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_progress_history_caps_oldest_first() {
+        let sample = |percent: f64| ProgressSample { timestamp_ms: percent as u64, percent, speed: Some(1024.0) };
+        let mut history = ProgressHistory::new(3);
+        for percent in [10.0, 20.0, 30.0, 40.0] {
+            history.push(sample(percent));
+        }
+
+        let percents: Vec<f64> = history.samples().iter().map(|s| s.percent).collect();
+        assert_eq!(percents, vec![20.0, 30.0, 40.0]);
+
+        let mut disabled = ProgressHistory::new(0);
+        disabled.push(sample(50.0));
+        assert!(disabled.samples().is_empty());
+    }
+
+    #[test]
+    fn test_is_max_filesize_skip() {
+        assert!(is_max_filesize_skip(
+            "[download] File is larger than max-filesize (52428800 bytes > 10485760 bytes). Aborting."
+        ));
+        assert!(is_max_filesize_skip("file is larger than max-filesize"));
+        assert!(!is_max_filesize_skip("[download] File is smaller than min-filesize (10 bytes < 100 bytes)."));
+        assert!(!is_max_filesize_skip("[download] 10.0% of 5.00MiB"));
+    }
+
+    #[test]
+    fn test_is_format_unavailable_error() {
+        assert!(is_format_unavailable_error(
+            "ERROR: [generic] abc: Requested format is not available. Use --list-formats for a list of formats"
+        ));
+        assert!(is_format_unavailable_error("error: requested format is not available"));
+        assert!(!is_format_unavailable_error("ERROR: Unable to download webpage"));
+        assert!(!is_format_unavailable_error("[download] 10.0% of 5.00MiB"));
+    }
+
+    #[test]
+    fn test_should_fallback_to_best() {
+        assert!(should_fallback_to_best(true, "video", true));
+        assert!(!should_fallback_to_best(false, "video", true));
+        assert!(!should_fallback_to_best(true, "audio", true));
+        assert!(!should_fallback_to_best(true, "video", false));
+    }
+
     #[test]
     fn test_parse_progress_percent_valid() {
         assert_eq!(parse_progress_percent("remedia-45.2%-2:30"), Some(45.2));
@@ -60,6 +359,88 @@ mod tests {
         assert_eq!(parse_progress_percent("remedia-abc-2:30"), None);
     }
 
+    #[test]
+    fn test_detect_postprocess_phase() {
+        assert_eq!(
+            detect_postprocess_phase("[Merger] Merging formats into \"/tmp/video.mp4\"").as_deref(),
+            Some("merging")
+        );
+        assert_eq!(
+            detect_postprocess_phase("[VideoRemuxer] Remuxing video from webm to mp4; Destination: a.mp4").as_deref(),
+            Some("remuxing")
+        );
+        assert_eq!(
+            detect_postprocess_phase("[ExtractAudio] Destination: /tmp/song.mp3").as_deref(),
+            Some("extractingAudio")
+        );
+        assert_eq!(detect_postprocess_phase("[download] 100% of 5.00MiB"), None);
+        assert_eq!(detect_postprocess_phase("WARNING: [Merger] is not a prefix here"), None);
+    }
+
+    #[test]
+    fn test_detect_output_path() {
+        assert_eq!(
+            detect_output_path("[download] Destination: /dl/Clip [abc].f137.mp4").as_deref(),
+            Some("/dl/Clip [abc].f137.mp4")
+        );
+        assert_eq!(
+            detect_output_path("[Merger] Merging formats into \"/dl/Clip [abc].mp4\"").as_deref(),
+            Some("/dl/Clip [abc].mp4")
+        );
+        assert_eq!(
+            detect_output_path("[download] /dl/Clip [abc].mp4 has already been downloaded").as_deref(),
+            Some("/dl/Clip [abc].mp4")
+        );
+        assert_eq!(
+            detect_output_path("[VideoRemuxer] Remuxing video from webm to mp4; Destination: /dl/a.mp4").as_deref(),
+            Some("/dl/a.mp4")
+        );
+        assert_eq!(detect_output_path("download:remedia-50.0%-10-1000.0"), None);
+        assert_eq!(detect_output_path("WARNING: Destination: nowhere"), None);
+    }
+
+    #[test]
+    fn test_download_stats_average_and_peak() {
+        let start = Instant::now();
+        let mut stats = DownloadStats::new(start);
+        stats.record(1000.0, start + Duration::from_secs(1));
+        stats.record(3000.0, start + Duration::from_secs(2));
+        stats.record(2000.0, start + Duration::from_secs(4));
+
+        let report = stats.report(7, start + Duration::from_secs(4));
+        assert_eq!(report.media_idx, 7);
+        assert_eq!(report.total_bytes, 1000 + 3000 + 4000);
+        assert_eq!(report.duration_secs, 4.0);
+        assert_eq!(report.avg_speed, 2000.0);
+        assert_eq!(report.peak_speed, 3000.0);
+    }
+
+    #[test]
+    fn test_download_stats_without_samples() {
+        let start = Instant::now();
+        let stats = DownloadStats::new(start);
+        let report = stats.report(1, start);
+        assert_eq!((report.avg_speed, report.peak_speed, report.total_bytes), (0.0, 0.0, 0));
+    }
+
+    #[test]
+    fn test_parse_progress_eta() {
+        assert_eq!(parse_progress_eta("download:remedia-12.3%-83-1048576.5"), Some(83));
+        assert_eq!(parse_progress_eta("remedia-100%-0-0"), Some(0));
+        assert_eq!(parse_progress_eta("remedia-12.3%-NA-NA"), None);
+        assert_eq!(parse_progress_eta("remedia-45.2%-2:30"), None);
+    }
+
+    #[test]
+    fn test_parse_progress_speed() {
+        assert_eq!(parse_progress_speed("download:remedia-12.3%-83-1048576.5"), Some(1_048_576.5));
+        assert_eq!(parse_progress_speed("remedia-100%-0-0"), Some(0.0));
+        assert_eq!(parse_progress_speed("remedia-12.3%-NA-NA"), None);
+        // Lines from the older template without speed still parse as progress only
+        assert_eq!(parse_progress_speed("remedia-45.2%-2:30"), None);
+        assert_eq!(parse_progress_percent("remedia-45.2%-83-1048576.5"), Some(45.2));
+    }
+
     #[test]
     fn test_should_emit_stderr() {
         assert!(should_emit_stderr("ERROR: Something went wrong"));
@@ -68,4 +449,55 @@ mod tests {
         assert!(!should_emit_stderr("[download] Downloading video 1 of 3"));
         assert!(!should_emit_stderr("[info] Metadata downloaded"));
     }
+
+    #[test]
+    fn test_classify_stderr() {
+        assert_eq!(classify_stderr("ERROR: Unable to download webpage"), StderrSeverity::Error);
+        assert_eq!(classify_stderr("Postprocessing failed"), StderrSeverity::Error);
+        assert_eq!(classify_stderr("WARNING: [youtube] Falling back"), StderrSeverity::Warning);
+        assert_eq!(classify_stderr("[download] Destination: a.mp4"), StderrSeverity::Info);
+    }
+
+    #[test]
+    fn test_stderr_throttle_coalesces_repeats() {
+        let mut throttle = StderrThrottle::new();
+        let t0 = Instant::now();
+        let err = "ERROR: HTTP Error 503: Service Unavailable";
+
+        assert_eq!(throttle.push(err, t0), vec![err]);
+        for i in 1..12 {
+            assert!(throttle.push(err, t0 + Duration::from_millis(i * 10)).is_empty());
+        }
+        assert_eq!(throttle.flush(), Some(format!("{} (x12)", err)));
+        assert_eq!(throttle.flush(), None);
+
+        // Once the interval passes the repeat count is emitted directly
+        assert_eq!(throttle.push(err, t0 + STDERR_EMIT_INTERVAL), vec![format!("{} (x13)", err)]);
+    }
+
+    #[test]
+    fn test_stderr_throttle_reports_count_before_a_new_line() {
+        let mut throttle = StderrThrottle::new();
+        let t0 = Instant::now();
+        let (a, b) = ("ERROR: HTTP Error 503: Service Unavailable", "ERROR: giving up");
+
+        assert_eq!(throttle.push(a, t0), vec![a]);
+        assert!(throttle.push(a, t0 + Duration::from_millis(10)).is_empty());
+        assert!(throttle.push(a, t0 + Duration::from_millis(20)).is_empty());
+        assert_eq!(throttle.push(b, t0 + Duration::from_millis(30)), vec![format!("{} (x3)", a), b.to_string()]);
+        assert_eq!(throttle.flush(), None);
+    }
+
+    #[test]
+    fn test_stderr_throttle_rate_limits_warnings_only() {
+        let mut throttle = StderrThrottle::new();
+        let t0 = Instant::now();
+
+        assert_eq!(throttle.push("WARNING: one", t0), vec!["WARNING: one"]);
+        assert!(throttle.push("WARNING: two", t0 + Duration::from_millis(200)).is_empty());
+        assert!(throttle.push("[info] ignored", t0 + Duration::from_millis(300)).is_empty());
+        // New errors are never held back, and the warning they replace is still reported
+        assert_eq!(throttle.push("ERROR: boom", t0 + Duration::from_millis(400)), vec!["WARNING: two", "ERROR: boom"]);
+        assert_eq!(throttle.push("WARNING: three", t0 + Duration::from_millis(1400)), vec!["WARNING: three"]);
+    }
 }
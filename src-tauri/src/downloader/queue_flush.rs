@@ -0,0 +1,75 @@
+//! Debounce for queue persistence writes.
+//!
+//! Queue mutations only mark the state dirty; a writer asks `take_due` on a timer and writes
+//! at most once per `QUEUE_FLUSH_INTERVAL`, so a high-churn batch costs one write instead of
+//! hundreds. No queue persistence exists yet, so nothing drives a scheduler today.
+
+use std::time::{Duration, Instant};
+
+/// Minimum gap between two queue writes
+#[allow(dead_code)]
+pub const QUEUE_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Coalesces dirty marks into flushes spaced at least `min_interval` apart
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct FlushScheduler {
+    min_interval: Duration,
+    dirty: bool,
+    last_flush: Option<Instant>,
+}
+
+#[allow(dead_code)]
+impl FlushScheduler {
+    pub fn new(min_interval: Duration) -> Self {
+        Self { min_interval, dirty: false, last_flush: None }
+    }
+
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Whether a flush is due at `now`. A due flush is claimed: the dirty mark is cleared
+    /// and `now` becomes the last flush time.
+    pub fn take_due(&mut self, now: Instant) -> bool {
+        let spaced = self.last_flush.is_none_or(|last| now.duration_since(last) >= self.min_interval);
+        if !self.dirty || !spaced {
+            return false;
+        }
+        self.dirty = false;
+        self.last_flush = Some(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flush_scheduler_coalesces_dirty_marks() {
+        let start = Instant::now();
+        let mut scheduler = FlushScheduler::new(QUEUE_FLUSH_INTERVAL);
+
+        // Nothing to write yet
+        assert!(!scheduler.take_due(start));
+
+        // Many mutations in a burst produce one flush
+        for _ in 0..100 {
+            scheduler.mark_dirty();
+        }
+        assert!(scheduler.take_due(start));
+        assert!(!scheduler.dirty);
+        assert!(!scheduler.take_due(start));
+
+        // Changes inside the interval wait for it to elapse
+        scheduler.mark_dirty();
+        scheduler.mark_dirty();
+        assert!(!scheduler.take_due(start + Duration::from_secs(2)));
+        assert!(scheduler.dirty);
+        assert!(scheduler.take_due(start + Duration::from_secs(5)));
+
+        // An idle interval does not flush
+        assert!(!scheduler.take_due(start + Duration::from_secs(60)));
+    }
+}
@@ -0,0 +1,127 @@
+//! Detection of partially-downloaded files that yt-dlp can resume.
+//!
+//! yt-dlp writes in-progress downloads as `<name>.part` and picks them up again
+//! via `--continue`. The final filename contains the title, which is only known
+//! after probing, so detection relies on the hash unique-id suffix instead.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::paths::resolve_output_dir;
+use super::settings::{DownloadSettings, generate_unique_id};
+
+/// Extension yt-dlp appends to incomplete downloads
+const PART_EXTENSION: &str = ".part";
+
+/// Filename fragment identifying a URL's download in hash unique-id mode (`<title> [<hash>].<ext>`)
+pub fn part_file_marker(url: &str) -> String {
+    format!(" [{}].", generate_unique_id(url))
+}
+
+/// Find a `.part` file in `dir` belonging to `url`, if one exists
+pub fn find_part_file(dir: &Path, url: &str) -> Option<PathBuf> {
+    let marker = part_file_marker(url);
+
+    fs::read_dir(dir).ok()?.flatten().map(|entry| entry.path()).find(|path| {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(PART_EXTENSION) && name.contains(&marker))
+    })
+}
+
+/// Whether a resumable `.part` file exists for this download.
+/// Only hash unique-id mode is supported; native ids need a yt-dlp probe to know the filename.
+/// With `--no-part` nothing is left to resume, and with `--paths temp:` the `.part` lives in the temp dir.
+pub fn is_resumable(url: &str, output_location: &str, subfolder: Option<&str>, settings: &DownloadSettings) -> bool {
+    if settings.no_part || !settings.append_unique_id || settings.unique_id_type != "hash" {
+        return false;
+    }
+
+    if let Some(temp_dir) = settings.temp_dir.as_deref() {
+        return find_part_file(Path::new(temp_dir), url).is_some();
+    }
+
+    let output_dir = resolve_output_dir(output_location, settings.effective_subfolder(subfolder));
+    find_part_file(Path::new(&output_dir), url).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    fn hash_settings() -> DownloadSettings {
+        let mut settings = DownloadSettings::remote_defaults();
+        settings.unique_id_type = "hash".to_string();
+        settings
+    }
+
+    #[test]
+    fn test_part_file_marker_uses_hash_id() {
+        let url = "https://example.com/video/1";
+        assert_eq!(part_file_marker(url), format!(" [{}].", generate_unique_id(url)));
+        assert_ne!(part_file_marker(url), part_file_marker("https://example.com/video/2"));
+    }
+
+    #[test]
+    fn test_find_part_file_matches_only_own_part() {
+        let dir = TestDir::new("resume");
+        let url = "https://example.com/video/1";
+        let id = generate_unique_id(url);
+
+        fs::write(dir.join(format!("Some Title [{}].mp4", id)), b"done").unwrap();
+        assert!(find_part_file(dir.path(), url).is_none(), "completed files are not resumable");
+
+        let other = generate_unique_id("https://example.com/video/2");
+        fs::write(dir.join(format!("Other [{}].mp4.part", other)), b"x").unwrap();
+        assert!(find_part_file(dir.path(), url).is_none());
+
+        let part = dir.join(format!("Some Title [{}].f137.mp4.part", id));
+        fs::write(&part, b"partial").unwrap();
+        assert_eq!(find_part_file(dir.path(), url), Some(part));
+    }
+
+    #[test]
+    fn test_is_resumable_requires_hash_mode() {
+        let dir = TestDir::new("resume");
+        let sub = dir.join("sub");
+        fs::create_dir_all(&sub).unwrap();
+        let url = "https://example.com/video/1";
+        fs::write(sub.join(format!("T [{}].webm.part", generate_unique_id(url))), b"x").unwrap();
+
+        let location = dir.path().to_str().unwrap();
+        assert!(is_resumable(url, location, Some("sub"), &hash_settings()));
+        assert!(!is_resumable(url, location, None, &hash_settings()));
+        assert!(!is_resumable(url, location, Some("sub"), &DownloadSettings::remote_defaults()));
+    }
+
+    #[test]
+    fn test_is_resumable_looks_in_temp_dir() {
+        let dir = TestDir::new("resume");
+        let scratch = dir.join("scratch");
+        fs::create_dir_all(&scratch).unwrap();
+        let url = "https://example.com/video/1";
+        fs::write(scratch.join(format!("T [{}].webm.part", generate_unique_id(url))), b"x").unwrap();
+
+        let location = dir.path().to_str().unwrap();
+        let mut settings = hash_settings();
+        assert!(!is_resumable(url, location, None, &settings), "part file is not in the output dir");
+
+        settings.temp_dir = Some(scratch.to_str().unwrap().to_string());
+        assert!(is_resumable(url, location, None, &settings));
+    }
+
+    #[test]
+    fn test_is_resumable_false_with_no_part() {
+        let dir = TestDir::new("resume");
+        let url = "https://example.com/video/1";
+        fs::write(dir.join(format!("T [{}].webm.part", generate_unique_id(url))), b"x").unwrap();
+
+        let location = dir.path().to_str().unwrap();
+        let mut settings = hash_settings();
+        assert!(is_resumable(url, location, None, &settings));
+
+        settings.no_part = true;
+        assert!(!is_resumable(url, location, None, &settings));
+    }
+}
@@ -1,8 +1,15 @@
 //! Download settings validation and yt-dlp argument building.
 
+use std::collections::HashMap;
+use std::io;
+use std::path::{MAIN_SEPARATOR, Path};
+
 use serde::{Deserialize, Serialize};
 
-use crate::error::DownloaderError;
+use crate::error::{DownloaderError, FrontendError};
+use crate::safe_mode::is_safe_mode;
+
+use super::health::probe_dir_writable;
 
 /// Maximum URL length to prevent abuse
 pub const MAX_URL_LENGTH: usize = 4096;
@@ -10,6 +17,70 @@ pub const MAX_URL_LENGTH: usize = 4096;
 /// Maximum output path length (OS limits)
 pub const MAX_OUTPUT_PATH_LENGTH: usize = 1024;
 
+/// Metadata fields users may override via `metadata_overrides`
+pub const METADATA_OVERRIDE_KEYS: &[&str] = &["title", "artist", "album", "date"];
+
+/// `--parse-metadata` rule used with `parse_title_metadata`: split "Artist - Title" video titles
+pub const TITLE_METADATA_PATTERN: &str = "title:%(artist)s - %(title)s";
+
+/// Allowed range for `max_filename_length` (passed to yt-dlp's `--trim-filenames`)
+pub const MIN_FILENAME_LENGTH: usize = 10;
+pub const MAX_FILENAME_LENGTH: usize = 255;
+
+/// Upper bound for `concurrent_fragments` per download
+pub const MAX_CONCURRENT_FRAGMENTS: usize = 64;
+
+/// Stand-in for the hash unique id when previewing a template without a URL
+pub const HASH_ID_PLACEHOLDER: &str = "<hash>";
+
+/// Filename prefix used with `prefix_date`: the upload date as YYYY-MM-DD
+pub const UPLOAD_DATE_PREFIX: &str = "%(upload_date>%Y-%m-%d)s - ";
+
+/// Connections per file aria2c opens when no fragment budget applies (its `-x` maximum)
+pub const ARIA2C_MAX_CONNECTIONS: usize = 16;
+
+/// Extra yt-dlp flags users may pass through `extra_args`, and whether each takes a value.
+/// Anything not listed (e.g. `--exec`, `-o`, `--paths`, `--config-location`, `--batch-file`)
+/// is rejected so settings can't hijack the command or redirect output. `--concurrent-fragments`
+/// goes through `concurrent_fragments`, so the app-wide fragment budget can cap it. `--no-check-certificates`
+/// stays out too: turning off TLS verification would let a network attacker swap the download.
+pub const ALLOWED_EXTRA_FLAGS: &[(&str, bool)] = &[
+    ("--geo-bypass", false),
+    ("--prefer-free-formats", false),
+    ("--force-ipv4", false),
+    ("--force-ipv6", false),
+    ("--no-mtime", false),
+    ("--write-subs", false),
+    ("--write-auto-subs", false),
+    ("--write-description", false),
+    ("--write-info-json", false),
+    ("--sub-langs", true),
+    ("--retries", true),
+    ("--fragment-retries", true),
+    ("--socket-timeout", true),
+    ("--sleep-interval", true),
+    ("--max-sleep-interval", true),
+    ("--sleep-requests", true),
+    ("--geo-bypass-country", true),
+    ("--sponsorblock-remove", true),
+];
+
+/// Browsers yt-dlp can read cookies from (`--cookies-from-browser`)
+pub const COOKIE_BROWSERS: &[&str] =
+    &["brave", "chrome", "chromium", "edge", "firefox", "opera", "safari", "vivaldi", "whale"];
+
+/// Extractor args passed to every yt-dlp invocation (browser impersonation for the generic extractor)
+pub const DEFAULT_EXTRACTOR_ARGS: &str = "generic:impersonate";
+
+/// Codec families `--remux-video mp4` can hold, as yt-dlp names them (`vcodec`/`acodec` up to the first '.')
+const MP4_CODECS: &[&str] = &[
+    "avc1", "avc3", "h264", "hvc1", "hev1", "h265", "hevc", "av01", "av1", "vp09", "vp9", "mp4a", "aac", "mp3", "opus",
+    "flac", "alac", "ac-3", "ac3", "ec-3", "eac3",
+];
+
+/// Codec families `--remux-video webm` can hold
+const WEBM_CODECS: &[&str] = &["vp8", "vp08", "vp9", "vp09", "av01", "av1", "opus", "vorbis"];
+
 /// Download settings from frontend
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -28,12 +99,64 @@ pub struct DownloadSettings {
     pub append_unique_id: bool, // Append unique ID to filenames
     #[serde(default = "default_native")]
     pub unique_id_type: String, // "native" = yt-dlp's %(id)s, "hash" = FNV-1a hash
+    #[serde(default)]
+    pub extractor_args: Option<Vec<String>>, // Extra "key:subkey=value" pairs for --extractor-args
+    #[serde(default)]
+    pub metadata_overrides: Option<HashMap<String, String>>, // Embedded metadata corrections, keyed by field
+    #[serde(default)]
+    pub max_filename_length: Option<usize>, // Trim filenames to this many characters (10-255)
+    #[serde(default)]
+    pub fallback_to_best: bool, // Retry once with `-f best` when the requested video format is unavailable
+    #[serde(default)]
+    pub downloader_backend: Option<String>, // "native" | "aria2c" (None = native)
+    #[serde(default)]
+    pub prefer_codec: Option<String>, // "h264" | "vp9" | "av1" | "any" (None = any)
+    #[serde(default)]
+    pub temp_dir: Option<String>, // Directory for intermediate files (--paths temp:<dir>)
+    #[serde(default)]
+    pub no_part: bool, // Write directly to the final file instead of a .part file
+    #[serde(default = "default_skip")]
+    pub on_duplicate: String, // "skip" | "overwrite" | "number" (when the output file already exists)
+    #[serde(default)]
+    pub extra_args: Option<Vec<String>>, // Additional yt-dlp flags, restricted to ALLOWED_EXTRA_FLAGS
+    #[serde(default)]
+    pub avoid_postprocessing: bool, // Pick pre-muxed formats and skip every step that needs ffmpeg
+    #[serde(default)]
+    pub cookies_source: Option<String>, // Browser to read cookies from (one of COOKIE_BROWSERS)
+    #[serde(default)]
+    pub cookies_file: Option<String>, // Netscape-format cookies.txt; exclusive with cookies_source
+    #[serde(default)]
+    pub verify_after_download: bool, // Re-resolve the URL after completion and check the file size
+    #[serde(default)]
+    pub prefix_date: bool, // Start filenames with the upload date (YYYY-MM-DD) for archival sorting
+    #[serde(default)]
+    pub file_mode: Option<String>, // Unix permissions (octal, e.g. "644") applied to the finished file
+    #[serde(default)]
+    pub dateafter: Option<String>, // Only items uploaded on/after this date (YYYYMMDD), for channel syncs
+    #[serde(default)]
+    pub parse_title_metadata: bool, // Audio: embed artist/title parsed from "Artist - Title" video titles
+    #[serde(default)]
+    pub concurrent_fragments: Option<usize>, // yt-dlp -N; capped by the app-wide fragment budget at start
+    #[serde(default)]
+    pub write_description: bool, // Save the video description as a .description file next to the download
+    #[serde(default)]
+    pub flatten_output: bool, // Ignore the collection subfolder and write straight into the output location
+    #[serde(default)]
+    pub keep_original: bool, // Keep the source streams/file after merging, remuxing or audio extraction (-k)
+    #[serde(default)]
+    pub download_archive: Option<String>, // --download-archive file, relative to the output folder unless absolute
+    #[serde(skip)]
+    pub aria2c_connections: Option<usize>, // aria2c -x/-s, set from the fragment budget at start (None = maximum)
 }
 
 fn default_native() -> String {
     "native".to_string()
 }
 
+fn default_skip() -> String {
+    "skip".to_string()
+}
+
 fn default_true() -> bool {
     true
 }
@@ -42,7 +165,47 @@ fn default_unlimited() -> String {
     "unlimited".to_string()
 }
 
+/// Compact description of the settings a download runs with, sent in `EVT_DOWNLOAD_STARTED`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsSummary {
+    pub mode: String,               // "video" | "audio"
+    pub resolution: Option<String>, // max_resolution in video mode, None for audio
+    pub format: String,             // video_format or audio_format, depending on mode
+}
+
 impl DownloadSettings {
+    /// Summarize the settings that determine what gets downloaded
+    pub fn summary(&self) -> SettingsSummary {
+        let is_audio = self.download_mode == "audio";
+        SettingsSummary {
+            mode: self.download_mode.clone(),
+            resolution: (!is_audio).then(|| self.max_resolution.clone()),
+            format: if is_audio { self.audio_format.clone() } else { self.video_format.clone() },
+        }
+    }
+
+    /// Lower `concurrent_fragments` to this download's share of the app-wide fragment budget.
+    /// Downloads that don't request parallel fragments are left at yt-dlp's default of one.
+    /// aria2c's connections per file count against the same share.
+    pub fn cap_concurrent_fragments(&mut self, allowance: usize) {
+        let allowance = allowance.max(1);
+        if let Some(fragments) = self.concurrent_fragments.as_mut() {
+            *fragments = (*fragments).min(allowance);
+        }
+        self.aria2c_connections = Some(allowance.min(ARIA2C_MAX_CONNECTIONS));
+    }
+
+    /// Whether downloads should be handed to aria2c instead of yt-dlp's native downloader
+    pub fn uses_aria2c(&self) -> bool {
+        self.downloader_backend.as_deref() == Some("aria2c")
+    }
+
+    /// The collection subfolder to write into, or None when `flatten_output` puts everything in the output location
+    pub fn effective_subfolder<'a>(&self, subfolder: Option<&'a str>) -> Option<&'a str> {
+        if self.flatten_output { None } else { subfolder }
+    }
+
     /// Default settings for remote control API
     pub fn remote_defaults() -> Self {
         Self {
@@ -56,6 +219,30 @@ impl DownloadSettings {
             max_file_size: default_unlimited(),
             append_unique_id: true,
             unique_id_type: default_native(),
+            extractor_args: None,
+            metadata_overrides: None,
+            max_filename_length: None,
+            fallback_to_best: false,
+            downloader_backend: None,
+            prefer_codec: None,
+            temp_dir: None,
+            no_part: false,
+            on_duplicate: default_skip(),
+            extra_args: None,
+            avoid_postprocessing: false,
+            cookies_source: None,
+            cookies_file: None,
+            verify_after_download: false,
+            prefix_date: false,
+            file_mode: None,
+            dateafter: None,
+            parse_title_metadata: false,
+            concurrent_fragments: None,
+            write_description: false,
+            flatten_output: false,
+            keep_original: false,
+            download_archive: None,
+            aria2c_connections: None,
         }
     }
 }
@@ -110,9 +297,255 @@ pub fn validate_settings(settings: &DownloadSettings) -> Result<(), DownloaderEr
         return Err(DownloaderError::invalid_settings(format!("Invalid unique_id_type: {}", settings.unique_id_type)));
     }
 
+    // Validate duplicate filename handling
+    if !matches!(settings.on_duplicate.as_str(), "skip" | "overwrite" | "number") {
+        return Err(DownloaderError::invalid_settings(format!("Invalid on_duplicate: {}", settings.on_duplicate)));
+    }
+
+    // Validate extra extractor args
+    if let Some(extractor_args) = &settings.extractor_args {
+        validate_extractor_args(extractor_args)?;
+    }
+
+    // Validate downloader backend
+    if let Some(backend) = &settings.downloader_backend
+        && !matches!(backend.as_str(), "native" | "aria2c")
+    {
+        return Err(DownloaderError::invalid_settings(format!("Invalid downloader_backend: {}", backend)));
+    }
+
+    // Validate preferred video codec
+    if let Some(codec) = &settings.prefer_codec
+        && !matches!(codec.as_str(), "h264" | "vp9" | "av1" | "any")
+    {
+        return Err(DownloaderError::invalid_settings(format!("Invalid prefer_codec: {}", codec)));
+    }
+
+    // Validate pass-through yt-dlp flags
+    if let Some(extra_args) = &settings.extra_args {
+        validate_extra_args_allowed(extra_args, is_safe_mode())?;
+    }
+
+    // Validate temp directory
+    if let Some(temp_dir) = &settings.temp_dir {
+        validate_temp_dir(temp_dir)?;
+    }
+
+    validate_cookies(settings.cookies_source.as_deref(), settings.cookies_file.as_deref())?;
+
+    // Validate output file permissions
+    if let Some(mode) = &settings.file_mode
+        && parse_file_mode(mode).is_none()
+    {
+        return Err(DownloaderError::invalid_settings(format!("Invalid file_mode: {}", mode)));
+    }
+
+    // Validate upload date cutoff
+    if let Some(date) = &settings.dateafter
+        && !is_valid_yyyymmdd(date)
+    {
+        return Err(DownloaderError::invalid_settings(format!("Invalid dateafter: {} (expected YYYYMMDD)", date)));
+    }
+
+    // Validate filename length limit
+    if let Some(len) = settings.max_filename_length
+        && !(MIN_FILENAME_LENGTH..=MAX_FILENAME_LENGTH).contains(&len)
+    {
+        return Err(DownloaderError::invalid_settings(format!(
+            "Invalid max_filename_length: {} (must be {}-{})",
+            len, MIN_FILENAME_LENGTH, MAX_FILENAME_LENGTH
+        )));
+    }
+
+    // Validate per-download fragment concurrency
+    if let Some(fragments) = settings.concurrent_fragments
+        && !(1..=MAX_CONCURRENT_FRAGMENTS).contains(&fragments)
+    {
+        return Err(DownloaderError::invalid_settings(format!(
+            "Invalid concurrent_fragments: {} (must be 1-{})",
+            fragments, MAX_CONCURRENT_FRAGMENTS
+        )));
+    }
+
+    // Artist/title parsing targets music; video downloads keep yt-dlp's own mapping
+    if settings.parse_title_metadata && settings.download_mode != "audio" {
+        return Err(DownloaderError::invalid_settings("parse_title_metadata requires download_mode \"audio\""));
+    }
+
+    // Validate metadata override keys against the allow-list
+    if let Some(overrides) = &settings.metadata_overrides {
+        for (key, value) in overrides {
+            if !METADATA_OVERRIDE_KEYS.contains(&key.as_str()) {
+                return Err(DownloaderError::invalid_settings(format!("Invalid metadata_overrides key: {}", key)));
+            }
+            if value.contains(['\n', '\r']) {
+                return Err(DownloaderError::invalid_settings(format!(
+                    "Invalid metadata_overrides value for {}: must be a single line",
+                    key
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate a temp directory: same rules as the output location, and it must exist and be writable
+pub fn validate_temp_dir(dir: &str) -> Result<(), DownloaderError> {
+    validate_output_location(dir)?;
+
+    let path = Path::new(dir);
+    if !path.is_dir() {
+        return Err(DownloaderError::invalid_path(format!("Temp directory does not exist: {}", dir)));
+    }
+    if !probe_dir_writable(path) {
+        return Err(DownloaderError::invalid_path(format!("Temp directory is not writable: {}", dir)));
+    }
+
+    Ok(())
+}
+
+/// Whether a stream in `source_codec` can be remuxed into `video_format` without re-encoding
+/// (e.g. webm can't hold AAC). Meant for a warning, not a hard check: "best", mkv, missing
+/// streams ("none") and codecs not in the matrix all count as compatible.
+pub fn check_remux_compat(video_format: &str, source_codec: &str) -> bool {
+    let codec = source_codec.trim().to_lowercase();
+    let family = codec.split('.').next().unwrap_or_default();
+    let known = MP4_CODECS.contains(&family) || WEBM_CODECS.contains(&family);
+
+    match video_format {
+        "mp4" if known => MP4_CODECS.contains(&family),
+        "webm" if known => WEBM_CODECS.contains(&family),
+        _ => true,
+    }
+}
+
+/// Parse a `file_mode` setting: 3 or 4 octal digits ("644", "0640")
+pub fn parse_file_mode(mode: &str) -> Option<u32> {
+    if !(3..=4).contains(&mode.len()) || !mode.chars().all(|c| ('0'..='7').contains(&c)) {
+        return None;
+    }
+    u32::from_str_radix(mode, 8).ok()
+}
+
+/// Check a `YYYYMMDD` date: eight digits naming a real calendar day (leap years included)
+pub fn is_valid_yyyymmdd(date: &str) -> bool {
+    if date.len() != 8 || !date.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    let year: u32 = date[0..4].parse().unwrap_or(0);
+    let month: u32 = date[4..6].parse().unwrap_or(0);
+    let day: u32 = date[6..8].parse().unwrap_or(0);
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days_in_month).contains(&day)
+}
+
+/// Set a finished file's permission bits. Windows has no mode bits, so this is a no-op there.
+#[cfg(unix)]
+pub fn apply_file_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+pub fn apply_file_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+    Ok(())
+}
+
+/// Validate cookie options: at most one of a browser source and a cookies file,
+/// the browser must be one yt-dlp supports and the file must exist.
+pub fn validate_cookies(cookies_source: Option<&str>, cookies_file: Option<&str>) -> Result<(), DownloaderError> {
+    match (cookies_source, cookies_file) {
+        (Some(_), Some(_)) => Err(DownloaderError::invalid_settings(
+            "cookies_source and cookies_file are mutually exclusive",
+        )),
+        (Some(browser), None) if !COOKIE_BROWSERS.contains(&browser) => {
+            Err(DownloaderError::invalid_settings(format!("Invalid cookies_source: {}", browser)))
+        }
+        (None, Some(file)) if !Path::new(file).is_file() => {
+            Err(DownloaderError::invalid_path(format!("Cookies file not found: {}", file)))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Validate user-supplied extractor args (each must look like `key:subkey=value`)
+pub fn validate_extractor_args(args: &[String]) -> Result<(), DownloaderError> {
+    for arg in args {
+        if !is_valid_extractor_arg(arg) {
+            return Err(DownloaderError::invalid_settings(format!("Invalid extractor_args entry: {}", arg)));
+        }
+    }
+    Ok(())
+}
+
+/// Validate pass-through yt-dlp flags, refusing any at all in safe mode
+pub fn validate_extra_args_allowed(args: &[String], safe_mode: bool) -> Result<(), DownloaderError> {
+    if safe_mode && !args.is_empty() {
+        return Err(DownloaderError::invalid_settings("extra_args is disabled in safe mode"));
+    }
+    validate_extra_args(args)
+}
+
+/// Validate pass-through yt-dlp flags against `ALLOWED_EXTRA_FLAGS`.
+/// Values follow their flag either as the next entry or as `--flag=value`.
+pub fn validate_extra_args(args: &[String]) -> Result<(), DownloaderError> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value)),
+            None => (arg.as_str(), None),
+        };
+
+        let Some(&(_, takes_value)) = ALLOWED_EXTRA_FLAGS.iter().find(|(allowed, _)| *allowed == flag) else {
+            return Err(DownloaderError::invalid_settings(format!("yt-dlp flag not allowed in extra_args: {}", flag)));
+        };
+
+        let value = match (takes_value, inline_value) {
+            (false, None) => continue,
+            (false, Some(_)) => {
+                return Err(DownloaderError::invalid_settings(format!("yt-dlp flag {} takes no value", flag)));
+            }
+            (true, Some(value)) => Some(value),
+            (true, None) => iter.next().map(String::as_str),
+        };
+
+        if !value.is_some_and(is_safe_extra_value) {
+            return Err(DownloaderError::invalid_settings(format!("Missing or invalid value for {}", flag)));
+        }
+    }
     Ok(())
 }
 
+/// A flag value must be non-empty, not look like another flag, and avoid shell metacharacters
+fn is_safe_extra_value(value: &str) -> bool {
+    !value.is_empty() && !value.starts_with('-') && !value.chars().any(|c| DANGEROUS_SHELL_CHARS.contains(&c))
+}
+
+/// Check a single extractor arg has the `key:subkey=value` shape yt-dlp expects
+fn is_valid_extractor_arg(arg: &str) -> bool {
+    let Some((key, rest)) = arg.split_once(':') else {
+        return false;
+    };
+    let Some((subkey, value)) = rest.split_once('=') else {
+        return false;
+    };
+
+    let is_ident = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    is_ident(key)
+        && is_ident(subkey)
+        && !value.is_empty()
+        && !value.chars().any(|c| c.is_whitespace() || DANGEROUS_SHELL_CHARS.contains(&c))
+}
+
 /// Validate a size or rate string (e.g., "50K", "1M", "unlimited")
 fn validate_size_or_rate(s: &str) -> bool {
     if s == "unlimited" {
@@ -149,6 +582,44 @@ fn validate_size_or_rate(s: &str) -> bool {
     }
 }
 
+/// One row of a bulk add, checked by `validate_batch` before anything is queued
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItem {
+    pub media_idx: i32,
+    pub url: String,
+}
+
+/// Rows of a bulk add split by whether they can be queued, in input order
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchValidation {
+    pub valid: Vec<i32>,
+    pub invalid: Vec<(i32, FrontendError)>,
+}
+
+/// Check every row of a bulk add against shared settings. The settings are validated once;
+/// if they are invalid every row is rejected with that error, otherwise each URL is checked.
+pub fn validate_batch(items: &[BatchItem], settings: &DownloadSettings) -> BatchValidation {
+    let settings_error = validate_settings(settings).err().map(|e| e.to_frontend_error());
+    let mut result = BatchValidation::default();
+
+    for item in items {
+        let error = match &settings_error {
+            Some(error) => Some(error.clone()),
+            None if item.media_idx < 0 => {
+                Some(DownloaderError::invalid_media_idx("Media index must be non-negative").to_frontend_error())
+            }
+            None => validate_url(&item.url).err().map(|e| e.to_frontend_error()),
+        };
+        match error {
+            Some(error) => result.invalid.push((item.media_idx, error)),
+            None => result.valid.push(item.media_idx),
+        }
+    }
+
+    result
+}
+
 /// Characters that could be dangerous if passed to a shell.
 /// Although we use `Command::arg()` which doesn't invoke a shell,
 /// rejecting these provides defense-in-depth.
@@ -170,8 +641,10 @@ pub fn validate_url(url: &str) -> Result<(), DownloaderError> {
         return Err(DownloaderError::invalid_url(format!("URL is too long (max {} characters)", MAX_URL_LENGTH)));
     }
 
-    // Defense-in-depth: reject shell metacharacters even though Command::arg() is safe
-    if url.chars().any(|c| DANGEROUS_SHELL_CHARS.contains(&c)) {
+    // Defense-in-depth: reject shell metacharacters even though Command::arg() is safe.
+    // `&` is allowed only inside the query string, where it separates parameters.
+    let query_start = url.find('?').unwrap_or(url.len());
+    if url.char_indices().any(|(i, c)| DANGEROUS_SHELL_CHARS.contains(&c) && !(c == '&' && i > query_start)) {
         return Err(DownloaderError::invalid_url("URL contains invalid characters"));
     }
 
@@ -192,6 +665,10 @@ pub fn validate_output_location(location: &str) -> Result<(), DownloaderError> {
         )));
     }
 
+    if location.chars().any(char::is_control) {
+        return Err(DownloaderError::invalid_path("Output location contains control characters"));
+    }
+
     Ok(())
 }
 
@@ -229,11 +706,60 @@ pub fn generate_unique_id(url: &str) -> String {
     result
 }
 
+/// Build the yt-dlp `--output` template for a download into `output_dir`.
+///
+/// With `append_unique_id`, the title is suffixed by either yt-dlp's native `%(id)s`
+/// or an 8-char hash of the URL (`HASH_ID_PLACEHOLDER` when no URL is given).
+///
+/// With `on_duplicate = "number"` a unique ID is always appended, so same-titled
+/// downloads get distinct filenames instead of being skipped.
+///
+/// With `prefix_date` the filename starts with `UPLOAD_DATE_PREFIX`; the unique-id
+/// suffix is unchanged, so resume detection keeps working.
+pub fn build_output_template(output_dir: &str, media_source_url: Option<&str>, settings: &DownloadSettings) -> String {
+    let date = if settings.prefix_date { UPLOAD_DATE_PREFIX } else { "" };
+
+    if settings.append_unique_id || settings.on_duplicate == "number" {
+        if settings.unique_id_type == "hash" {
+            // Custom short hash - consistent 8-char format across all platforms
+            let unique_id = media_source_url.map(generate_unique_id).unwrap_or_else(|| HASH_ID_PLACEHOLDER.to_string());
+            format!("{}{}{}%(title)s [{}].%(ext)s", output_dir, MAIN_SEPARATOR, date, unique_id)
+        } else {
+            // Native yt-dlp ID - truly idempotent per video (handles URL variations)
+            format!("{}{}{}%(title)s [%(id)s].%(ext)s", output_dir, MAIN_SEPARATOR, date)
+        }
+    } else {
+        format!("{}{}{}%(title)s.%(ext)s", output_dir, MAIN_SEPARATOR, date)
+    }
+}
+
+/// yt-dlp format filter selecting a preferred video codec (None for "any")
+fn codec_format_filter(codec: &str) -> Option<&'static str> {
+    match codec {
+        "h264" => Some("[vcodec^=avc1]"),
+        "vp9" => Some("[vcodec~='^vp0?9']"),
+        "av1" => Some("[vcodec^=av01]"),
+        _ => None,
+    }
+}
+
 /// Build format selection arguments for yt-dlp based on settings
 pub fn build_format_args(settings: &DownloadSettings) -> Vec<String> {
     let mut args = Vec::new();
 
-    if settings.download_mode == "audio" {
+    if settings.avoid_postprocessing {
+        // Single pre-muxed file as served; no merge, remux or audio extraction (all need ffmpeg)
+        let format_str = if settings.download_mode == "audio" {
+            "bestaudio[ext=m4a]/bestaudio".to_string()
+        } else if settings.max_resolution != "no-limit" {
+            let height = settings.max_resolution.trim_end_matches('p');
+            format!("best[ext=mp4][height<={0}]/best[height<={0}]", height)
+        } else {
+            "best[ext=mp4]/best".to_string()
+        };
+        args.push("-f".to_string());
+        args.push(format_str);
+    } else if settings.download_mode == "audio" {
         // Audio-only mode
         args.push("-f".to_string());
         args.push("bestaudio".to_string());
@@ -248,12 +774,17 @@ pub fn build_format_args(settings: &DownloadSettings) -> Vec<String> {
         args.push(settings.audio_quality.clone());
     } else {
         // Video mode
-        let format_str = if settings.max_resolution != "no-limit" {
-            let height = settings.max_resolution.trim_end_matches('p');
-            format!("bestvideo[height<={}]+bestaudio/best[height<={}]", height, height)
+        let height_filter = if settings.max_resolution != "no-limit" {
+            format!("[height<={}]", settings.max_resolution.trim_end_matches('p'))
         } else {
-            String::from("bestvideo+bestaudio/best")
+            String::new()
         };
+        let mut format_str = format!("bestvideo{0}+bestaudio/best{0}", height_filter);
+
+        // Prefer a codec the user's hardware decodes well, falling back to any codec
+        if let Some(codec_filter) = settings.prefer_codec.as_deref().and_then(codec_format_filter) {
+            format_str = format!("bestvideo{}{}+bestaudio/{}", height_filter, codec_filter, format_str);
+        }
 
         args.push("-f".to_string());
         args.push(format_str);
@@ -268,6 +799,73 @@ pub fn build_format_args(settings: &DownloadSettings) -> Vec<String> {
         }
     }
 
+    // Keep the inputs of merge/remux/extraction next to the result; roughly doubles disk usage per item
+    if settings.keep_original && !settings.avoid_postprocessing {
+        args.push("--keep-video".to_string());
+    }
+
+    args
+}
+
+/// Build the `--embed-*` flags; empty when post-processing is avoided, since embedding runs ffmpeg
+pub fn build_embed_args(settings: &DownloadSettings) -> Vec<String> {
+    if settings.avoid_postprocessing {
+        return Vec::new();
+    }
+    ["--embed-thumbnail", "--embed-subs", "--embed-metadata", "--embed-chapters"]
+        .iter()
+        .map(|flag| flag.to_string())
+        .collect()
+}
+
+/// Build `--extractor-args` pairs: the default impersonate arg followed by any user-supplied extras
+pub fn build_extractor_args(extra: Option<&[String]>) -> Vec<String> {
+    let mut args = vec!["--extractor-args".to_string(), DEFAULT_EXTRACTOR_ARGS.to_string()];
+
+    for arg in extra.unwrap_or_default() {
+        args.push("--extractor-args".to_string());
+        args.push(arg.clone());
+    }
+
+    args
+}
+
+/// Build arguments that set embedded metadata fields to fixed values.
+///
+/// yt-dlp has no option to assign a literal: a `--parse-metadata` FROM made only of letters is
+/// read as a field name, so `"Band:%(meta_artist)s"` would embed NA. Each override instead
+/// creates the field empty (`--parse-metadata ":(?P<meta_<key>>)"`) and then fills it with
+/// `--replace-in-metadata meta_<key> "^$" <value>`, backslashes escaped for the replacement.
+/// The `meta_` prefix only affects the embedded metadata, not the output filename. Keys are
+/// emitted in sorted order so the argument list is deterministic. With `parse_title_metadata`,
+/// the `TITLE_METADATA_PATTERN` rule comes first so explicit overrides still win.
+pub fn build_metadata_override_args(settings: &DownloadSettings) -> Vec<String> {
+    let mut args = Vec::new();
+
+    if settings.parse_title_metadata {
+        args.push("--parse-metadata".to_string());
+        args.push(TITLE_METADATA_PATTERN.to_string());
+    }
+
+    let Some(overrides) = &settings.metadata_overrides else {
+        return args;
+    };
+
+    let mut keys: Vec<&String> = overrides.keys().collect();
+    keys.sort();
+
+    for key in keys {
+        // The value is a regex replacement string, where only `\` is special (`\1`, `\g<name>`)
+        let value = overrides[key].replace('\\', "\\\\");
+        let field = format!("meta_{}", key);
+        args.push("--parse-metadata".to_string());
+        args.push(format!(":(?P<{}>)", field));
+        args.push("--replace-in-metadata".to_string());
+        args.push(field);
+        args.push("^$".to_string());
+        args.push(value);
+    }
+
     args
 }
 
@@ -288,9 +886,123 @@ pub fn build_rate_and_size_args(settings: &DownloadSettings) -> Vec<String> {
     args
 }
 
+/// Build `--concurrent-fragments` arguments when the download asks for parallel fragments
+pub fn build_fragment_args(settings: &DownloadSettings) -> Vec<String> {
+    match settings.concurrent_fragments {
+        Some(fragments) => vec!["--concurrent-fragments".to_string(), fragments.to_string()],
+        None => Vec::new(),
+    }
+}
+
+/// Build `--downloader` arguments for the external downloader backend, if any
+pub fn build_downloader_args(settings: &DownloadSettings) -> Vec<String> {
+    if !settings.uses_aria2c() {
+        return Vec::new();
+    }
+
+    let connections = settings.aria2c_connections.unwrap_or(ARIA2C_MAX_CONNECTIONS);
+    vec![
+        "--downloader".to_string(),
+        "aria2c".to_string(),
+        "--downloader-args".to_string(),
+        format!("aria2c:-x{0} -s{0}", connections),
+    ]
+}
+
+/// Pass-through yt-dlp flags (already validated against the allowlist)
+pub fn build_extra_args(settings: &DownloadSettings) -> Vec<String> {
+    settings.extra_args.clone().unwrap_or_default()
+}
+
+/// Build overwrite arguments for when the output file already exists
+pub fn build_duplicate_args(settings: &DownloadSettings) -> Vec<String> {
+    match settings.on_duplicate.as_str() {
+        "overwrite" => vec!["--force-overwrites".to_string()],
+        // "number" relies on the unique ID in the output template; never clobber an existing file
+        _ => vec!["--no-overwrites".to_string()],
+    }
+}
+
+/// Build arguments controlling where intermediate files go (`--paths temp:`) and `.part` usage
+pub fn build_temp_file_args(settings: &DownloadSettings) -> Vec<String> {
+    let mut args = Vec::new();
+
+    if let Some(temp_dir) = &settings.temp_dir {
+        args.push("--paths".to_string());
+        args.push(format!("temp:{}", temp_dir));
+    }
+
+    if settings.no_part {
+        args.push("--no-part".to_string());
+    }
+
+    args
+}
+
+/// Build `--cookies-from-browser` / `--cookies` arguments (validated as mutually exclusive)
+pub fn build_cookie_args(cookies_source: Option<&str>, cookies_file: Option<&str>) -> Vec<String> {
+    if let Some(browser) = cookies_source {
+        vec!["--cookies-from-browser".to_string(), browser.to_string()]
+    } else if let Some(file) = cookies_file {
+        vec!["--cookies".to_string(), file.to_string()]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Build `--dateafter` arguments when an upload date cutoff is set
+pub fn build_date_filter_args(settings: &DownloadSettings) -> Vec<String> {
+    date_filter_args(settings.dateafter.as_deref())
+}
+
+/// `--dateafter` with `--break-on-reject`. During playlist expansion this stops a channel scan at
+/// the first older item instead of walking the rest of the (newest-first) listing; for a single
+/// download it makes a rejected item exit with `DATE_REJECT_EXIT_CODE` rather than succeed empty.
+pub fn date_filter_args(dateafter: Option<&str>) -> Vec<String> {
+    match dateafter {
+        Some(date) => vec!["--dateafter".to_string(), date.to_string(), "--break-on-reject".to_string()],
+        None => Vec::new(),
+    }
+}
+
+/// yt-dlp's exit code when `--break-on-reject` stopped it at an item outside the date filter
+pub const DATE_REJECT_EXIT_CODE: i32 = 101;
+
+/// Build `--write-description` when the description sidecar is wanted. yt-dlp names it from the
+/// `--output` template, so it sits beside the media file as `<title> [<id>].description`.
+pub fn build_sidecar_args(settings: &DownloadSettings) -> Vec<String> {
+    if settings.write_description {
+        vec!["--write-description".to_string()]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Build `--download-archive` so yt-dlp records each finished item's ID, which `resume_playlist`
+/// later filters against. A relative file is placed in `output_dir`, matching `resolve_archive_path`.
+pub fn build_archive_args(settings: &DownloadSettings, output_dir: &str) -> Vec<String> {
+    match settings.download_archive.as_deref().map(str::trim).filter(|file| !file.is_empty()) {
+        Some(file) => {
+            let path = Path::new(output_dir).join(file);
+            vec!["--download-archive".to_string(), path.to_string_lossy().to_string()]
+        }
+        None => Vec::new(),
+    }
+}
+
+/// Build `--trim-filenames` arguments when a filename length limit is set
+pub fn build_filename_length_args(settings: &DownloadSettings) -> Vec<String> {
+    match settings.max_filename_length {
+        Some(len) => vec!["--trim-filenames".to_string(), len.to_string()],
+        None => Vec::new(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::downloader::resume::part_file_marker;
+    use crate::test_dir::TestDir;
 
     /// Create default DownloadSettings for tests - reduces boilerplate
     fn default_settings() -> DownloadSettings {
@@ -305,6 +1017,30 @@ mod tests {
             max_file_size: "unlimited".to_string(),
             append_unique_id: true,
             unique_id_type: "native".to_string(),
+            extractor_args: None,
+            metadata_overrides: None,
+            max_filename_length: None,
+            fallback_to_best: false,
+            downloader_backend: None,
+            prefer_codec: None,
+            temp_dir: None,
+            no_part: false,
+            on_duplicate: "skip".to_string(),
+            extra_args: None,
+            avoid_postprocessing: false,
+            cookies_source: None,
+            cookies_file: None,
+            verify_after_download: false,
+            prefix_date: false,
+            file_mode: None,
+            dateafter: None,
+            parse_title_metadata: false,
+            concurrent_fragments: None,
+            write_description: false,
+            flatten_output: false,
+            keep_original: false,
+            download_archive: None,
+            aria2c_connections: None,
         }
     }
 
@@ -357,6 +1093,65 @@ mod tests {
         assert_eq!(args[format_idx + 1], "bestvideo[height<=1080]+bestaudio/best[height<=1080]");
     }
 
+    #[test]
+    fn test_build_format_args_prefer_h264() {
+        let mut settings = default_settings();
+        settings.prefer_codec = Some("h264".to_string());
+        settings.max_resolution = "1080p".to_string();
+
+        let args = build_format_args(&settings);
+
+        let format_idx = args.iter().position(|a| a == "-f").unwrap();
+        assert_eq!(
+            args[format_idx + 1],
+            "bestvideo[height<=1080][vcodec^=avc1]+bestaudio/bestvideo[height<=1080]+bestaudio/best[height<=1080]"
+        );
+    }
+
+    #[test]
+    fn test_build_format_args_prefer_any_is_unbiased() {
+        let mut settings = default_settings();
+        settings.prefer_codec = Some("any".to_string());
+
+        let args = build_format_args(&settings);
+
+        let format_idx = args.iter().position(|a| a == "-f").unwrap();
+        assert_eq!(args[format_idx + 1], "bestvideo+bestaudio/best");
+    }
+
+    #[test]
+    fn test_validate_prefer_codec() {
+        let mut settings = default_settings();
+        for codec in ["h264", "vp9", "av1", "any"] {
+            settings.prefer_codec = Some(codec.to_string());
+            assert!(validate_settings(&settings).is_ok(), "{codec} should be accepted");
+        }
+
+        settings.prefer_codec = Some("hevc".to_string());
+        assert!(validate_settings(&settings).is_err());
+    }
+
+    #[test]
+    fn test_build_format_args_keep_original() {
+        let mut settings = default_settings();
+        settings.video_format = "mkv".to_string();
+        assert!(!build_format_args(&settings).contains(&"--keep-video".to_string()));
+
+        settings.keep_original = true;
+        let args = build_format_args(&settings);
+        assert!(args.contains(&"--remux-video".to_string()), "remux still runs; both files are kept");
+        assert_eq!(args.last().unwrap(), "--keep-video");
+
+        settings.download_mode = "audio".to_string();
+        let args = build_format_args(&settings);
+        assert!(args.contains(&"--extract-audio".to_string()));
+        assert!(args.contains(&"--keep-video".to_string()));
+
+        // Nothing is post-processed, so there is no separate original to keep
+        settings.avoid_postprocessing = true;
+        assert!(!build_format_args(&settings).contains(&"--keep-video".to_string()));
+    }
+
     #[test]
     fn test_build_format_args_video_container_mp4() {
         let mut settings = default_settings();
@@ -415,6 +1210,46 @@ mod tests {
         assert!(!args.contains(&"--remux-video".to_string()));
     }
 
+    #[test]
+    fn test_build_format_args_avoid_postprocessing_video() {
+        let mut settings = default_settings();
+        settings.video_format = "mkv".to_string();
+        settings.prefer_codec = Some("h264".to_string());
+        settings.avoid_postprocessing = true;
+
+        let args = build_format_args(&settings);
+        assert_eq!(args, vec!["-f", "best[ext=mp4]/best"]);
+
+        settings.max_resolution = "720p".to_string();
+        let args = build_format_args(&settings);
+        assert_eq!(args, vec!["-f", "best[ext=mp4][height<=720]/best[height<=720]"]);
+    }
+
+    #[test]
+    fn test_build_format_args_avoid_postprocessing_audio() {
+        let mut settings = default_settings();
+        settings.download_mode = "audio".to_string();
+        settings.audio_format = "mp3".to_string();
+        settings.avoid_postprocessing = true;
+
+        let args = build_format_args(&settings);
+        assert_eq!(args, vec!["-f", "bestaudio[ext=m4a]/bestaudio"]);
+        assert!(!args.contains(&"--extract-audio".to_string()));
+        assert!(!args.contains(&"--audio-format".to_string()));
+    }
+
+    #[test]
+    fn test_build_embed_args() {
+        let mut settings = default_settings();
+        assert_eq!(
+            build_embed_args(&settings),
+            vec!["--embed-thumbnail", "--embed-subs", "--embed-metadata", "--embed-chapters"]
+        );
+
+        settings.avoid_postprocessing = true;
+        assert!(build_embed_args(&settings).is_empty());
+    }
+
     #[test]
     fn test_validate_settings_rate_limit_and_size() {
         let mut settings = default_settings();
@@ -543,6 +1378,25 @@ mod tests {
         assert!(!args.iter().any(|arg| arg == "--max-filesize"));
     }
 
+    #[test]
+    fn test_concurrent_fragments_capped_and_built() {
+        let mut settings = default_settings();
+        settings.cap_concurrent_fragments(2);
+        assert!(build_fragment_args(&settings).is_empty(), "no -N unless requested");
+
+        settings.concurrent_fragments = Some(8);
+        assert!(validate_settings(&settings).is_ok());
+        settings.cap_concurrent_fragments(3);
+        assert_eq!(build_fragment_args(&settings), vec!["--concurrent-fragments", "3"]);
+        settings.cap_concurrent_fragments(16);
+        assert_eq!(settings.concurrent_fragments, Some(3), "the budget only lowers");
+
+        settings.concurrent_fragments = Some(0);
+        assert!(validate_settings(&settings).is_err());
+        settings.concurrent_fragments = Some(MAX_CONCURRENT_FRAGMENTS + 1);
+        assert!(validate_settings(&settings).is_err());
+    }
+
     #[test]
     fn test_build_rate_and_size_args_with_max_filesize() {
         let mut settings = default_settings();
@@ -589,10 +1443,501 @@ mod tests {
         assert!(!args.iter().any(|arg| arg == "--max-filesize"));
     }
 
+    #[test]
+    fn test_build_extractor_args_default_only() {
+        let args = build_extractor_args(None);
+        assert_eq!(args, vec!["--extractor-args".to_string(), DEFAULT_EXTRACTOR_ARGS.to_string()]);
+    }
+
+    #[test]
+    fn test_build_extractor_args_preserves_default_with_extras() {
+        let extra = vec!["youtube:player_client=android".to_string()];
+        let args = build_extractor_args(Some(&extra));
+
+        // Default impersonate arg always comes first
+        assert_eq!(args[0], "--extractor-args");
+        assert_eq!(args[1], DEFAULT_EXTRACTOR_ARGS);
+        assert_eq!(args[2], "--extractor-args");
+        assert_eq!(args[3], "youtube:player_client=android");
+        assert_eq!(args.len(), 4);
+    }
+
+    #[test]
+    fn test_validate_settings_extractor_args() {
+        let mut settings = default_settings();
+
+        settings.extractor_args = Some(vec!["youtube:player_client=android,web".to_string()]);
+        assert!(validate_settings(&settings).is_ok());
+
+        settings.extractor_args = Some(vec![]);
+        assert!(validate_settings(&settings).is_ok());
+
+        for invalid in [
+            "youtube",
+            "youtube:player_client",
+            ":a=b",
+            "youtube:=b",
+            "youtube:a=",
+            "you tube:a=b",
+            "youtube:a=b;rm",
+        ] {
+            settings.extractor_args = Some(vec![invalid.to_string()]);
+            let result = validate_settings(&settings);
+            assert!(result.is_err(), "Should reject extractor arg: {}", invalid);
+            assert!(result.unwrap_err().to_string().contains("extractor_args"));
+        }
+    }
+
+    #[test]
+    fn test_summary_video_mode() {
+        let mut settings = default_settings();
+        settings.max_resolution = "1080p".to_string();
+        settings.video_format = "mp4".to_string();
+        settings.audio_format = "mp3".to_string();
+
+        let summary = settings.summary();
+        assert_eq!(summary.mode, "video");
+        assert_eq!(summary.resolution.as_deref(), Some("1080p"));
+        assert_eq!(summary.format, "mp4");
+    }
+
+    #[test]
+    fn test_summary_audio_mode() {
+        let mut settings = default_settings();
+        settings.download_mode = "audio".to_string();
+        settings.max_resolution = "720p".to_string();
+        settings.audio_format = "opus".to_string();
+
+        let summary = settings.summary();
+        assert_eq!(summary.mode, "audio");
+        assert_eq!(summary.resolution, None);
+        assert_eq!(summary.format, "opus");
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json, serde_json::json!({"mode": "audio", "resolution": null, "format": "opus"}));
+    }
+
+    #[test]
+    fn test_validate_downloader_backend() {
+        let mut settings = default_settings();
+        for backend in ["native", "aria2c"] {
+            settings.downloader_backend = Some(backend.to_string());
+            assert!(validate_settings(&settings).is_ok(), "{backend} should be accepted");
+        }
+
+        settings.downloader_backend = Some("curl".to_string());
+        assert!(validate_settings(&settings).is_err());
+    }
+
+    #[test]
+    fn test_build_downloader_args() {
+        let mut settings = default_settings();
+        assert!(build_downloader_args(&settings).is_empty());
+
+        settings.downloader_backend = Some("native".to_string());
+        assert!(build_downloader_args(&settings).is_empty());
+
+        settings.downloader_backend = Some("aria2c".to_string());
+        assert_eq!(
+            build_downloader_args(&settings),
+            vec!["--downloader", "aria2c", "--downloader-args", "aria2c:-x16 -s16"]
+        );
+
+        // The fragment budget caps aria2c's connections per file too
+        settings.cap_concurrent_fragments(3);
+        assert_eq!(build_downloader_args(&settings)[3], "aria2c:-x3 -s3");
+        settings.cap_concurrent_fragments(40);
+        assert_eq!(build_downloader_args(&settings)[3], "aria2c:-x16 -s16");
+    }
+
+    #[test]
+    fn test_build_output_template_native_id() {
+        let settings = default_settings();
+        let template = build_output_template("/dl", Some("https://example.com/v"), &settings);
+        assert_eq!(template, format!("/dl{}%(title)s [%(id)s].%(ext)s", MAIN_SEPARATOR));
+    }
+
+    #[test]
+    fn test_build_output_template_hash_id() {
+        let mut settings = default_settings();
+        settings.unique_id_type = "hash".to_string();
+        let url = "https://example.com/v";
+
+        let template = build_output_template("/dl", Some(url), &settings);
+        assert_eq!(template, format!("/dl{}%(title)s [{}].%(ext)s", MAIN_SEPARATOR, generate_unique_id(url)));
+
+        let preview = build_output_template("/dl", None, &settings);
+        assert_eq!(preview, format!("/dl{}%(title)s [{}].%(ext)s", MAIN_SEPARATOR, HASH_ID_PLACEHOLDER));
+    }
+
+    #[test]
+    fn test_build_output_template_prefix_date() {
+        let url = "https://example.com/v";
+        let mut settings = default_settings();
+        settings.prefix_date = true;
+
+        let native = build_output_template("/dl", Some(url), &settings);
+        assert_eq!(native, format!("/dl{}%(upload_date>%Y-%m-%d)s - %(title)s [%(id)s].%(ext)s", MAIN_SEPARATOR));
+
+        settings.unique_id_type = "hash".to_string();
+        let hashed = build_output_template("/dl", Some(url), &settings);
+        assert_eq!(
+            hashed,
+            format!("/dl{}%(upload_date>%Y-%m-%d)s - %(title)s [{}].%(ext)s", MAIN_SEPARATOR, generate_unique_id(url))
+        );
+        assert!(hashed.contains(&part_file_marker(url)), "resume marker survives the date prefix");
+
+        settings.append_unique_id = false;
+        let plain = build_output_template("/dl", Some(url), &settings);
+        assert_eq!(plain, format!("/dl{}%(upload_date>%Y-%m-%d)s - %(title)s.%(ext)s", MAIN_SEPARATOR));
+    }
+
+    #[test]
+    fn test_build_output_template_without_unique_id() {
+        let mut settings = default_settings();
+        settings.append_unique_id = false;
+        settings.unique_id_type = "hash".to_string();
+
+        let template = build_output_template("/dl", Some("https://example.com/v"), &settings);
+        assert_eq!(template, format!("/dl{}%(title)s.%(ext)s", MAIN_SEPARATOR));
+    }
+
+    #[test]
+    fn test_validate_extra_args_accepts_allowlisted_flags() {
+        let args: Vec<String> = ["--geo-bypass", "--retries", "10", "--sub-langs=en,de", "--sleep-interval", "2"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(validate_extra_args(&args).is_ok());
+
+        let mut settings = default_settings();
+        settings.extra_args = Some(args.clone());
+        assert!(validate_settings(&settings).is_ok());
+        assert_eq!(build_extra_args(&settings), args);
+        assert!(build_extra_args(&default_settings()).is_empty());
+    }
+
+    #[test]
+    fn test_validate_extra_args_rejected_in_safe_mode() {
+        let args = vec!["--geo-bypass".to_string()];
+        assert!(validate_extra_args_allowed(&args, false).is_ok());
+
+        let err = validate_extra_args_allowed(&args, true).unwrap_err();
+        assert!(err.to_string().contains("safe mode"));
+        // Nothing to pass through, nothing to refuse
+        assert!(validate_extra_args_allowed(&[], true).is_ok());
+    }
+
+    #[test]
+    fn test_validate_extra_args_rejects_dangerous_flags() {
+        let rejected: &[&[&str]] = &[
+            &["--exec", "rm -rf ~"],
+            &["-o", "/etc/passwd"],
+            &["--output=/tmp/x"],
+            &["--paths", "home:/"],
+            &["--config-location", "/tmp/evil.conf"],
+            &["--batch-file", "urls.txt"],
+            &["--no-check-certificates"],
+            // Would bypass the fragment budget; use concurrent_fragments
+            &["--concurrent-fragments", "64"],
+            // Value smuggling another flag, or shell metacharacters
+            &["--retries", "--exec"],
+            &["--sub-langs", "en;rm"],
+            // Missing value, or a value on a switch
+            &["--retries"],
+            &["--geo-bypass=yes"],
+        ];
+
+        for args in rejected {
+            let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+            assert!(validate_extra_args(&args).is_err(), "{args:?} should be rejected");
+        }
+
+        let mut settings = default_settings();
+        settings.extra_args = Some(vec!["--exec".to_string(), "echo".to_string()]);
+        assert!(validate_settings(&settings).unwrap_err().to_string().contains("--exec"));
+    }
+
+    #[test]
+    fn test_build_duplicate_args_per_mode() {
+        let mut settings = default_settings();
+        assert_eq!(build_duplicate_args(&settings), vec!["--no-overwrites"]);
+
+        settings.on_duplicate = "overwrite".to_string();
+        assert_eq!(build_duplicate_args(&settings), vec!["--force-overwrites"]);
+
+        settings.on_duplicate = "number".to_string();
+        assert_eq!(build_duplicate_args(&settings), vec!["--no-overwrites"]);
+    }
+
+    #[test]
+    fn test_on_duplicate_number_forces_unique_id() {
+        let mut settings = default_settings();
+        settings.append_unique_id = false;
+        assert!(!build_output_template("/dl", None, &settings).contains("[%(id)s]"));
+
+        settings.on_duplicate = "number".to_string();
+        assert!(build_output_template("/dl", None, &settings).contains("[%(id)s]"));
+    }
+
+    #[test]
+    fn test_validate_on_duplicate() {
+        let mut settings = default_settings();
+        for mode in ["skip", "overwrite", "number"] {
+            settings.on_duplicate = mode.to_string();
+            assert!(validate_settings(&settings).is_ok(), "{mode}");
+        }
+
+        settings.on_duplicate = "rename".to_string();
+        assert!(validate_settings(&settings).unwrap_err().to_string().contains("on_duplicate"));
+    }
+
+    #[test]
+    fn test_build_temp_file_args() {
+        let mut settings = default_settings();
+        assert!(build_temp_file_args(&settings).is_empty());
+
+        settings.no_part = true;
+        assert_eq!(build_temp_file_args(&settings), vec!["--no-part"]);
+
+        settings.temp_dir = Some("/mnt/scratch".to_string());
+        assert_eq!(build_temp_file_args(&settings), vec!["--paths", "temp:/mnt/scratch", "--no-part"]);
+
+        settings.no_part = false;
+        assert_eq!(build_temp_file_args(&settings), vec!["--paths", "temp:/mnt/scratch"]);
+    }
+
+    #[test]
+    fn test_validate_temp_dir() {
+        let scratch = TestDir::new("temp-dir");
+        let dir = scratch.join("temp");
+        let dir_str = dir.to_str().unwrap().to_string();
+
+        assert!(validate_temp_dir("").is_err());
+        assert!(validate_temp_dir(&dir_str).is_err(), "missing directory should be rejected");
+
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(validate_temp_dir(&dir_str).is_ok());
+
+        let mut settings = default_settings();
+        settings.temp_dir = Some(dir_str);
+        assert!(validate_settings(&settings).is_ok());
+    }
+
+    #[test]
+    fn test_validate_cookies_mutually_exclusive() {
+        let dir = TestDir::new("cookies");
+        let file = dir.join("cookies.txt");
+        std::fs::write(&file, "# Netscape HTTP Cookie File\n").unwrap();
+        let file_str = file.to_str().unwrap().to_string();
+
+        let mut settings = default_settings();
+        settings.cookies_file = Some(file_str.clone());
+        assert!(validate_settings(&settings).is_ok());
+
+        settings.cookies_source = Some("firefox".to_string());
+        let err = validate_settings(&settings).unwrap_err();
+        assert!(err.to_string().contains("mutually exclusive"), "{}", err);
+
+        settings.cookies_file = None;
+        assert!(validate_settings(&settings).is_ok());
+    }
+
+    #[test]
+    fn test_validate_cookies_file_and_browser() {
+        let dir = TestDir::new("cookies-dir");
+        assert!(validate_cookies(None, Some("/nonexistent/remedia/cookies.txt")).is_err());
+        assert!(validate_cookies(None, dir.path().to_str()).is_err(), "a directory is not a cookies file");
+        assert!(validate_cookies(Some("netscape"), None).is_err());
+        assert!(validate_cookies(Some("chrome"), None).is_ok());
+        assert!(validate_cookies(None, None).is_ok());
+    }
+
+    #[test]
+    fn test_build_cookie_args() {
+        assert!(build_cookie_args(None, None).is_empty());
+        assert_eq!(build_cookie_args(Some("firefox"), None), vec!["--cookies-from-browser", "firefox"]);
+        assert_eq!(build_cookie_args(None, Some("/home/me/cookies.txt")), vec!["--cookies", "/home/me/cookies.txt"]);
+    }
+
+    #[test]
+    fn test_validate_max_filename_length_bounds() {
+        let mut settings = default_settings();
+        for len in [MIN_FILENAME_LENGTH, 100, MAX_FILENAME_LENGTH] {
+            settings.max_filename_length = Some(len);
+            assert!(validate_settings(&settings).is_ok(), "{len} should be accepted");
+        }
+        for len in [0, MIN_FILENAME_LENGTH - 1, MAX_FILENAME_LENGTH + 1] {
+            settings.max_filename_length = Some(len);
+            assert!(validate_settings(&settings).is_err(), "{len} should be rejected");
+        }
+    }
+
+    #[test]
+    fn test_build_sidecar_args() {
+        let mut settings = default_settings();
+        assert!(build_sidecar_args(&settings).is_empty());
+
+        settings.write_description = true;
+        assert_eq!(build_sidecar_args(&settings), vec!["--write-description"]);
+    }
+
+    #[test]
+    fn test_build_archive_args_resolves_relative_to_output_dir() {
+        let mut settings = default_settings();
+        assert!(build_archive_args(&settings, "/media/Mix").is_empty());
+
+        settings.download_archive = Some("  ".to_string());
+        assert!(build_archive_args(&settings, "/media/Mix").is_empty());
+
+        settings.download_archive = Some("archive.txt".to_string());
+        let expected = Path::new("/media/Mix").join("archive.txt").to_string_lossy().to_string();
+        assert_eq!(build_archive_args(&settings, "/media/Mix"), vec!["--download-archive".to_string(), expected]);
+
+        let absolute = std::env::temp_dir().join("archive.txt").to_string_lossy().to_string();
+        settings.download_archive = Some(absolute.clone());
+        assert_eq!(build_archive_args(&settings, "/media/Mix"), vec!["--download-archive".to_string(), absolute]);
+    }
+
+    #[test]
+    fn test_build_filename_length_args() {
+        let mut settings = default_settings();
+        assert!(build_filename_length_args(&settings).is_empty());
+
+        settings.max_filename_length = Some(120);
+        assert_eq!(build_filename_length_args(&settings), vec!["--trim-filenames", "120"]);
+    }
+
+    #[test]
+    fn test_build_metadata_override_args_none() {
+        let settings = default_settings();
+        assert!(build_metadata_override_args(&settings).is_empty());
+    }
+
+    #[test]
+    fn test_build_metadata_override_args_per_key() {
+        for key in METADATA_OVERRIDE_KEYS {
+            let mut settings = default_settings();
+            settings.metadata_overrides = Some(HashMap::from([(key.to_string(), "Value".to_string())]));
+
+            let field = format!("meta_{}", key);
+            let args = build_metadata_override_args(&settings);
+            assert_eq!(
+                args,
+                vec![
+                    "--parse-metadata".to_string(),
+                    format!(":(?P<{}>)", field),
+                    "--replace-in-metadata".to_string(),
+                    field,
+                    "^$".to_string(),
+                    "Value".to_string(),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_build_metadata_override_args_sorted_and_escaped() {
+        let mut settings = default_settings();
+        settings.metadata_overrides = Some(HashMap::from([
+            ("title".to_string(), "Live: 100% %(id)s \\1 Raw".to_string()),
+            ("artist".to_string(), "Band".to_string()),
+        ]));
+
+        // Values pass through literally; only backslashes are escaped for the regex replacement
+        let args = build_metadata_override_args(&settings);
+        assert_eq!(
+            args,
+            vec![
+                "--parse-metadata",
+                ":(?P<meta_artist>)",
+                "--replace-in-metadata",
+                "meta_artist",
+                "^$",
+                "Band",
+                "--parse-metadata",
+                ":(?P<meta_title>)",
+                "--replace-in-metadata",
+                "meta_title",
+                "^$",
+                "Live: 100% %(id)s \\\\1 Raw",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_metadata_override_args_parse_title() {
+        let mut settings = default_settings();
+        settings.download_mode = "audio".to_string();
+        settings.parse_title_metadata = true;
+        assert_eq!(build_metadata_override_args(&settings), vec!["--parse-metadata", "title:%(artist)s - %(title)s"]);
+        assert!(validate_settings(&settings).is_ok());
+
+        // Explicit overrides are applied after the parsed fields
+        settings.metadata_overrides = Some(HashMap::from([("artist".to_string(), "Band".to_string())]));
+        let args = build_metadata_override_args(&settings);
+        assert_eq!(&args[..2], ["--parse-metadata", "title:%(artist)s - %(title)s"]);
+        assert_eq!(
+            &args[2..],
+            ["--parse-metadata", ":(?P<meta_artist>)", "--replace-in-metadata", "meta_artist", "^$", "Band"]
+        );
+    }
+
+    #[test]
+    fn test_validate_settings_parse_title_metadata_requires_audio() {
+        let mut settings = default_settings();
+        settings.parse_title_metadata = true;
+        assert!(validate_settings(&settings).is_err());
+    }
+
+    #[test]
+    fn test_validate_settings_metadata_overrides() {
+        let mut settings = default_settings();
+
+        settings.metadata_overrides = Some(HashMap::from([("album".to_string(), "Greatest Hits".to_string())]));
+        assert!(validate_settings(&settings).is_ok());
+
+        settings.metadata_overrides = Some(HashMap::from([("genre".to_string(), "Rock".to_string())]));
+        let result = validate_settings(&settings);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("metadata_overrides"));
+
+        settings.metadata_overrides = Some(HashMap::from([("title".to_string(), "Line\nBreak".to_string())]));
+        assert!(validate_settings(&settings).is_err());
+    }
+
     // ========================================
     // URL Validation Tests
     // ========================================
 
+    #[test]
+    fn test_validate_batch_partitions_mixed_rows() {
+        let item = |media_idx: i32, url: &str| BatchItem { media_idx, url: url.to_string() };
+        let items = [
+            item(0, "https://example.com/a"),
+            item(1, "not a url"),
+            item(2, "https://example.com/b?x=1&y=2"),
+            item(-1, "https://example.com/c"),
+            item(3, "https://example.com/$(rm)"),
+        ];
+
+        let result = validate_batch(&items, &default_settings());
+        assert_eq!(result.valid, vec![0, 2]);
+        let invalid: Vec<(i32, &str)> = result.invalid.iter().map(|(idx, e)| (*idx, e.code)).collect();
+        assert_eq!(
+            invalid,
+            vec![(1, "E_VAL_INVALID_URL"), (-1, "E_VAL_INVALID_MEDIA_IDX"), (3, "E_VAL_INVALID_URL")]
+        );
+
+        // Invalid shared settings reject every row with the same error
+        let mut settings = default_settings();
+        settings.download_mode = "podcast".to_string();
+        let result = validate_batch(&items, &settings);
+        assert!(result.valid.is_empty());
+        assert_eq!(result.invalid.len(), items.len());
+        assert!(result.invalid.iter().all(|(_, e)| e.code == "E_VAL_INVALID_SETTINGS"));
+    }
+
     #[test]
     fn test_validate_url_valid_http() {
         assert!(validate_url("http://example.com").is_ok());
@@ -673,6 +2018,19 @@ mod tests {
         assert!(validate_url("https://example.com/video<test>").is_err());
     }
 
+    #[test]
+    fn test_validate_url_allows_ampersand_only_in_query() {
+        // `&` separates query parameters, so it is allowed after the `?`
+        assert!(validate_url("https://www.youtube.com/watch?v=abc&list=PL123&index=2").is_ok());
+        assert!(validate_url("https://example.com/?&").is_ok());
+        // ...but nowhere before it
+        assert!(validate_url("https://example.com/a&b?x=1").is_err());
+        assert!(validate_url("https://example.com&ls").is_err());
+        // Other metacharacters stay rejected inside the query
+        assert!(validate_url("https://example.com/v?a=1&b=$(id)").is_err());
+        assert!(validate_url("https://example.com/v?a=1;b=2").is_err());
+    }
+
     #[test]
     fn test_validate_url_allows_safe_special_chars() {
         // Query params with = and # are fine (common in URLs)
@@ -714,6 +2072,12 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("too long"));
     }
 
+    #[test]
+    fn test_validate_output_location_control_chars() {
+        assert!(validate_output_location("/tmp/a\0b").is_err());
+        assert!(validate_output_location("/tmp/a\nb").is_err());
+    }
+
     // ========================================
     // Settings Field Validation Tests
     // ========================================
@@ -846,4 +2210,108 @@ mod tests {
         assert_eq!(settings.video_quality, "best");
         assert_eq!(settings.max_resolution, "no-limit");
     }
+
+    #[test]
+    fn test_is_valid_yyyymmdd() {
+        assert!(is_valid_yyyymmdd("20240131"));
+        assert!(is_valid_yyyymmdd("19991201"));
+        assert!(is_valid_yyyymmdd("20240229"), "leap year");
+        assert!(is_valid_yyyymmdd("20000229"), "divisible by 400");
+        for bad in ["2024-01-31", "2024013", "202401311", "20241301", "20240100", "20240132", "now-1week", ""] {
+            assert!(!is_valid_yyyymmdd(bad), "{bad}");
+        }
+        for bad in ["20240231", "20230229", "19000229", "20240431"] {
+            assert!(!is_valid_yyyymmdd(bad), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_validate_settings_dateafter() {
+        let mut settings = default_settings();
+        settings.dateafter = Some("20240101".to_string());
+        assert!(validate_settings(&settings).is_ok());
+
+        settings.dateafter = Some("01/01/2024".to_string());
+        assert!(validate_settings(&settings).is_err());
+    }
+
+    #[test]
+    fn test_build_date_filter_args() {
+        let mut settings = default_settings();
+        assert!(build_date_filter_args(&settings).is_empty());
+
+        settings.dateafter = Some("20240101".to_string());
+        assert_eq!(build_date_filter_args(&settings), vec!["--dateafter", "20240101", "--break-on-reject"]);
+    }
+
+    #[test]
+    fn test_parse_file_mode() {
+        assert_eq!(parse_file_mode("644"), Some(0o644));
+        assert_eq!(parse_file_mode("0640"), Some(0o640));
+        assert_eq!(parse_file_mode("2775"), Some(0o2775));
+        for bad in ["", "64", "00644", "648", "rw-", "-644", "+64"] {
+            assert_eq!(parse_file_mode(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_validate_settings_file_mode() {
+        let mut settings = default_settings();
+        settings.file_mode = Some("664".to_string());
+        assert!(validate_settings(&settings).is_ok());
+
+        settings.file_mode = Some("999".to_string());
+        assert!(validate_settings(&settings).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_apply_file_mode_sets_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TestDir::new("mode");
+        let path = dir.join("file");
+        std::fs::write(&path, b"x").unwrap();
+        apply_file_mode(&path, 0o640).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+
+    #[cfg(not(unix))]
+    #[test]
+    fn test_apply_file_mode_is_noop_without_unix() {
+        // Not even touched: a missing file is fine
+        let dir = TestDir::new("mode");
+        let path = dir.join("file");
+        assert!(apply_file_mode(&path, 0o644).is_ok());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_check_remux_compat() {
+        let cases = [
+            ("webm", "mp4a.40.2", false),
+            ("webm", "avc1.64001F", false),
+            ("webm", "vp09.00.51.08", true),
+            ("webm", "opus", true),
+            ("mp4", "avc1.64001F", true),
+            ("mp4", "mp4a.40.2", true),
+            ("mp4", "vorbis", false),
+            ("mp4", "vp8", false),
+            ("mp4", "av01.0.08M.08", true),
+            ("mkv", "vorbis", true),
+            ("best", "mp4a.40.2", true),
+        ];
+        for (format, codec, expected) in cases {
+            assert_eq!(check_remux_compat(format, codec), expected, "{format} <- {codec}");
+        }
+    }
+
+    #[test]
+    fn test_check_remux_compat_unknown_codecs_pass() {
+        assert!(check_remux_compat("webm", "none"));
+        assert!(check_remux_compat("mp4", "theora"));
+        assert!(check_remux_compat("webm", ""));
+        assert!(check_remux_compat("mp4", " AVC1.4d401e "));
+    }
 }
@@ -3,26 +3,44 @@
 //! Handles spawning yt-dlp processes, monitoring their output, and managing
 //! cancellation via atomic flags.
 
-use std::collections::HashMap;
-use std::path;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
 use std::process::Stdio;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use serde::Serialize;
 use serde_json::json;
 use tauri::{Emitter, Manager, WebviewWindow};
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::BufReader;
 use tokio::process::Command;
 
 use crate::download_queue::with_queue;
+use crate::error::{classify_ytdlp_stderr, ErrorCode};
 use crate::events::*;
 use crate::logging::append_yt_dlp_log;
 use crate::remote_control::{broadcast_if_active, broadcast_remote_event};
 
-use super::events::emit_download_error;
-use super::progress::parse_progress_percent;
-use super::settings::{build_format_args, build_rate_and_size_args, generate_unique_id, DownloadSettings};
-use super::{notify_queue, progress::should_emit_stderr};
+use super::events::{emit_download_error, DownloadEventSink};
+use super::progress::{
+    is_format_unavailable_error, is_max_filesize_skip, parse_progress_percent, should_fallback_to_best,
+};
+use super::ytdlp::{probe_aria2c_version, LossyLines};
+use super::settings::{
+    apply_file_mode, build_archive_args, build_cookie_args, build_date_filter_args, build_downloader_args,
+    build_duplicate_args, build_embed_args, build_extra_args, build_extractor_args, build_filename_length_args,
+    build_format_args, build_fragment_args, build_metadata_override_args, build_output_template,
+    build_rate_and_size_args, build_sidecar_args, build_temp_file_args, parse_file_mode, DownloadSettings,
+    DATE_REJECT_EXIT_CODE,
+};
+use super::verify::{verify_download, VerifyOutcome};
+use super::mock::{is_mock_mode, mock_step_interval, synthetic_progress, MOCK_PROGRESS_STEPS};
+use super::{is_ffmpeg_available, notify_queue};
+use super::progress::{
+    detect_output_path, detect_postprocess_phase, parse_progress_eta, parse_progress_speed, should_emit_stderr,
+    DownloadStats, ProgressHistory, ProgressSample, StderrThrottle,
+};
 
 /// Interval in milliseconds to check for cancellation requests
 const CANCELLATION_POLL_INTERVAL_MS: u64 = 100;
@@ -30,16 +48,212 @@ const CANCELLATION_POLL_INTERVAL_MS: u64 = 100;
 /// Debounce interval for progress updates
 const PROGRESS_DEBOUNCE_MS: u128 = 100;
 
+/// Raw yt-dlp lines kept per download for `get_download_output`
+const OUTPUT_TAIL_LINES: usize = 200;
+
+/// Progress samples kept per download for `get_progress_history` (10 minutes at one per second)
+const PROGRESS_HISTORY_SAMPLES: usize = 600;
+
+/// Minimum gap between two progress history samples; 100% is always recorded
+const PROGRESS_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Why a download was cancelled, reported in `EVT_DOWNLOAD_CANCELLED` as `[media_idx, reason]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CancelReason {
+    /// The user cancelled this download
+    User = 1,
+    /// Cancelled as part of cancel-all
+    All = 2,
+    /// Another download ran out of disk space on the way (see `pause_for_disk_full`)
+    DiskLow = 3,
+    /// Running longer than `cancel_stale_downloads` allowed
+    TooOld = 4,
+    /// Stopped and requeued because the concurrency limit was lowered (strict overflow mode)
+    Paused = 5,
+}
+
+impl CancelReason {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(Self::User),
+            2 => Some(Self::All),
+            3 => Some(Self::DiskLow),
+            4 => Some(Self::TooOld),
+            5 => Some(Self::Paused),
+            _ => None,
+        }
+    }
+}
+
+/// Cancellation flag shared with a running download. Holds the first reason requested.
+#[derive(Debug, Default)]
+pub struct CancelFlag(AtomicU8);
+
+impl CancelFlag {
+    /// Request cancellation; a reason already set is kept
+    pub fn request(&self, reason: CancelReason) {
+        let _ = self.0.compare_exchange(0, reason as u8, Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    /// The requested cancellation reason, if any
+    pub fn reason(&self) -> Option<CancelReason> {
+        CancelReason::from_u8(self.0.load(Ordering::Relaxed))
+    }
+}
+
 // Track cancellation flags for active downloads
-static DOWNLOAD_CANCEL_FLAGS: LazyLock<Mutex<HashMap<i32, Arc<AtomicBool>>>> =
+static DOWNLOAD_CANCEL_FLAGS: LazyLock<Mutex<HashMap<i32, Arc<CancelFlag>>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
 
+// Latest reported speed (bytes/sec) of each running download, for concurrency auto-tuning
+static DOWNLOAD_SPEEDS: LazyLock<Mutex<HashMap<i32, f64>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Process id of each running download's yt-dlp, so orphan cleanup can tell them apart
+static DOWNLOAD_PIDS: LazyLock<Mutex<HashMap<i32, u32>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Latest reported seconds remaining per active download
+static DOWNLOAD_ETAS: LazyLock<Mutex<HashMap<i32, u64>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Recent raw yt-dlp output per download. Kept after a failure or cancel for troubleshooting,
+/// dropped on success and when the same index starts again.
+static DOWNLOAD_OUTPUT: LazyLock<Mutex<HashMap<i32, OutputTail>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Progress samples per download, kept after it finishes so its speed can still be charted.
+/// Reset when the same index starts again.
+static PROGRESS_HISTORY: LazyLock<Mutex<HashMap<i32, ProgressHistory>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Bounded buffer of the most recent output lines; the oldest line is dropped once full
+#[derive(Debug)]
+pub struct OutputTail {
+    lines: VecDeque<String>,
+    capacity: usize,
+}
+
+impl OutputTail {
+    pub fn new(capacity: usize) -> Self {
+        Self { lines: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    pub fn push(&mut self, line: &str) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line.to_string());
+    }
+
+    /// Up to `limit` most recent lines, oldest first
+    pub fn last(&self, limit: usize) -> Vec<String> {
+        self.lines.iter().skip(self.lines.len().saturating_sub(limit)).cloned().collect()
+    }
+}
+
+fn record_output_line(media_idx: i32, line: &str) {
+    DOWNLOAD_OUTPUT
+        .lock()
+        .unwrap()
+        .entry(media_idx)
+        .or_insert_with(|| OutputTail::new(OUTPUT_TAIL_LINES))
+        .push(line);
+}
+
+fn clear_output(media_idx: i32) {
+    DOWNLOAD_OUTPUT.lock().unwrap().remove(&media_idx);
+}
+
+/// The last `limit` raw yt-dlp lines of a running or failed download (empty if none are kept)
+pub fn download_output(media_idx: i32, limit: usize) -> Vec<String> {
+    DOWNLOAD_OUTPUT.lock().unwrap().get(&media_idx).map(|tail| tail.last(limit)).unwrap_or_default()
+}
+
+fn record_progress_sample(media_idx: i32, sample: ProgressSample) {
+    PROGRESS_HISTORY
+        .lock()
+        .unwrap()
+        .entry(media_idx)
+        .or_insert_with(|| ProgressHistory::new(PROGRESS_HISTORY_SAMPLES))
+        .push(sample);
+}
+
+fn clear_progress_history(media_idx: i32) {
+    PROGRESS_HISTORY.lock().unwrap().remove(&media_idx);
+}
+
+/// Progress samples of a running or finished download, oldest first (empty if none are kept)
+pub fn progress_history(media_idx: i32) -> Vec<ProgressSample> {
+    PROGRESS_HISTORY.lock().unwrap().get(&media_idx).map(ProgressHistory::samples).unwrap_or_default()
+}
+
+/// Record the speed and ETA reported by a progress line
+fn record_transfer_stats(media_idx: i32, line: &str) {
+    if let Some(speed) = parse_progress_speed(line) {
+        DOWNLOAD_SPEEDS.lock().unwrap().insert(media_idx, speed);
+    }
+    if let Some(eta) = parse_progress_eta(line) {
+        DOWNLOAD_ETAS.lock().unwrap().insert(media_idx, eta);
+    }
+}
+
+/// Seconds remaining for one active download, or None if it is not running or has not reported an ETA yet
+pub fn download_eta(media_idx: i32) -> Option<u64> {
+    DOWNLOAD_ETAS.lock().unwrap().get(&media_idx).copied()
+}
+
+/// Number of downloads reporting a speed, and their combined speed in bytes/sec
+pub fn aggregate_speed() -> (usize, f64) {
+    let speeds = DOWNLOAD_SPEEDS.lock().unwrap();
+    (speeds.len(), speeds.values().sum())
+}
+
+/// Register a fresh cancellation flag for a download about to run
+fn register_cancel_flag(media_idx: i32) -> Arc<CancelFlag> {
+    let flag = Arc::new(CancelFlag::default());
+    let mut flags = DOWNLOAD_CANCEL_FLAGS.lock().unwrap();
+    flags.insert(media_idx, flag.clone());
+    flag
+}
+
+/// Drop everything tracked for a finished download: its cancel flag and speed/ETA samples.
+/// The flag is only removed while it is still this task's, so a retry already registered
+/// under the same index keeps its own.
+fn release_download(media_idx: i32, flag: &Arc<CancelFlag>) {
+    {
+        let mut flags = DOWNLOAD_CANCEL_FLAGS.lock().unwrap();
+        if flags.get(&media_idx).is_some_and(|current| Arc::ptr_eq(current, flag)) {
+            flags.remove(&media_idx);
+        }
+    }
+    release_transfer_state(media_idx);
+}
+
+/// Drop a finished attempt's speed/ETA samples and PID but keep its cancel flag registered,
+/// for a `-f best` retry that takes the flag over so a cancel in between isn't lost
+fn release_transfer_state(media_idx: i32) {
+    DOWNLOAD_SPEEDS.lock().unwrap().remove(&media_idx);
+    DOWNLOAD_ETAS.lock().unwrap().remove(&media_idx);
+    DOWNLOAD_PIDS.lock().unwrap().remove(&media_idx);
+}
+
+/// Media indices whose download task still holds a cancel flag, i.e. has not finished yet
+pub fn registered_downloads() -> HashSet<i32> {
+    DOWNLOAD_CANCEL_FLAGS.lock().unwrap().keys().copied().collect()
+}
+
+/// Process ids of the yt-dlp downloads this session is running
+pub fn running_download_pids() -> HashSet<u32> {
+    DOWNLOAD_PIDS.lock().unwrap().values().copied().collect()
+}
+
 /// Request cancellation for a specific download.
-pub fn request_cancel(media_idx: i32) -> bool {
+pub fn request_cancel(media_idx: i32, reason: CancelReason) -> bool {
     let flags = DOWNLOAD_CANCEL_FLAGS.lock().unwrap();
     if let Some(flag) = flags.get(&media_idx) {
-        flag.store(true, Ordering::Relaxed);
-        eprintln!("Cancellation requested for media_idx {}", media_idx);
+        flag.request(reason);
+        eprintln!("Cancellation requested for media_idx {} ({:?})", media_idx, reason);
         true
     } else {
         eprintln!("No active download found for media_idx {}", media_idx);
@@ -50,96 +264,371 @@ pub fn request_cancel(media_idx: i32) -> bool {
 /// Request cancellation for all active downloads.
 /// Returns the indices of downloads that were flagged.
 pub fn request_cancel_all() -> Vec<i32> {
-    let flags = DOWNLOAD_CANCEL_FLAGS.lock().unwrap();
-    for (_idx, flag) in flags.iter() {
-        flag.store(true, Ordering::Relaxed);
+    flag_all(&DOWNLOAD_CANCEL_FLAGS.lock().unwrap())
+}
+
+fn flag_all(flags: &HashMap<i32, Arc<CancelFlag>>) -> Vec<i32> {
+    for flag in flags.values() {
+        flag.request(CancelReason::All);
     }
     flags.keys().copied().collect()
 }
 
+/// Stop all downloads before the app exits so no yt-dlp process is orphaned.
+///
+/// Drops queued items, flags every active download for cancellation, then waits up to
+/// `grace` for their tasks to kill the child processes and unregister. Async so callers on the
+/// UI thread can spawn it instead of blocking for the whole grace period.
+/// Returns how many downloads were still running when the grace period ended.
+pub async fn shutdown_downloads(grace: Duration) -> usize {
+    with_queue(|queue| queue.cancel_all());
+    let flagged = request_cancel_all();
+    if !flagged.is_empty() {
+        eprintln!("Shutting down {} active download(s)", flagged.len());
+    }
+
+    let deadline = Instant::now() + grace;
+    loop {
+        let remaining = DOWNLOAD_CANCEL_FLAGS.lock().unwrap().len();
+        if remaining == 0 || Instant::now() >= deadline {
+            return remaining;
+        }
+        tokio::time::sleep(Duration::from_millis(CANCELLATION_POLL_INTERVAL_MS)).await;
+    }
+}
+
+/// Announce that a failing host is on cooldown and re-pump the queue once it ends,
+/// so items held back for that host get picked up again.
+fn start_host_cooldown(window: &WebviewWindow, host: String) {
+    let cooldown = with_queue(|queue| queue.host_cooldown());
+    eprintln!("Host {} failed repeatedly; pausing its downloads for {}s", host, cooldown.as_secs());
+
+    if let Err(e) = window.emit(EVT_HOST_COOLDOWN, (&host, cooldown.as_secs())) {
+        eprintln!("Failed to emit host-cooldown: {}", e);
+    }
+    broadcast_remote_event(EVT_HOST_COOLDOWN, json!([host, cooldown.as_secs()]));
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(cooldown).await;
+        notify_queue();
+    });
+}
+
+fn emit_stderr_line(sink: &impl DownloadEventSink, media_idx: i32, line: &str) {
+    sink.emit_event(EVT_YTDLP_STDERR, json!([media_idx, line]));
+}
+
+/// Turns yt-dlp output lines into progress and post-processing events for one download
+struct OutputTracker {
+    media_idx: i32,
+    last_progress_emit: Instant,
+    postprocess_phase: Option<String>,
+    /// Latest file yt-dlp reported writing; after a successful run, the final file
+    output_path: Option<String>,
+    stats: DownloadStats,
+    last_history_sample: Option<Instant>,
+}
+
+impl OutputTracker {
+    /// Emit 0% right away so the UI shows activity before yt-dlp prints progress
+    fn start(sink: &impl DownloadEventSink, media_idx: i32, now: Instant) -> Self {
+        sink.emit_event(EVT_DOWNLOAD_PROGRESS, json!([media_idx, 0.0]));
+        Self {
+            media_idx,
+            last_progress_emit: now,
+            postprocess_phase: None,
+            output_path: None,
+            stats: DownloadStats::new(now),
+            last_history_sample: None,
+        }
+    }
+
+    /// Handle one stdout/stderr line. Returns true if it produced a progress event;
+    /// progress is debounced to `PROGRESS_DEBOUNCE_MS` except for 100%, which always goes through.
+    fn handle_line(&mut self, sink: &impl DownloadEventSink, line: &str, now: Instant) -> bool {
+        record_transfer_stats(self.media_idx, line);
+        if let Some(speed) = parse_progress_speed(line) {
+            self.stats.record(speed, now);
+        }
+        // Post-processor lines ("[Merger] ...") go to stdout or stderr depending on yt-dlp version
+        self.track_postprocess_phase(sink, line);
+        if let Some(path) = detect_output_path(line) {
+            self.output_path = Some(path);
+        }
+
+        let Some(percent) = parse_progress_percent(line) else {
+            return false;
+        };
+        self.sample_history(line, percent, now);
+        if percent < 100.0 && now.duration_since(self.last_progress_emit).as_millis() < PROGRESS_DEBOUNCE_MS {
+            return false;
+        }
+        sink.emit_event(EVT_DOWNLOAD_PROGRESS, json!([self.media_idx, percent]));
+        self.last_progress_emit = now;
+        true
+    }
+
+    /// Add a `get_progress_history` sample, at most one per `PROGRESS_SAMPLE_INTERVAL`
+    fn sample_history(&mut self, line: &str, percent: f64, now: Instant) {
+        let spaced = self.last_history_sample.is_none_or(|last| now.duration_since(last) >= PROGRESS_SAMPLE_INTERVAL);
+        if percent < 100.0 && !spaced {
+            return;
+        }
+        let timestamp_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        let speed = parse_progress_speed(line);
+        record_progress_sample(self.media_idx, ProgressSample { timestamp_ms, percent, speed });
+        self.last_history_sample = Some(now);
+    }
+
+    /// Emit `EVT_DOWNLOAD_POSTPROCESS` when `line` starts a new post-processing phase
+    fn track_postprocess_phase(&mut self, sink: &impl DownloadEventSink, line: &str) {
+        let Some(phase) = detect_postprocess_phase(line) else {
+            return;
+        };
+        if self.postprocess_phase.as_deref() == Some(phase.as_str()) {
+            return;
+        }
+        sink.emit_event(EVT_DOWNLOAD_POSTPROCESS, json!([self.media_idx, phase]));
+        self.postprocess_phase = Some(phase);
+    }
+}
+
+/// Re-check a completed download in the background (`verify_after_download`).
+/// A mismatch is reported as a yt-dlp warning line; the download itself stays complete.
+fn spawn_verification(
+    window: WebviewWindow,
+    media_idx: i32,
+    url: String,
+    settings: DownloadSettings,
+    best_fallback: bool,
+    output_path: Option<String>,
+) {
+    tauri::async_runtime::spawn(async move {
+        match verify_download(&url, &settings, best_fallback, output_path.as_deref()).await {
+            VerifyOutcome::Verified => window.emit_event(EVT_DOWNLOAD_VERIFIED, json!(media_idx)),
+            VerifyOutcome::Mismatch(reason) => {
+                let warning = format!("WARNING: download verification failed: {}", reason);
+                append_yt_dlp_log(window.app_handle(), media_idx, &warning);
+                emit_stderr_line(&window, media_idx, &warning);
+            }
+        }
+    });
+}
+
+/// Report a download that ended without error: cancelled if a reason was recorded, otherwise complete
+fn emit_finished(sink: &impl DownloadEventSink, media_idx: i32, cancelled: Option<CancelReason>) {
+    match cancelled {
+        Some(reason) => {
+            sink.emit_event(EVT_DOWNLOAD_CANCELLED, json!([media_idx, reason]));
+            // A paused download is back in the queue and resumes when a slot frees up
+            if reason == CancelReason::Paused {
+                sink.emit_event(EVT_DOWNLOAD_QUEUED, json!(media_idx));
+            }
+        }
+        None => sink.emit_event(EVT_DOWNLOAD_COMPLETE, json!(media_idx)),
+    }
+}
+
+/// Record a cancelled download in the queue. One paused for a lowered concurrency limit has
+/// exited by now, so it moves back to the front of the queue instead.
+fn record_cancel(media_idx: i32, reason: CancelReason) {
+    with_queue(|queue| {
+        if reason != CancelReason::Paused || !queue.requeue_paused(media_idx) {
+            queue.cancel(media_idx);
+        }
+    });
+}
+
+/// Pause the queue after a download ran out of disk space, so queued items
+/// don't fail the same way, and cancel the other running downloads, which write
+/// to the same full disk. The queue stays paused until the user resumes it.
+fn pause_for_disk_full(window: &WebviewWindow, media_idx: i32, output_dir: &str) {
+    with_queue(|queue| queue.set_paused(true));
+    eprintln!("Disk full while writing to {}; pausing the download queue", output_dir);
+
+    for other in registered_downloads() {
+        if other != media_idx {
+            request_cancel(other, CancelReason::DiskLow);
+        }
+    }
+
+    if let Err(e) = window.emit(EVT_DISK_LOW, output_dir) {
+        eprintln!("Failed to emit disk-low: {}", e);
+    }
+    broadcast_remote_event(EVT_DISK_LOW, json!(output_dir));
+}
+
 /// Execute a download (called by queue processor).
 ///
 /// Spawns yt-dlp as a subprocess, monitors its output for progress,
-/// and handles cancellation requests.
+/// and handles cancellation requests. `output_dir` must already exist (see `prepare_output_dir`).
 pub fn execute_download(
     window: WebviewWindow,
     media_idx: i32,
     media_source_url: String,
-    output_location: String,
-    subfolder: Option<String>,
+    output_dir: String,
     settings: DownloadSettings,
 ) {
+    if is_mock_mode() {
+        spawn_mock_download(window, media_idx);
+        return;
+    }
+
+    spawn_download(window, media_idx, media_source_url, output_dir, settings, None);
+}
+
+/// Simulate a download in mock mode: progress 0 -> 100 over `MOCK_DOWNLOAD_DURATION`,
+/// honouring cancellation, without spawning any process.
+fn spawn_mock_download(window: WebviewWindow, media_idx: i32) {
+    tauri::async_runtime::spawn(async move {
+        let cancel_flag = register_cancel_flag(media_idx);
+        let interval = mock_step_interval(MOCK_PROGRESS_STEPS);
+
+        let mut cancelled: Option<CancelReason> = None;
+        for percent in synthetic_progress(MOCK_PROGRESS_STEPS) {
+            if let Some(reason) = cancel_flag.reason() {
+                cancelled = Some(reason);
+                break;
+            }
+
+            window.emit_event(EVT_DOWNLOAD_PROGRESS, json!([media_idx, percent]));
+            tokio::time::sleep(interval).await;
+        }
+
+        release_download(media_idx, &cancel_flag);
+
+        emit_finished(&window, media_idx, cancelled);
+        if let Some(reason) = cancelled {
+            record_cancel(media_idx, reason);
+        } else {
+            with_queue(|queue| queue.complete(media_idx));
+        }
+
+        notify_queue();
+    });
+}
+
+/// Spawn the yt-dlp task. With `retry_flag`, format selection is replaced by `-f best`; this is the
+/// single retry made when `fallback_to_best` is set and the requested format is missing, and it keeps
+/// the first attempt's cancel flag, which stayed registered throughout.
+fn spawn_download(
+    window: WebviewWindow,
+    media_idx: i32,
+    media_source_url: String,
+    output_dir: String,
+    mut settings: DownloadSettings,
+    retry_flag: Option<Arc<CancelFlag>>,
+) {
+    let best_fallback = retry_flag.is_some();
     let window_clone = window.clone();
+    // Without ffmpeg, merge/remux/embed steps would fail after the download finishes
+    if !is_ffmpeg_available() {
+        settings.avoid_postprocessing = true;
+    }
 
     tauri::async_runtime::spawn(async move {
         let window = window_clone;
-        // Register cancellation flag for this download
-        let cancel_flag = Arc::new(AtomicBool::new(false));
-        {
-            let mut flags = DOWNLOAD_CANCEL_FLAGS.lock().unwrap();
-            flags.insert(media_idx, cancel_flag.clone());
-        }
-
-        let mark_queue_fail = |_context: &str| with_queue(|queue| queue.fail(media_idx));
-
-        // Build base output directory (with subfolder if present)
-        let output_dir = match &subfolder {
-            Some(folder) if !folder.is_empty() => {
-                let subfolder_path = format!("{}{}{}", output_location, path::MAIN_SEPARATOR, folder);
-                // Create subfolder if it doesn't exist
-                if let Err(e) = std::fs::create_dir_all(&subfolder_path) {
-                    eprintln!("Warning: Failed to create subfolder {}: {}", subfolder_path, e);
-                    // Fall back to base output location
-                    output_location.clone()
-                } else {
-                    subfolder_path
-                }
+        // Register cancellation flag for this download, or keep the one a retry inherited
+        let cancel_flag = retry_flag.unwrap_or_else(|| register_cancel_flag(media_idx));
+        // A -f best retry keeps the first attempt's output, which explains why it retried
+        if !best_fallback {
+            clear_output(media_idx);
+        }
+        clear_progress_history(media_idx);
+
+        let mark_queue_fail = |_context: &str| {
+            if let Some(host) = with_queue(|queue| queue.fail(media_idx)) {
+                start_host_cooldown(&window, host);
             }
-            _ => output_location.clone(),
         };
 
         // Build output template: optionally include unique ID for avoiding collisions
-        let output_format = if settings.append_unique_id {
-            if settings.unique_id_type == "hash" {
-                // Custom short hash - consistent 8-char format across all platforms
-                let unique_id = generate_unique_id(&media_source_url);
-                format!(
-                    "{}{}%(title)s [{}].%(ext)s",
-                    output_dir,
-                    path::MAIN_SEPARATOR,
-                    unique_id
-                )
-            } else {
-                // Native yt-dlp ID - truly idempotent per video (handles URL variations)
-                format!("{}{}%(title)s [%(id)s].%(ext)s", output_dir, path::MAIN_SEPARATOR)
-            }
-        } else {
-            format!("{}{}%(title)s.%(ext)s", output_dir, path::MAIN_SEPARATOR)
-        };
+        let output_format = build_output_template(&output_dir, Some(&media_source_url), &settings);
+
+        // aria2c must be on PATH before yt-dlp is told to hand off to it
+        if settings.uses_aria2c() && probe_aria2c_version().await.is_none() {
+            mark_queue_fail("after aria2c probe failure");
+            release_download(media_idx, &cancel_flag);
+            emit_download_error(&window, media_idx, "aria2c downloader selected but aria2c was not found on PATH");
+            notify_queue();
+            return;
+        }
 
         // Build the yt-dlp command
         let mut cmd = Command::new("yt-dlp");
         cmd.arg(&media_source_url)
             .arg("--progress-template")
-            .arg("download:remedia-%(progress._percent_str)s-%(progress.eta)s")
+            .arg("download:remedia-%(progress._percent_str)s-%(progress.eta)s-%(progress.speed)s")
             .arg("--newline")
             .arg("--continue")
-            .arg("--no-overwrites") // Prevent silent overwrites
+            .args(build_duplicate_args(&settings)) // Skip, overwrite, or keep both copies
             .arg("--output")
             .arg(output_format)
-            .arg("--embed-thumbnail")
-            .arg("--embed-subs")
-            .arg("--embed-metadata")
-            .arg("--embed-chapters")
-            .arg("--windows-filenames"); // Safe filenames for Windows
+            .args(build_embed_args(&settings)) // Skipped when avoiding ffmpeg post-processing
+            .arg("--windows-filenames") // Safe filenames for Windows
+            .args(build_extractor_args(settings.extractor_args.as_deref()));
 
         // Apply optional rate and size limits
         for arg in build_rate_and_size_args(&settings) {
             cmd.arg(arg);
         }
 
+        // Parallel fragment downloads, within this download's share of the fragment budget
+        for arg in build_fragment_args(&settings) {
+            cmd.arg(arg);
+        }
+
         // Apply settings-based format selection using extracted function
-        for arg in build_format_args(&settings) {
+        if best_fallback {
+            cmd.arg("-f").arg("best");
+        } else {
+            for arg in build_format_args(&settings) {
+                cmd.arg(arg);
+            }
+        }
+
+        // Hand off to an external downloader if configured
+        for arg in build_downloader_args(&settings) {
+            cmd.arg(arg);
+        }
+
+        // Intermediate file placement (temp dir, .part files)
+        for arg in build_temp_file_args(&settings) {
+            cmd.arg(arg);
+        }
+
+        // Authenticate with browser cookies or a cookies.txt file
+        for arg in build_cookie_args(settings.cookies_source.as_deref(), settings.cookies_file.as_deref()) {
+            cmd.arg(arg);
+        }
+
+        // Only fetch items uploaded on/after the configured date (channel syncs)
+        for arg in build_date_filter_args(&settings) {
+            cmd.arg(arg);
+        }
+
+        // Description sidecar, named after the output template
+        for arg in build_sidecar_args(&settings) {
+            cmd.arg(arg);
+        }
+
+        // Record finished IDs so an interrupted playlist can be resumed
+        for arg in build_archive_args(&settings, &output_dir) {
+            cmd.arg(arg);
+        }
+
+        // Keep long titles within OS filename limits
+        for arg in build_filename_length_args(&settings) {
+            cmd.arg(arg);
+        }
+
+        // Apply manual metadata corrections (embedded via --embed-metadata)
+        for arg in build_metadata_override_args(&settings) {
+            cmd.arg(arg);
+        }
+
+        // User-supplied pass-through flags (allowlisted during validation)
+        for arg in build_extra_args(&settings) {
             cmd.arg(arg);
         }
 
@@ -152,25 +641,22 @@ pub fn execute_download(
             Ok(child) => child,
             Err(e) => {
                 mark_queue_fail("while marking fail after spawn error");
-                {
-                    let mut flags = DOWNLOAD_CANCEL_FLAGS.lock().unwrap();
-                    flags.remove(&media_idx);
-                }
+                release_download(media_idx, &cancel_flag);
                 emit_download_error(&window, media_idx, &format!("spawn yt-dlp failed: {e}"));
                 notify_queue();
                 return;
             }
         };
+        if let Some(pid) = child.id() {
+            DOWNLOAD_PIDS.lock().unwrap().insert(media_idx, pid);
+        }
 
         let stdout = match child.stdout.take() {
             Some(stdout) => stdout,
             None => {
                 mark_queue_fail("while handling missing stdout");
                 emit_download_error(&window, media_idx, "yt-dlp stdout unavailable");
-                {
-                    let mut flags = DOWNLOAD_CANCEL_FLAGS.lock().unwrap();
-                    flags.remove(&media_idx);
-                }
+                release_download(media_idx, &cancel_flag);
                 notify_queue();
                 return;
             }
@@ -181,28 +667,22 @@ pub fn execute_download(
             None => {
                 mark_queue_fail("while handling missing stderr");
                 emit_download_error(&window, media_idx, "yt-dlp stderr unavailable");
-                {
-                    let mut flags = DOWNLOAD_CANCEL_FLAGS.lock().unwrap();
-                    flags.remove(&media_idx);
-                }
+                release_download(media_idx, &cancel_flag);
                 notify_queue();
                 return;
             }
         };
 
-        let mut out_reader = BufReader::new(stdout).lines();
-        let mut err_reader = BufReader::new(stderr).lines();
+        let mut out_reader = LossyLines::new(BufReader::new(stdout));
+        let mut err_reader = LossyLines::new(BufReader::new(stderr));
 
-        // Debounce progress updates
-        let mut last_progress_emit = std::time::Instant::now();
+        let mut tracker = OutputTracker::start(&window, media_idx, Instant::now());
 
-        // Immediately emit 0% so UI shows activity even before yt-dlp prints progress
-        if let Err(e) = window.emit(EVT_DOWNLOAD_PROGRESS, (media_idx, 0.0)) {
-            eprintln!("Failed to emit initial download progress: {}", e);
-        }
-        broadcast_if_active(EVT_DOWNLOAD_PROGRESS, json!([media_idx, 0.0]));
-
-        let mut cancelled = false;
+        let mut cancelled: Option<CancelReason> = None;
+        let mut format_unavailable = false;
+        let mut too_large = false;
+        let mut classified_error: Option<ErrorCode> = None;
+        let mut stderr_throttle = StderrThrottle::new();
         let mut stdout_done = false;
         let mut stderr_done = false;
         let mut process_exited = false;
@@ -216,9 +696,9 @@ pub fn execute_download(
             tokio::select! {
                 // Check cancellation
                 _ = tokio::time::sleep(std::time::Duration::from_millis(CANCELLATION_POLL_INTERVAL_MS)) => {
-                    if cancel_flag.load(Ordering::Relaxed) {
+                    if let Some(reason) = cancel_flag.reason() {
                         eprintln!("Cancelling download for media_idx {}", media_idx);
-                        cancelled = true;
+                        cancelled = Some(reason);
                         if let Err(e) = child.start_kill() {
                             eprintln!("Failed to kill yt-dlp process: {}", e);
                         }
@@ -229,17 +709,9 @@ pub fn execute_download(
                 res = out_reader.next_line(), if !stdout_done => {
                     match res {
                         Ok(Some(line)) => {
-                            // Parse progress using extracted function
-                            if let Some(percent) = parse_progress_percent(&line) {
-                                // Check debounce (always emit 100% or if enough time passed)
-                                if percent >= 100.0 || last_progress_emit.elapsed().as_millis() >= PROGRESS_DEBOUNCE_MS {
-                                    if let Err(e) = window.emit(EVT_DOWNLOAD_PROGRESS, (media_idx, percent)) {
-                                        eprintln!("Failed to emit download progress: {}", e);
-                                    }
-                                    broadcast_if_active(EVT_DOWNLOAD_PROGRESS, json!([media_idx, percent]));
-                                    last_progress_emit = std::time::Instant::now();
-                                }
-                            }
+                            record_output_line(media_idx, &line);
+                            too_large |= is_max_filesize_skip(&line);
+                            tracker.handle_line(&window, &line, Instant::now());
                             broadcast_if_active(EVT_DOWNLOAD_RAW, json!([media_idx, "stdout", line]));
                         }
                         Ok(None) => stdout_done = true,
@@ -254,35 +726,24 @@ pub fn execute_download(
                 res = err_reader.next_line(), if !stderr_done => {
                     match res {
                         Ok(Some(line)) => {
-                            // Attempt to parse progress from stderr too (yt-dlp often writes progress there)
-                            let mut progress_emitted = false;
-                            if let Some(percent) = parse_progress_percent(&line)
-                                && (percent >= 100.0
-                                    || last_progress_emit.elapsed().as_millis() >= PROGRESS_DEBOUNCE_MS)
-                            {
-                                if let Err(e) =
-                                    window.emit(EVT_DOWNLOAD_PROGRESS, (media_idx, percent))
-                                {
-                                    eprintln!("Failed to emit download progress: {}", e);
-                                }
-                                last_progress_emit = std::time::Instant::now();
-                                broadcast_if_active(
-                                    EVT_DOWNLOAD_PROGRESS,
-                                    json!([media_idx, percent]),
-                                );
-                                progress_emitted = true;
+                            record_output_line(media_idx, &line);
+                            // yt-dlp often writes progress to stderr too
+                            let progress_emitted = tracker.handle_line(&window, &line, Instant::now());
+                            format_unavailable |= is_format_unavailable_error(&line);
+                            too_large |= is_max_filesize_skip(&line);
+                            if let Some(code) = classify_ytdlp_stderr(&line) {
+                                classified_error = Some(code);
                             }
 
-                            // Filter stderr events to prevent flooding the frontend
+                            // Filter and throttle stderr events to prevent flooding the frontend
                             if !progress_emitted && should_emit_stderr(&line) {
                                 // Persist to rotated log file next to the app config
                                 let app = window.app_handle();
                                 append_yt_dlp_log(app, media_idx, &line);
 
-                                if let Err(e) = window.emit(EVT_YTDLP_STDERR, (media_idx, &line)) {
-                                    eprintln!("Failed to emit yt-dlp stderr: {}", e);
+                                for text in stderr_throttle.push(&line, Instant::now()) {
+                                    emit_stderr_line(&window, media_idx, &text);
                                 }
-                                broadcast_if_active(EVT_YTDLP_STDERR, json!([media_idx, line]));
                             }
                             broadcast_if_active(EVT_DOWNLOAD_RAW, json!([media_idx, "stderr", line]));
                         }
@@ -308,30 +769,79 @@ pub fn execute_download(
             }
         }
 
-        // Clean up cancellation flag
-        {
-            let mut flags = DOWNLOAD_CANCEL_FLAGS.lock().unwrap();
-            flags.remove(&media_idx);
+        // Report the final count of any coalesced stderr line that was held back
+        if let Some(text) = stderr_throttle.flush() {
+            emit_stderr_line(&window, media_idx, &text);
+        }
+
+        // Retry once with `-f best` when the requested format was missing
+        let retry_with_best = cancelled.is_none()
+            && !too_large
+            && !best_fallback
+            && status.is_some_and(|s| !s.success())
+            && should_fallback_to_best(settings.fallback_to_best, &settings.download_mode, format_unavailable);
+
+        // Clean up cancellation flag and transfer stats; a retry keeps the flag
+        if retry_with_best {
+            release_transfer_state(media_idx);
+        } else {
+            release_download(media_idx, &cancel_flag);
         }
 
         // Emit appropriate event based on outcome
-        if cancelled {
-            if let Err(e) = window.emit(EVT_DOWNLOAD_CANCELLED, media_idx) {
-                eprintln!("Failed to emit download-cancelled: {}", e);
-            }
-            broadcast_remote_event(EVT_DOWNLOAD_CANCELLED, json!(media_idx));
+        if let Some(reason) = cancelled {
+            emit_finished(&window, media_idx, cancelled);
             // Mark as cancelled in queue
-            with_queue(|queue| queue.cancel(media_idx));
+            record_cancel(media_idx, reason);
+        } else if too_large && status.is_some() {
+            // yt-dlp declined the file because of `max_file_size`; not an error
+            window.emit_event(EVT_DOWNLOAD_SKIPPED, json!([media_idx, "too_large"]));
+            clear_output(media_idx);
+            with_queue(|queue| queue.skip(media_idx));
+        } else if settings.dateafter.is_some() && status.is_some_and(|s| s.code() == Some(DATE_REJECT_EXIT_CODE)) {
+            // Uploaded before `dateafter`; `--break-on-reject` stopped yt-dlp. Filtered out, not failed
+            window.emit_event(EVT_DOWNLOAD_SKIPPED, json!([media_idx, "date_filtered"]));
+            clear_output(media_idx);
+            with_queue(|queue| queue.skip(media_idx));
         } else if let Some(status) = status {
             if status.success() {
-                if let Err(e) = window.emit(EVT_DOWNLOAD_COMPLETE, media_idx) {
-                    eprintln!("Failed to emit download-complete: {}", e);
-                }
-                broadcast_remote_event(EVT_DOWNLOAD_COMPLETE, json!(media_idx));
+                let stats = tracker.stats.report(media_idx, Instant::now());
+                window.emit_event(EVT_DOWNLOAD_STATS, json!(stats));
+                emit_finished(&window, media_idx, None);
+                clear_output(media_idx);
                 // Mark as completed in queue
                 with_queue(|queue| queue.complete(media_idx));
+                if let Some(mode) = settings.file_mode.as_deref().and_then(parse_file_mode)
+                    && let Some(path) = tracker.output_path.as_deref()
+                    && let Err(e) = apply_file_mode(Path::new(path), mode)
+                {
+                    let note = format!("Failed to set file mode {:o} on {}: {}", mode, path, e);
+                    append_yt_dlp_log(window.app_handle(), media_idx, &note);
+                }
+                if settings.verify_after_download {
+                    let output_path = tracker.output_path.take();
+                    let url = media_source_url;
+                    spawn_verification(window.clone(), media_idx, url, settings, best_fallback, output_path);
+                }
+            } else if retry_with_best {
+                // The queue entry stays active and the retry reports the outcome
+                eprintln!("Requested format unavailable for media_idx {}, retrying with -f best", media_idx);
+                let note = "Requested format unavailable, retrying with -f best";
+                append_yt_dlp_log(window.app_handle(), media_idx, note);
+                spawn_download(window, media_idx, media_source_url, output_dir, settings, Some(cancel_flag));
+                return;
             } else {
-                emit_download_error(&window, media_idx, "yt-dlp exited with error status");
+                let reason = match classified_error {
+                    Some(code) => match code.hint() {
+                        Some(hint) => format!("yt-dlp exited with error status [{}]: {}", code.as_str(), hint),
+                        None => format!("yt-dlp exited with error status [{}]", code.as_str()),
+                    },
+                    None => "yt-dlp exited with error status".to_string(),
+                };
+                emit_download_error(&window, media_idx, &reason);
+                if classified_error == Some(ErrorCode::EIoDiskFull) {
+                    pause_for_disk_full(&window, media_idx, &output_dir);
+                }
                 // Mark as failed in queue
                 mark_queue_fail("after non-success status");
             }
@@ -345,3 +855,264 @@ pub fn execute_download(
         notify_queue();
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::downloader::events::EventCollector;
+
+    /// Cancel flags live in a global map, so each test uses its own media indices
+    fn registered(media_idx: i32) -> Arc<CancelFlag> {
+        register_cancel_flag(media_idx)
+    }
+
+    fn unregister(indices: &[i32]) {
+        let mut flags = DOWNLOAD_CANCEL_FLAGS.lock().unwrap();
+        for idx in indices {
+            flags.remove(idx);
+        }
+    }
+
+    /// Feed `lines` through a tracker as if yt-dlp printed one every `step`
+    fn replay<'a>(sink: &EventCollector, media_idx: i32, lines: impl IntoIterator<Item = &'a str>, step: Duration) {
+        let start = Instant::now();
+        let mut tracker = OutputTracker::start(sink, media_idx, start);
+        for (i, line) in lines.into_iter().enumerate() {
+            tracker.handle_line(sink, line, start + step * (i as u32 + 1));
+        }
+        DOWNLOAD_SPEEDS.lock().unwrap().remove(&media_idx);
+        DOWNLOAD_ETAS.lock().unwrap().remove(&media_idx);
+    }
+
+    #[test]
+    fn test_stdout_stream_emits_progress_then_complete() {
+        let sink = EventCollector::default();
+        let stdout = [
+            "[youtube] dQw4w9WgXcQ: Downloading webpage",
+            "download:remedia-  5.0%-40-1000.0",
+            "download:remedia- 10.0%-35-1000.0", // within the debounce window, dropped
+            "download:remedia- 50.0%-10-1000.0",
+            "download:remedia-100.0%-0-1000.0",
+            "[Merger] Merging formats into \"video.mp4\"",
+        ];
+        replay(&sink, -7200, stdout, Duration::from_millis(60));
+        emit_finished(&sink, -7200, None);
+
+        let expected = vec![
+            (EVT_DOWNLOAD_PROGRESS.to_string(), json!([-7200, 0.0])),
+            (EVT_DOWNLOAD_PROGRESS.to_string(), json!([-7200, 5.0])),
+            (EVT_DOWNLOAD_PROGRESS.to_string(), json!([-7200, 50.0])),
+            (EVT_DOWNLOAD_PROGRESS.to_string(), json!([-7200, 100.0])),
+            (EVT_DOWNLOAD_POSTPROCESS.to_string(), json!([-7200, "merging"])),
+            (EVT_DOWNLOAD_COMPLETE.to_string(), json!(-7200)),
+        ];
+        assert_eq!(sink.take(), expected);
+    }
+
+    #[test]
+    fn test_final_progress_bypasses_debounce() {
+        let sink = EventCollector::default();
+        let stdout = ["download:remedia- 99.0%-1-1000.0", "download:remedia-100.0%-0-1000.0"];
+        replay(&sink, -7201, stdout, Duration::from_millis(1));
+
+        let percents: Vec<_> = sink.take().into_iter().map(|(_, payload)| payload[1].clone()).collect();
+        assert_eq!(percents, vec![json!(0.0), json!(100.0)]);
+    }
+
+    #[test]
+    fn test_cancelled_download_emits_reason() {
+        let sink = EventCollector::default();
+        replay(&sink, -7202, ["download:remedia- 20.0%-30-1000.0"], Duration::from_millis(200));
+        emit_finished(&sink, -7202, Some(CancelReason::User));
+
+        let events = sink.take();
+        assert_eq!(events.last(), Some(&(EVT_DOWNLOAD_CANCELLED.to_string(), json!([-7202, "user"]))));
+        assert_eq!(events.len(), 3);
+    }
+
+    #[test]
+    fn test_paused_download_reports_requeue() {
+        let sink = EventCollector::default();
+        emit_finished(&sink, -7203, Some(CancelReason::Paused));
+
+        let expected = vec![
+            (EVT_DOWNLOAD_CANCELLED.to_string(), json!([-7203, "paused"])),
+            (EVT_DOWNLOAD_QUEUED.to_string(), json!(-7203)),
+        ];
+        assert_eq!(sink.take(), expected);
+    }
+
+    #[test]
+    fn test_release_download_clears_all_state_after_completion() {
+        let flag = registered(-7300);
+        let sink = EventCollector::default();
+        let mut tracker = OutputTracker::start(&sink, -7300, Instant::now());
+        tracker.handle_line(&sink, "download:remedia-100.0%-0-4096.0", Instant::now());
+        assert_eq!(download_eta(-7300), Some(0));
+
+        release_download(-7300, &flag);
+        emit_finished(&sink, -7300, None);
+
+        assert!(!DOWNLOAD_CANCEL_FLAGS.lock().unwrap().contains_key(&-7300));
+        assert!(!DOWNLOAD_SPEEDS.lock().unwrap().contains_key(&-7300));
+        assert!(!DOWNLOAD_ETAS.lock().unwrap().contains_key(&-7300));
+    }
+
+    #[test]
+    fn test_cancel_between_attempt_and_best_retry_reaches_the_retry() {
+        let flag = registered(-7302);
+        DOWNLOAD_SPEEDS.lock().unwrap().insert(-7302, 1024.0);
+
+        // The first attempt hands its flag to the `-f best` retry
+        release_transfer_state(-7302);
+        assert!(!DOWNLOAD_SPEEDS.lock().unwrap().contains_key(&-7302));
+        assert!(request_cancel(-7302, CancelReason::User), "cancel before the retry starts must not be lost");
+        let retry_flag = flag.clone();
+        assert_eq!(retry_flag.reason(), Some(CancelReason::User));
+
+        release_download(-7302, &retry_flag);
+        assert!(!DOWNLOAD_CANCEL_FLAGS.lock().unwrap().contains_key(&-7302));
+    }
+
+    #[test]
+    fn test_release_download_keeps_a_newer_flag() {
+        let stale = registered(-7301);
+        let current = registered(-7301); // e.g. the `-f best` retry
+
+        release_download(-7301, &stale);
+        assert!(request_cancel(-7301, CancelReason::User), "the retry must stay cancellable");
+        assert_eq!(current.reason(), Some(CancelReason::User));
+
+        release_download(-7301, &current);
+        assert!(!DOWNLOAD_CANCEL_FLAGS.lock().unwrap().contains_key(&-7301));
+    }
+
+    #[test]
+    fn test_output_tail_caps_at_capacity() {
+        let mut tail = OutputTail::new(3);
+        for i in 1..=5 {
+            tail.push(&format!("line {i}"));
+        }
+        assert_eq!(tail.last(10), vec!["line 3", "line 4", "line 5"]);
+        assert_eq!(tail.last(2), vec!["line 4", "line 5"]);
+        assert!(tail.last(0).is_empty());
+
+        let mut disabled = OutputTail::new(0);
+        disabled.push("ignored");
+        assert!(disabled.last(5).is_empty());
+    }
+
+    #[test]
+    fn test_progress_history_samples_at_most_once_per_interval() {
+        let sink = EventCollector::default();
+        let lines = [
+            "download:remedia- 10.0%-40-1000.0",
+            "download:remedia- 20.0%-30-2000.0",
+            "[youtube] not a progress line",
+            "download:remedia- 30.0%-20-NA",
+            "download:remedia-100.0%-0-4000.0",
+        ];
+        // 400ms apart: the first line and the one 1.2s later are sampled, plus the final 100%
+        replay(&sink, -7500, lines, Duration::from_millis(400));
+
+        let history = progress_history(-7500);
+        let points: Vec<(f64, Option<f64>)> = history.iter().map(|s| (s.percent, s.speed)).collect();
+        assert_eq!(points, vec![(10.0, Some(1000.0)), (30.0, None), (100.0, Some(4000.0))]);
+        assert!(history.windows(2).all(|w| w[0].timestamp_ms <= w[1].timestamp_ms));
+
+        clear_progress_history(-7500);
+        assert!(progress_history(-7500).is_empty());
+    }
+
+    #[test]
+    fn test_download_output_lookup() {
+        assert!(download_output(-7400, 10).is_empty(), "no output kept for unknown downloads");
+
+        for i in 0..(OUTPUT_TAIL_LINES + 5) {
+            record_output_line(-7401, &format!("[download] line {i}"));
+        }
+        let lines = download_output(-7401, usize::MAX);
+        assert_eq!(lines.len(), OUTPUT_TAIL_LINES);
+        assert_eq!(lines.first().map(String::as_str), Some("[download] line 5"));
+        assert_eq!(download_output(-7401, 1), vec![format!("[download] line {}", OUTPUT_TAIL_LINES + 4)]);
+
+        clear_output(-7401);
+        assert!(download_output(-7401, 10).is_empty());
+    }
+
+    #[test]
+    fn test_download_eta_lookup() {
+        assert_eq!(download_eta(-7100), None, "inactive downloads have no ETA");
+
+        record_transfer_stats(-7101, "download:remedia-40.0%-NA-NA");
+        assert_eq!(download_eta(-7101), None, "an unknown ETA is not recorded");
+
+        record_transfer_stats(-7101, "download:remedia-42.0%-95-2048.0");
+        assert_eq!(download_eta(-7101), Some(95));
+
+        DOWNLOAD_ETAS.lock().unwrap().remove(&-7101);
+        DOWNLOAD_SPEEDS.lock().unwrap().remove(&-7101);
+        assert_eq!(download_eta(-7101), None);
+    }
+
+    #[test]
+    fn test_flag_all_sets_every_flag_once() {
+        let mut flags = HashMap::new();
+        for idx in [1, 2, 3] {
+            flags.insert(idx, Arc::new(CancelFlag::default()));
+        }
+        flags[&2].request(CancelReason::User);
+
+        let mut flagged = flag_all(&flags);
+        flagged.sort();
+        assert_eq!(flagged, vec![1, 2, 3]);
+        assert_eq!(flags[&1].reason(), Some(CancelReason::All));
+        assert_eq!(flags[&3].reason(), Some(CancelReason::All));
+        // An earlier reason is kept
+        assert_eq!(flags[&2].reason(), Some(CancelReason::User));
+    }
+
+    #[test]
+    fn test_cancel_flag_keeps_first_reason() {
+        let flag = CancelFlag::default();
+        assert_eq!(flag.reason(), None);
+
+        flag.request(CancelReason::DiskLow);
+        flag.request(CancelReason::All);
+        assert_eq!(flag.reason(), Some(CancelReason::DiskLow));
+    }
+
+    #[test]
+    fn test_request_cancel_propagates_user_reason() {
+        let flag = registered(910_001);
+        let other = registered(910_002);
+
+        assert!(request_cancel(910_001, CancelReason::User));
+        assert_eq!(flag.reason(), Some(CancelReason::User));
+        assert_eq!(other.reason(), None);
+        assert!(!request_cancel(910_999, CancelReason::User));
+
+        unregister(&[910_001, 910_002]);
+    }
+
+    #[test]
+    fn test_request_cancel_all_propagates_all_reason() {
+        let first = registered(920_001);
+        let second = registered(920_002);
+
+        let flagged = request_cancel_all();
+        assert!(flagged.contains(&920_001) && flagged.contains(&920_002));
+        assert_eq!(first.reason(), Some(CancelReason::All));
+        assert_eq!(second.reason(), Some(CancelReason::All));
+
+        unregister(&[920_001, 920_002]);
+    }
+
+    #[test]
+    fn test_cancel_reason_serialization() {
+        assert_eq!(serde_json::to_value(CancelReason::User).unwrap(), "user");
+        assert_eq!(serde_json::to_value(CancelReason::DiskLow).unwrap(), "diskLow");
+        assert_eq!(serde_json::to_value(CancelReason::TooOld).unwrap(), "tooOld");
+        assert_eq!(json!([3, CancelReason::All]), json!([3, "all"]));
+    }
+}
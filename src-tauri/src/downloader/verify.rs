@@ -0,0 +1,142 @@
+//! Optional post-download verification (`verify_after_download`).
+//!
+//! Re-resolves the URL with `yt-dlp --simulate` and compares the size yt-dlp reports for the
+//! selected format with the file on disk, to catch downloads that stopped early but exited 0.
+
+use std::path::Path;
+
+use tokio::process::Command;
+
+use super::settings::{build_cookie_args, build_extractor_args, build_format_args, DownloadSettings};
+use super::ytdlp::run_yt_dlp;
+
+/// A file smaller than this fraction of the expected size counts as truncated.
+/// Sizes are often estimates (`filesize_approx`), and remuxing or embedding shifts them a little.
+pub const TRUNCATION_RATIO: f64 = 0.9;
+
+/// Result of re-checking a finished download
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyOutcome {
+    /// The URL still resolves and the file is not smaller than expected (or no size is known)
+    Verified,
+    /// Something does not match; the message says what
+    Mismatch(String),
+}
+
+/// Arguments for the `--simulate` run: the download's format selection, printing the expected size
+pub fn build_verify_args(url: &str, settings: &DownloadSettings, best_fallback: bool) -> Vec<String> {
+    let mut args = vec![
+        url.to_string(),
+        "--simulate".to_string(),
+        "--no-playlist".to_string(),
+        "--print".to_string(),
+        "%(filesize,filesize_approx)s".to_string(),
+    ];
+
+    if best_fallback {
+        args.extend(["-f".to_string(), "best".to_string()]);
+    } else {
+        // Only the selector matters; post-processing flags are ignored when simulating
+        args.extend(build_format_args(settings).into_iter().take(2));
+    }
+    args.extend(build_extractor_args(settings.extractor_args.as_deref()));
+    args.extend(build_cookie_args(settings.cookies_source.as_deref(), settings.cookies_file.as_deref()));
+    args
+}
+
+/// Expected size from the `--print` output ("NA" when yt-dlp does not know it)
+fn parse_expected_size(output: &str) -> Option<u64> {
+    let line = output.lines().map(str::trim).rfind(|l| !l.is_empty())?;
+    let size = line.parse::<f64>().ok()?;
+    (size.is_finite() && size > 0.0).then_some(size as u64)
+}
+
+/// Decide the outcome from the simulate output and the size of the downloaded file.
+/// `compare_size` is false when the file was transcoded (audio extraction), so sizes can't match.
+pub fn verify_decision(simulate_output: &str, actual_size: Option<u64>, compare_size: bool) -> VerifyOutcome {
+    if simulate_output.trim().is_empty() {
+        return VerifyOutcome::Mismatch("URL no longer resolves to the requested format".to_string());
+    }
+    let Some(actual) = actual_size else {
+        return VerifyOutcome::Mismatch("downloaded file not found".to_string());
+    };
+
+    match parse_expected_size(simulate_output) {
+        Some(expected) if compare_size && (actual as f64) < expected as f64 * TRUNCATION_RATIO => {
+            VerifyOutcome::Mismatch(format!("file is {} bytes, expected about {}", actual, expected))
+        }
+        _ => VerifyOutcome::Verified,
+    }
+}
+
+/// Re-check a completed download. `output_path` is the final file reported by yt-dlp.
+pub async fn verify_download(
+    url: &str,
+    settings: &DownloadSettings,
+    best_fallback: bool,
+    output_path: Option<&str>,
+) -> VerifyOutcome {
+    let mut cmd = Command::new("yt-dlp");
+    cmd.args(build_verify_args(url, settings, best_fallback));
+
+    let output = match run_yt_dlp(&mut cmd, None).await {
+        Ok((output, _errors)) => output,
+        Err(e) => return VerifyOutcome::Mismatch(format!("could not run yt-dlp: {}", e)),
+    };
+
+    let actual_size = output_path.and_then(|path| std::fs::metadata(Path::new(path)).ok()).map(|m| m.len());
+    let transcoded = settings.download_mode == "audio" && !settings.avoid_postprocessing;
+    verify_decision(&output, actual_size, !transcoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_decision_detects_truncation() {
+        assert_eq!(verify_decision("1000000\n", Some(999_000), true), VerifyOutcome::Verified);
+        assert_eq!(verify_decision("1000000\n", Some(1_050_000), true), VerifyOutcome::Verified);
+        assert!(matches!(verify_decision("1000000\n", Some(400_000), true), VerifyOutcome::Mismatch(_)));
+        // Transcoded audio is compared for presence only
+        assert_eq!(verify_decision("1000000\n", Some(400_000), false), VerifyOutcome::Verified);
+    }
+
+    #[test]
+    fn test_verify_decision_unknown_size_or_missing_output() {
+        assert_eq!(verify_decision("NA\n", Some(10), true), VerifyOutcome::Verified);
+        assert_eq!(
+            verify_decision("", Some(10), true),
+            VerifyOutcome::Mismatch("URL no longer resolves to the requested format".to_string())
+        );
+        assert_eq!(
+            verify_decision("1000000\n", None, true),
+            VerifyOutcome::Mismatch("downloaded file not found".to_string())
+        );
+    }
+
+    #[test]
+    fn test_build_verify_args_uses_download_format() {
+        let mut settings = DownloadSettings::remote_defaults();
+        settings.max_resolution = "720p".to_string();
+        settings.video_format = "mp4".to_string();
+
+        let args = build_verify_args("https://example.com/v", &settings, false);
+        let f_idx = args.iter().position(|a| a == "-f").unwrap();
+        assert_eq!(args[f_idx + 1], "bestvideo[height<=720]+bestaudio/best[height<=720]");
+        assert!(args.contains(&"--simulate".to_string()));
+        assert!(!args.contains(&"--remux-video".to_string()));
+
+        let args = build_verify_args("https://example.com/v", &settings, true);
+        let f_idx = args.iter().position(|a| a == "-f").unwrap();
+        assert_eq!(args[f_idx + 1], "best");
+    }
+
+    #[test]
+    fn test_parse_expected_size() {
+        assert_eq!(parse_expected_size("123456\n"), Some(123_456));
+        assert_eq!(parse_expected_size("123456.7"), Some(123_456));
+        assert_eq!(parse_expected_size("NA"), None);
+        assert_eq!(parse_expected_size(""), None);
+    }
+}
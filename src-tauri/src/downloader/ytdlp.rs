@@ -1,13 +1,57 @@
 //! yt-dlp subprocess interaction utilities.
 
 use std::process::Stdio;
+use std::sync::LazyLock;
+use std::time::Duration;
 
-use tokio::io::AsyncReadExt;
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, BufReader, Split};
 use tokio::process::Command;
+use tokio_util::sync::CancellationToken;
+
+/// GitHub API endpoint for the latest yt-dlp release
+const YTDLP_LATEST_RELEASE_URL: &str = "https://api.github.com/repos/yt-dlp/yt-dlp/releases/latest";
+
+/// Keep the update check snappy when offline or behind a slow proxy
+const UPDATE_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+static UPDATE_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .user_agent("remedia-update-check/0.1.0")
+        .timeout(UPDATE_CHECK_TIMEOUT)
+        .build()
+        .expect("Failed to build reqwest client")
+});
+
+/// Installed vs. latest released yt-dlp version
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateStatus {
+    pub current: String,
+    /// None when the release lookup failed (e.g. offline)
+    pub latest: Option<String>,
+    pub update_available: bool,
+}
+
+/// Why `run_yt_dlp` returned no output
+#[derive(Debug, Error)]
+pub enum RunError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// The cancellation token fired; the child was killed
+    #[error("yt-dlp was cancelled")]
+    Cancelled,
+}
 
 /// Run yt-dlp command and capture stdout/stderr.
 /// Ensures stdin is closed and output is captured concurrently.
-pub async fn run_yt_dlp(cmd: &mut Command) -> Result<(String, String), std::io::Error> {
+/// Triggering `cancel` kills the child and returns `RunError::Cancelled`.
+pub async fn run_yt_dlp(
+    cmd: &mut Command,
+    cancel: Option<&CancellationToken>,
+) -> Result<(String, String), RunError> {
     // Ensure we capture output and close stdin
     cmd.stdin(Stdio::null());
     cmd.stdout(Stdio::piped());
@@ -22,19 +66,97 @@ pub async fn run_yt_dlp(cmd: &mut Command) -> Result<(String, String), std::io::
     let mut stdout = child.stdout.take().ok_or_else(|| std::io::Error::other("Could not capture stdout"))?;
     let mut stderr = child.stderr.take().ok_or_else(|| std::io::Error::other("Could not capture stderr"))?;
 
-    let mut output = String::new();
-    let mut errors = String::new();
+    let mut out_bytes = Vec::new();
+    let mut err_bytes = Vec::new();
+
+    // Read stdout and stderr concurrently as bytes; titles can contain invalid UTF-8
+    let finished = async {
+        let (out_res, err_res) = tokio::join!(stdout.read_to_end(&mut out_bytes), stderr.read_to_end(&mut err_bytes));
+        out_res?;
+        err_res?;
+        child.wait().await
+    };
+
+    let outcome = match cancel {
+        Some(token) => tokio::select! {
+            status = finished => Some(status),
+            _ = token.cancelled() => None,
+        },
+        None => Some(finished.await),
+    };
+    let Some(status) = outcome else {
+        let _ = child.start_kill();
+        let _ = child.wait().await;
+        return Err(RunError::Cancelled);
+    };
+    let status = status?;
+
+    let output = String::from_utf8_lossy(&out_bytes).into_owned();
+    let mut errors = String::from_utf8_lossy(&err_bytes).into_owned();
+    append_exit_status(&mut errors, status);
+
+    Ok((output, errors))
+}
+
+/// Run yt-dlp, handing each stdout line to `on_line` as it arrives, and return stderr.
+/// For long-running calls whose output is worth reporting before the process exits.
+/// Triggering `cancel` kills the child and returns `RunError::Cancelled`.
+pub async fn run_yt_dlp_lines(
+    cmd: &mut Command,
+    cancel: Option<&CancellationToken>,
+    mut on_line: impl FnMut(String),
+) -> Result<String, RunError> {
+    cmd.stdin(Stdio::null());
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+
+    #[cfg(windows)]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let mut child = cmd.spawn()?;
+
+    let stdout = child.stdout.take().ok_or_else(|| std::io::Error::other("Could not capture stdout"))?;
+    let mut stderr = child.stderr.take().ok_or_else(|| std::io::Error::other("Could not capture stderr"))?;
+
+    let mut err_bytes = Vec::new();
+    let read_stdout = async {
+        let mut lines = LossyLines::new(BufReader::new(stdout));
+        while let Some(line) = lines.next_line().await? {
+            on_line(line);
+        }
+        Ok::<_, std::io::Error>(())
+    };
+
+    let finished = async {
+        let (out_res, err_res) = tokio::join!(read_stdout, stderr.read_to_end(&mut err_bytes));
+        out_res?;
+        err_res?;
+        child.wait().await
+    };
 
-    // Read stdout and stderr concurrently
-    let (out_res, err_res) = tokio::join!(stdout.read_to_string(&mut output), stderr.read_to_string(&mut errors));
+    let outcome = match cancel {
+        Some(token) => tokio::select! {
+            status = finished => Some(status),
+            _ = token.cancelled() => None,
+        },
+        None => Some(finished.await),
+    };
+    let Some(status) = outcome else {
+        let _ = child.start_kill();
+        let _ = child.wait().await;
+        return Err(RunError::Cancelled);
+    };
+    let status = status?;
 
-    out_res?;
-    err_res?;
+    let mut errors = String::from_utf8_lossy(&err_bytes).into_owned();
+    append_exit_status(&mut errors, status);
 
-    let status = child.wait().await?;
+    Ok(errors)
+}
 
-    // yt-dlp can emit valid JSON while returning non-zero (warnings, partial failures).
-    // Preserve the output so callers can still parse it, but surface the status via stderr.
+/// yt-dlp can emit valid JSON while returning non-zero (warnings, partial failures).
+/// Callers keep the output so they can still parse it; the status is surfaced via stderr.
+fn append_exit_status(errors: &mut String, status: std::process::ExitStatus) {
     if !status.success() {
         let status_note = match status.code() {
             Some(code) => format!("yt-dlp exited with status code {code}"),
@@ -48,8 +170,139 @@ pub async fn run_yt_dlp(cmd: &mut Command) -> Result<(String, String), std::io::
             errors.push_str(&status_note);
         }
     }
+}
 
-    Ok((output, errors))
+/// Line reader that decodes each line lossily, so an invalid UTF-8 byte replaces
+/// one character instead of failing the whole stream (as `AsyncBufReadExt::lines` does).
+pub struct LossyLines<R> {
+    inner: Split<R>,
+}
+
+impl<R: AsyncBufRead + Unpin> LossyLines<R> {
+    pub fn new(reader: R) -> Self {
+        Self { inner: reader.split(b'\n') }
+    }
+
+    /// Next line without its trailing `\n` / `\r\n`, or None at end of stream
+    pub async fn next_line(&mut self) -> std::io::Result<Option<String>> {
+        let Some(mut bytes) = self.inner.next_segment().await? else {
+            return Ok(None);
+        };
+        if bytes.last() == Some(&b'\r') {
+            bytes.pop();
+        }
+        Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+    }
+}
+
+/// Probe the installed yt-dlp by running `yt-dlp --version`.
+/// Returns the reported version, or None if yt-dlp could not be executed.
+pub async fn probe_ytdlp_version() -> Option<String> {
+    probe_version("yt-dlp").await
+}
+
+/// Probe aria2c on PATH by running `aria2c --version`.
+/// Returns the first line of its version output, or None if aria2c could not be executed.
+pub async fn probe_aria2c_version() -> Option<String> {
+    probe_version("aria2c").await
+}
+
+/// Probe ffmpeg on PATH by running `ffmpeg -version`.
+/// Returns the reported version (e.g. "6.1.1"), or None if ffmpeg could not be executed.
+pub async fn probe_ffmpeg() -> Option<String> {
+    let mut cmd = Command::new("ffmpeg");
+    cmd.arg("-version");
+
+    let (output, _errors) = run_yt_dlp(&mut cmd, None).await.ok()?;
+    parse_ffmpeg_version(&output)
+}
+
+/// Extract the version from an `ffmpeg -version` banner ("ffmpeg version 6.1.1 Copyright ...")
+fn parse_ffmpeg_version(output: &str) -> Option<String> {
+    let banner = output.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let version = banner.strip_prefix("ffmpeg version ")?.split_whitespace().next()?;
+    Some(version.to_string())
+}
+
+/// Run `<program> --version` and return the first non-empty output line
+async fn probe_version(program: &str) -> Option<String> {
+    let mut cmd = Command::new(program);
+    cmd.arg("--version");
+
+    let (output, _errors) = run_yt_dlp(&mut cmd, None).await.ok()?;
+    parse_version_output(&output)
+}
+
+/// Extract the version line from `--version` output
+fn parse_version_output(output: &str) -> Option<String> {
+    output.lines().map(str::trim).find(|l| !l.is_empty()).map(str::to_string)
+}
+
+/// Fetch the latest yt-dlp release tag. Returns None on any network or parse failure.
+pub async fn fetch_latest_ytdlp_version() -> Option<String> {
+    let resp = UPDATE_CLIENT.get(YTDLP_LATEST_RELEASE_URL).send().await.ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+    let body: Value = resp.json().await.ok()?;
+    body.get("tag_name").and_then(Value::as_str).map(|tag| tag.trim().to_string())
+}
+
+/// Compare installed and latest yt-dlp versions into an `UpdateStatus`
+pub fn build_update_status(current: String, latest: Option<String>) -> UpdateStatus {
+    let update_available = latest.as_deref().is_some_and(|latest| is_newer_version(&current, latest));
+    UpdateStatus { current, latest, update_available }
+}
+
+/// Whether `latest` is a newer yt-dlp version than `current`.
+/// Versions are date-based (`2025.07.21`, nightlies add a fourth part) and compared
+/// numerically part by part; unparsable versions never report an update.
+fn is_newer_version(current: &str, latest: &str) -> bool {
+    match (parse_version_parts(current), parse_version_parts(latest)) {
+        (Some(current), Some(latest)) => latest > current,
+        _ => false,
+    }
+}
+
+fn parse_version_parts(version: &str) -> Option<Vec<u64>> {
+    version.trim().trim_start_matches('v').split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Run `yt-dlp -U` (self-update, standalone installs only) and interpret the result.
+pub async fn self_update_ytdlp() -> Result<String, String> {
+    let mut cmd = Command::new("yt-dlp");
+    cmd.arg("-U");
+
+    let (output, errors) = run_yt_dlp(&mut cmd, None).await.map_err(|e| format!("Failed to run yt-dlp: {}", e))?;
+    interpret_update_output(&output, &errors)
+}
+
+/// Map `yt-dlp -U` output to the resulting version line, or a helpful error for
+/// installs that can't self-update (pip, package managers, read-only locations).
+fn interpret_update_output(stdout: &str, stderr: &str) -> Result<String, String> {
+    let combined = format!("{}\n{}", stdout, stderr);
+    let lower = combined.to_lowercase();
+
+    if lower.contains("with pip") || lower.contains("from pypi") {
+        return Err("yt-dlp was installed with pip; update it with `pip install -U yt-dlp`".to_string());
+    }
+    if lower.contains("package manager") {
+        return Err("yt-dlp was installed by a package manager; update it through that package manager".to_string());
+    }
+    if lower.contains("unable to write") || lower.contains("permission denied") || lower.contains("not writable") {
+        return Err("yt-dlp's install location is not writable; re-run the update with elevated permissions".into());
+    }
+
+    let result_line = combined
+        .lines()
+        .map(str::trim)
+        .find(|l| l.starts_with("Updated yt-dlp to") || l.starts_with("yt-dlp is up to date"));
+    if let Some(line) = result_line {
+        return Ok(line.to_string());
+    }
+
+    let error_line = combined.lines().map(str::trim).find(|l| l.starts_with("ERROR"));
+    Err(error_line.unwrap_or("yt-dlp update did not report a result").to_string())
 }
 
 #[cfg(test)]
@@ -71,10 +324,158 @@ mod tests {
         };
 
         let (stdout, stderr) =
-            run_yt_dlp(&mut cmd).await.expect("should not fail when command exits non-zero but produces output");
+            run_yt_dlp(&mut cmd, None).await.expect("should not fail when command exits non-zero but produces output");
 
         assert!(stdout.contains("ok"), "stdout should include command output");
         assert!(stderr.contains("warn"), "stderr should include warnings");
         assert!(stderr.contains("status"), "stderr should capture exit status note");
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_yt_dlp_cancelled_before_completion() {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("echo started && sleep 30");
+
+        let token = CancellationToken::new();
+        let trigger = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            trigger.cancel();
+        });
+
+        let started = std::time::Instant::now();
+        let result = run_yt_dlp(&mut cmd, Some(&token)).await;
+        assert!(matches!(result, Err(RunError::Cancelled)), "{result:?}");
+        assert!(started.elapsed() < Duration::from_secs(10), "child should be killed, not awaited");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_yt_dlp_lines_cancelled_after_first_line() {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("echo started && sleep 30");
+
+        let token = CancellationToken::new();
+        let trigger = token.clone();
+        let mut lines = Vec::new();
+        let started = std::time::Instant::now();
+        let result = run_yt_dlp_lines(&mut cmd, Some(&token), |line| {
+            lines.push(line);
+            trigger.cancel();
+        })
+        .await;
+
+        assert!(matches!(result, Err(RunError::Cancelled)), "{result:?}");
+        assert_eq!(lines, vec!["started"]);
+        assert!(started.elapsed() < Duration::from_secs(10), "child should be killed, not awaited");
+    }
+
+    #[tokio::test]
+    async fn test_lossy_lines_survive_invalid_utf8() {
+        let input: &[u8] = b"remedia-10.0%-5-100\nbad title \xff\xfe here\r\nremedia-20.0%-4-200\n";
+        let mut lines = LossyLines::new(tokio::io::BufReader::new(input));
+
+        assert_eq!(lines.next_line().await.unwrap().as_deref(), Some("remedia-10.0%-5-100"));
+        assert_eq!(lines.next_line().await.unwrap().as_deref(), Some("bad title \u{FFFD}\u{FFFD} here"));
+        assert_eq!(lines.next_line().await.unwrap().as_deref(), Some("remedia-20.0%-4-200"));
+        assert_eq!(lines.next_line().await.unwrap(), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_yt_dlp_tolerates_invalid_utf8() {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(r#"printf '{"title":"caf\351"}\nnext\n'"#);
+
+        let (stdout, _stderr) = run_yt_dlp(&mut cmd, None).await.expect("invalid UTF-8 should not fail the read");
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(lines, vec!["{\"title\":\"caf\u{FFFD}\"}", "next"]);
+        assert!(serde_json::from_str::<serde_json::Value>(lines[0]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_version_output() {
+        assert_eq!(parse_version_output("2025.07.21\n"), Some("2025.07.21".to_string()));
+        assert_eq!(parse_version_output("\n  2025.07.21  \n"), Some("2025.07.21".to_string()));
+        assert_eq!(parse_version_output(""), None);
+        assert_eq!(parse_version_output("  \n"), None);
+    }
+
+    #[test]
+    fn test_parse_ffmpeg_version() {
+        let banner = "ffmpeg version 6.1.1-3ubuntu5 Copyright (c) 2000-2023 the FFmpeg developers\n\
+                      built with gcc 13 (Ubuntu 13.2.0-23ubuntu3)\n\
+                      configuration: --prefix=/usr --enable-gpl\n\
+                      libavutil      58. 29.100 / 58. 29.100\n";
+        assert_eq!(parse_ffmpeg_version(banner), Some("6.1.1-3ubuntu5".to_string()));
+        assert_eq!(
+            parse_ffmpeg_version("ffmpeg version n7.0 Copyright (c) 2000-2024 the FFmpeg developers"),
+            Some("n7.0".to_string())
+        );
+        assert_eq!(parse_ffmpeg_version(""), None);
+        assert_eq!(parse_ffmpeg_version("ffprobe version 6.1.1"), None);
+    }
+
+    #[test]
+    fn test_is_newer_version() {
+        assert!(is_newer_version("2025.07.21", "2025.08.11"));
+        assert!(is_newer_version("2024.12.31", "2025.01.01"));
+        // Numeric, not lexical, comparison
+        assert!(is_newer_version("2025.9.1", "2025.10.1"));
+        // Nightly build suffix
+        assert!(is_newer_version("2025.07.21", "2025.07.21.232906"));
+        assert!(!is_newer_version("2025.07.21.232906", "2025.07.21"));
+
+        assert!(!is_newer_version("2025.07.21", "2025.07.21"));
+        assert!(!is_newer_version("2025.08.11", "2025.07.21"));
+        assert!(!is_newer_version("unknown", "2025.07.21"));
+    }
+
+    #[test]
+    fn test_interpret_update_output_success() {
+        let updated = "Current version: stable@2025.07.21 from yt-dlp/yt-dlp\n\
+            Latest version: stable@2025.08.11 from yt-dlp/yt-dlp\n\
+            Current Build Hash: abc123\n\
+            Updating to stable@2025.08.11 from yt-dlp/yt-dlp ...\n\
+            Updated yt-dlp to stable@2025.08.11 from yt-dlp/yt-dlp\n";
+        assert_eq!(
+            interpret_update_output(updated, ""),
+            Ok("Updated yt-dlp to stable@2025.08.11 from yt-dlp/yt-dlp".to_string())
+        );
+
+        let up_to_date = "Latest version: stable@2025.08.11 from yt-dlp/yt-dlp\n\
+            yt-dlp is up to date (stable@2025.08.11 from yt-dlp/yt-dlp)\n";
+        assert_eq!(
+            interpret_update_output(up_to_date, ""),
+            Ok("yt-dlp is up to date (stable@2025.08.11 from yt-dlp/yt-dlp)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_interpret_update_output_failures() {
+        let pip = "ERROR: You installed yt-dlp with pip or using the wheel from PyPi; Use that to update";
+        assert!(interpret_update_output("", pip).unwrap_err().contains("pip install -U"));
+
+        let package = "ERROR: You installed yt-dlp from a manual build or with a package manager; Use that to update";
+        assert!(interpret_update_output("", package).unwrap_err().contains("package manager"));
+
+        let read_only = "ERROR: Unable to write to /usr/local/bin/yt-dlp; try running as administrator";
+        assert!(interpret_update_output("", read_only).unwrap_err().contains("not writable"));
+
+        let other = "ERROR: Unable to obtain version info (HTTP Error 403: Forbidden)";
+        assert_eq!(interpret_update_output("", other), Err(other.to_string()));
+
+        assert!(interpret_update_output("", "").is_err());
+    }
+
+    #[test]
+    fn test_build_update_status() {
+        let status = build_update_status("2025.07.21".to_string(), Some("2025.08.11".to_string()));
+        assert!(status.update_available);
+
+        let offline = build_update_status("2025.07.21".to_string(), None);
+        assert_eq!(offline.latest, None);
+        assert!(!offline.update_available);
+    }
 }
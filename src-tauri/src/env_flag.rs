@@ -0,0 +1,41 @@
+//! Boolean feature flags read from environment variables.
+//!
+//! Opt-in modes (`REMEDIA_MOCK`, `REMEDIA_SAFE_MODE`, `REMEDIA_ORPHAN_CLEANUP`) are
+//! enabled with "1" or "true" and read once per process, so toggling a variable
+//! after startup has no effect.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// Whether the flag in `var` is enabled ("1" or "true", case-insensitive; read once per process)
+pub fn env_flag_enabled(var: &'static str) -> bool {
+    static FLAGS: OnceLock<Mutex<HashMap<&'static str, bool>>> = OnceLock::new();
+
+    let mut flags = FLAGS.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+    *flags.entry(var).or_insert_with(|| std::env::var(var).is_ok_and(|v| parse_env_flag(&v)))
+}
+
+fn parse_env_flag(value: &str) -> bool {
+    matches!(value.trim().to_lowercase().as_str(), "1" | "true")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_env_flag() {
+        assert!(parse_env_flag("1"));
+        assert!(parse_env_flag(" TRUE "));
+        assert!(parse_env_flag("True"));
+        assert!(!parse_env_flag("0"));
+        assert!(!parse_env_flag("yes"));
+        assert!(!parse_env_flag(""));
+    }
+
+    #[test]
+    fn test_env_flag_enabled_defaults_to_off() {
+        assert!(!env_flag_enabled("REMEDIA_TEST_UNSET_FLAG"));
+        assert!(!env_flag_enabled("REMEDIA_TEST_UNSET_FLAG"), "cached value is reused");
+    }
+}
@@ -24,12 +24,14 @@ pub enum ErrorCode {
     EValInvalidSettings,
     EValInvalidPath,
     EValInvalidMediaIdx,
+    EValUnsupportedUrl,
 
     // IO errors (E_IO_*)
     EIoReadFailed,
     EIoWriteFailed,
     EIoNotFound,
     EIoPermissionDenied,
+    EIoDiskFull,
 
     // Download errors (E_DL_*)
     EDlSpawnFailed,
@@ -37,11 +39,14 @@ pub enum ErrorCode {
     EDlCancelled,
     EDlTimeout,
     EDlOutputUnavailable,
+    EDlFfmpegMissing,
+    EDlAuthRequired,
 
     // Network errors (E_NET_*)
     ENetConnectionFailed,
     ENetTimeout,
     ENetRateLimited,
+    ENetGeoBlocked,
 
     // Queue errors (E_Q_*)
     EQueueFull,
@@ -74,18 +79,23 @@ impl ErrorCode {
             Self::EValInvalidSettings => "E_VAL_INVALID_SETTINGS",
             Self::EValInvalidPath => "E_VAL_INVALID_PATH",
             Self::EValInvalidMediaIdx => "E_VAL_INVALID_MEDIA_IDX",
+            Self::EValUnsupportedUrl => "E_VAL_UNSUPPORTED_URL",
             Self::EIoReadFailed => "E_IO_READ_FAILED",
             Self::EIoWriteFailed => "E_IO_WRITE_FAILED",
             Self::EIoNotFound => "E_IO_NOT_FOUND",
             Self::EIoPermissionDenied => "E_IO_PERMISSION_DENIED",
+            Self::EIoDiskFull => "E_IO_DISK_FULL",
             Self::EDlSpawnFailed => "E_DL_SPAWN_FAILED",
             Self::EDlProcessFailed => "E_DL_PROCESS_FAILED",
             Self::EDlCancelled => "E_DL_CANCELLED",
             Self::EDlTimeout => "E_DL_TIMEOUT",
             Self::EDlOutputUnavailable => "E_DL_OUTPUT_UNAVAILABLE",
+            Self::EDlFfmpegMissing => "E_DL_FFMPEG_MISSING",
+            Self::EDlAuthRequired => "E_DL_AUTH_REQUIRED",
             Self::ENetConnectionFailed => "E_NET_CONNECTION_FAILED",
             Self::ENetTimeout => "E_NET_TIMEOUT",
             Self::ENetRateLimited => "E_NET_RATE_LIMITED",
+            Self::ENetGeoBlocked => "E_NET_GEO_BLOCKED",
             Self::EQueueFull => "E_Q_FULL",
             Self::EQueueDuplicate => "E_Q_DUPLICATE",
             Self::EQueueNotFound => "E_Q_NOT_FOUND",
@@ -94,6 +104,51 @@ impl ErrorCode {
             Self::EIntLockPoisoned => "E_INT_LOCK_POISONED",
         }
     }
+
+    /// Returns a user-facing suggestion for resolving this error, if one applies.
+    pub const fn hint(&self) -> Option<&'static str> {
+        match self {
+            Self::ENetGeoBlocked => Some("Content is not available in your region; try a proxy or VPN"),
+            Self::EIoDiskFull => Some("Disk is full; free up space, then resume the queue"),
+            Self::EDlFfmpegMissing => Some("ffmpeg is not installed; install it or enable avoid post-processing"),
+            Self::EDlAuthRequired => Some("Private or members-only content; use cookies from an account with access"),
+            Self::EValUnsupportedUrl => Some("yt-dlp does not recognize this site; check the link or update yt-dlp"),
+            _ => None,
+        }
+    }
+}
+
+/// Classify a yt-dlp stderr line into a stable error code, if it matches a known failure.
+pub fn classify_ytdlp_stderr(line: &str) -> Option<ErrorCode> {
+    let lower = line.to_lowercase();
+
+    if lower.contains("available in your country")
+        || lower.contains("geo restricted")
+        || lower.contains("geo-restricted")
+    {
+        return Some(ErrorCode::ENetGeoBlocked);
+    }
+
+    // "ERROR: Unsupported URL: https://..." - no extractor matched, so retrying can't help
+    if lower.contains("unsupported url") {
+        return Some(ErrorCode::EValUnsupportedUrl);
+    }
+
+    if lower.contains("no space left on device") {
+        return Some(ErrorCode::EIoDiskFull);
+    }
+
+    // Private and members-only videos need an account with access; age gates are a different failure
+    if lower.contains("private video") || lower.contains("available to this channel's members") {
+        return Some(ErrorCode::EDlAuthRequired);
+    }
+
+    // "Postprocessing: ffmpeg not found" or "requested merging ... but ffmpeg is not installed"
+    if lower.contains("ffmpeg not found") || (lower.contains("ffmpeg") && lower.contains("not installed")) {
+        return Some(ErrorCode::EDlFfmpegMissing);
+    }
+
+    None
 }
 
 /// Categories of validation errors.
@@ -103,6 +158,7 @@ pub enum ValidationKind {
     InvalidSettings,
     InvalidPath,
     InvalidMediaIdx,
+    UnsupportedUrl,
 }
 
 /// Kinds of queue errors. Use this to classify queue failures
@@ -176,6 +232,7 @@ impl DownloaderError {
                     ValidationKind::InvalidSettings => ErrorCode::EValInvalidSettings,
                     ValidationKind::InvalidPath => ErrorCode::EValInvalidPath,
                     ValidationKind::InvalidMediaIdx => ErrorCode::EValInvalidMediaIdx,
+                    ValidationKind::UnsupportedUrl => ErrorCode::EValUnsupportedUrl,
                 };
                 (code, false)
             }
@@ -236,6 +293,14 @@ impl DownloaderError {
         }
     }
 
+    /// Create an error for a well-formed URL that yt-dlp has no extractor for.
+    pub fn unsupported_url(message: impl Into<String>) -> Self {
+        Self::Validation {
+            kind: ValidationKind::UnsupportedUrl,
+            message: message.into(),
+        }
+    }
+
     /// Create a settings validation error.
     pub fn invalid_settings(message: impl Into<String>) -> Self {
         Self::Validation {
@@ -434,18 +499,23 @@ mod tests {
             ErrorCode::EValInvalidSettings,
             ErrorCode::EValInvalidPath,
             ErrorCode::EValInvalidMediaIdx,
+            ErrorCode::EValUnsupportedUrl,
             ErrorCode::EIoReadFailed,
             ErrorCode::EIoWriteFailed,
             ErrorCode::EIoNotFound,
             ErrorCode::EIoPermissionDenied,
+            ErrorCode::EIoDiskFull,
             ErrorCode::EDlSpawnFailed,
             ErrorCode::EDlProcessFailed,
             ErrorCode::EDlCancelled,
             ErrorCode::EDlTimeout,
             ErrorCode::EDlOutputUnavailable,
+            ErrorCode::EDlFfmpegMissing,
+            ErrorCode::EDlAuthRequired,
             ErrorCode::ENetConnectionFailed,
             ErrorCode::ENetTimeout,
             ErrorCode::ENetRateLimited,
+            ErrorCode::ENetGeoBlocked,
             ErrorCode::EQueueFull,
             ErrorCode::EQueueDuplicate,
             ErrorCode::EQueueNotFound,
@@ -461,6 +531,94 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_classify_ytdlp_stderr_geo_blocked() {
+        let lines = [
+            "ERROR: [youtube] abc123: This video is not available in your country",
+            "ERROR: [generic] The uploader has not made this video available in your country.",
+            "ERROR: [bbc] p0abc: This video is geo restricted",
+            "ERROR: [viki] 1234v: Geo-restricted content",
+        ];
+        for line in lines {
+            let code = classify_ytdlp_stderr(line);
+            assert_eq!(code, Some(ErrorCode::ENetGeoBlocked), "{line}");
+            assert_eq!(code.unwrap().as_str(), "E_NET_GEO_BLOCKED");
+        }
+
+        assert!(ErrorCode::ENetGeoBlocked.hint().unwrap().contains("proxy"));
+        assert!(!ErrorCode::ENetGeoBlocked.is_retryable());
+    }
+
+    #[test]
+    fn test_classify_ytdlp_stderr_unsupported_url() {
+        let line = "ERROR: Unsupported URL: https://example.com/about";
+        assert_eq!(classify_ytdlp_stderr(line), Some(ErrorCode::EValUnsupportedUrl));
+
+        let fe = DownloaderError::unsupported_url("no extractor").to_frontend_error();
+        assert_eq!(fe.code, "E_VAL_UNSUPPORTED_URL");
+        assert!(!fe.retryable);
+        assert!(ErrorCode::EValUnsupportedUrl.hint().unwrap().contains("update yt-dlp"));
+    }
+
+    #[test]
+    fn test_classify_ytdlp_stderr_disk_full() {
+        let lines = [
+            "ERROR: unable to write data: [Errno 28] No space left on device",
+            "[ffmpeg] av_interleaved_write_frame(): No space left on device",
+        ];
+        for line in lines {
+            assert_eq!(classify_ytdlp_stderr(line), Some(ErrorCode::EIoDiskFull), "{line}");
+        }
+
+        assert_eq!(ErrorCode::EIoDiskFull.as_str(), "E_IO_DISK_FULL");
+        assert!(ErrorCode::EIoDiskFull.hint().is_some());
+        assert!(!ErrorCode::EIoDiskFull.is_retryable());
+    }
+
+    #[test]
+    fn test_classify_ytdlp_stderr_auth_required() {
+        let lines = [
+            "ERROR: [youtube] abc123: Join this channel to get access to members-only content like this video, \
+             and other exclusive perks. This video is available to this channel's members on level: Tier 1",
+            "ERROR: [youtube] abc123: Private video. Sign in if you've been granted access to this video",
+        ];
+        for line in lines {
+            assert_eq!(classify_ytdlp_stderr(line), Some(ErrorCode::EDlAuthRequired), "{line}");
+        }
+
+        assert_eq!(ErrorCode::EDlAuthRequired.as_str(), "E_DL_AUTH_REQUIRED");
+        assert!(ErrorCode::EDlAuthRequired.hint().unwrap().contains("cookies"));
+        assert!(!ErrorCode::EDlAuthRequired.is_retryable());
+
+        // Age-restricted videos are not an access/membership problem
+        let age_gate =
+            "ERROR: [youtube] abc123: Sign in to confirm your age. This video may be inappropriate for some users.";
+        assert_ne!(classify_ytdlp_stderr(age_gate), Some(ErrorCode::EDlAuthRequired));
+    }
+
+    #[test]
+    fn test_classify_ytdlp_stderr_ffmpeg_missing() {
+        let lines = [
+            "ERROR: Postprocessing: ffmpeg not found. Please install or provide the path using --ffmpeg-location",
+            "ERROR: You have requested merging of multiple formats but ffmpeg is not installed. Aborting",
+            "WARNING: You have requested merging of multiple formats but ffmpeg is not installed",
+        ];
+        for line in lines {
+            assert_eq!(classify_ytdlp_stderr(line), Some(ErrorCode::EDlFfmpegMissing), "{line}");
+        }
+
+        assert_eq!(ErrorCode::EDlFfmpegMissing.as_str(), "E_DL_FFMPEG_MISSING");
+        assert!(ErrorCode::EDlFfmpegMissing.hint().unwrap().contains("post-processing"));
+        assert!(!ErrorCode::EDlFfmpegMissing.is_retryable());
+        assert_eq!(classify_ytdlp_stderr("[Merger] Merging formats into \"video.mp4\""), None);
+    }
+
+    #[test]
+    fn test_classify_ytdlp_stderr_unrecognized() {
+        assert_eq!(classify_ytdlp_stderr("ERROR: Unable to download webpage"), None);
+        assert_eq!(classify_ytdlp_stderr("[download] 50.0% of 10MiB"), None);
+    }
+
     #[test]
     fn test_download_error_frontend_conversion() {
         let err = DownloaderError::download(123, "spawn failed");
@@ -2,6 +2,7 @@
 //! Keep these in sync with `src/types/events.ts`.
 
 pub const EVT_UPDATE_MEDIA_INFO: &str = "update-media-info";
+pub const EVT_MEDIA_INFO_MULTI: &str = "media-info-multi";
 pub const EVT_DOWNLOAD_PROGRESS: &str = "download-progress";
 pub const EVT_DOWNLOAD_COMPLETE: &str = "download-complete";
 pub const EVT_DOWNLOAD_ERROR: &str = "download-error";
@@ -10,6 +11,19 @@ pub const EVT_DOWNLOAD_INVOKE_ACK: &str = "download-invoke-ack";
 pub const EVT_DOWNLOAD_CANCELLED: &str = "download-cancelled";
 pub const EVT_DOWNLOAD_STARTED: &str = "download-started";
 pub const EVT_DOWNLOAD_QUEUED: &str = "download-queued";
+pub const EVT_DOWNLOAD_SKIPPED: &str = "download-skipped";
+pub const EVT_QUEUE_CHANGED: &str = "queue-changed";
+pub const EVT_HOST_COOLDOWN: &str = "host-cooldown";
+pub const EVT_DISK_LOW: &str = "disk-low";
+pub const EVT_SUBFOLDER_FALLBACK: &str = "subfolder-fallback";
+pub const EVT_FFMPEG_MISSING: &str = "ffmpeg-missing";
+pub const EVT_METERED_CHANGED: &str = "metered-changed";
+pub const EVT_QUEUE_FULL: &str = "queue-full";
+pub const EVT_BATCH_SUMMARY: &str = "batch-summary";
+pub const EVT_DOWNLOAD_POSTPROCESS: &str = "download-postprocess";
+pub const EVT_DOWNLOAD_VERIFIED: &str = "download-verified";
+pub const EVT_DOWNLOAD_STATS: &str = "download-stats";
+pub const EVT_PLAYLIST_EXPAND_PROGRESS: &str = "playlist-expand-progress";
 pub const EVT_YTDLP_STDERR: &str = "yt-dlp-stderr";
 pub const EVT_REMOTE_ADD_URL: &str = "remote-add-url";
 pub const EVT_REMOTE_START: &str = "remote-start-downloads";
@@ -20,6 +34,9 @@ pub const EVT_REMOTE_SET_DOWNLOAD_DIR: &str = "remote-set-download-dir";
 // Startup/critical system events
 pub const EVT_STARTUP_ERROR: &str = "startup-error";
 
+// Debugging events
+pub const EVT_LOG_LINE: &str = "log-line";
+
 // Remote debugging events
 pub const EVT_DOWNLOAD_EXEC: &str = "download-exec";
 pub const EVT_DOWNLOAD_RAW: &str = "download-raw";
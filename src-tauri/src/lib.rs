@@ -19,12 +19,17 @@ impl std::error::Error for SetupError {}
 
 pub mod download_queue;
 pub mod downloader;
+pub mod env_flag;
 pub mod error;
 pub mod events;
+pub mod log_tail;
 pub mod logging;
 pub mod redgifs;
 pub mod remedia;
 pub mod remote_control;
+pub mod safe_mode;
+#[cfg(test)]
+mod test_dir;
 pub mod thumbnail;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -69,7 +74,7 @@ pub fn run() {
         if let Err(e) = downloader::start_queue_pump(app.app_handle().clone()) {
             // Log and fail setup so the app doesn't start in a non-functional state
             crate::logging::log_error_simple(
-                &app.app_handle(),
+                app.app_handle(),
                 crate::logging::ErrorCategory::System,
                 "Failed to initialize download queue pump",
                 Some(&e),
@@ -85,6 +90,9 @@ pub fn run() {
             return Err(Box::new(SetupError(e)));
         }
 
+        // Merging, remuxing and embedding need ffmpeg; warn early if it is missing
+        downloader::start_ffmpeg_probe(app.app_handle().clone());
+
         let enable_remote_env = std::env::var("ENABLE_REMOTE_HARNESS").ok();
         let enable_remote = enable_remote_env.as_deref().map(|v| v == "1").unwrap_or(cfg!(debug_assertions));
         eprintln!(
@@ -102,14 +110,75 @@ pub fn run() {
         Ok(())
     });
 
+    // Closing the main window ends the app; stop downloads off the UI thread so no yt-dlp
+    // process is orphaned, then exit
+    builder = builder.on_window_event(|window, event| {
+        if window.label() == "main" && matches!(event, tauri::WindowEvent::Destroyed) {
+            let app = window.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                downloader::shutdown_downloads(downloader::SHUTDOWN_GRACE).await;
+                app.exit(0);
+            });
+        }
+    });
+
     builder = builder.invoke_handler(tauri::generate_handler![
+        downloader::commands::add_url,
         downloader::commands::get_media_info,
+        downloader::commands::get_media_info_batch,
+        downloader::commands::cancel_media_info_batch,
+        downloader::commands::resync_media_info,
+        downloader::commands::clear_media_info_cache,
         downloader::commands::expand_playlist,
+        downloader::commands::is_playlist,
+        downloader::commands::fetch_channel_art,
+        downloader::commands::resume_playlist,
+        downloader::commands::cancel_playlist_expansion,
+        downloader::commands::compute_folder_slug,
+        downloader::commands::preview_folder_name,
+        downloader::commands::compute_unique_id,
+        downloader::commands::import_urls,
+        downloader::commands::export_queue,
+        downloader::commands::validate_download_settings,
+        downloader::commands::validate_batch,
+        downloader::commands::check_remux_compat,
+        downloader::commands::normalize_path,
+        downloader::commands::check_resumable,
+        downloader::commands::resolve_output_path,
         downloader::commands::download_media,
         downloader::commands::cancel_download,
+        downloader::commands::cancel_current_download,
+        downloader::commands::cancel_stale_downloads,
         downloader::commands::cancel_all_downloads,
+        downloader::commands::find_orphaned_ytdlp,
+        downloader::commands::kill_orphaned_ytdlp,
         downloader::commands::set_max_concurrent_downloads,
+        downloader::commands::set_drain_overflow_on_decrease,
+        downloader::commands::set_download_subfolder,
+        downloader::commands::sort_queue,
+        downloader::commands::set_auto_concurrency,
+        downloader::commands::set_collection_concurrency,
+        downloader::commands::set_fragment_budget,
+        downloader::commands::set_max_queued,
+        downloader::commands::set_dedupe_urls,
+        downloader::commands::set_queue_paused,
+        downloader::commands::set_metered,
         downloader::commands::get_queue_status,
+        downloader::commands::get_free_slots,
+        downloader::commands::get_download_eta,
+        downloader::commands::get_download_output,
+        downloader::commands::get_progress_history,
+        downloader::commands::set_default_output_location,
+        downloader::commands::get_default_output_location,
+        downloader::commands::health_check,
+        downloader::commands::check_host_reachable,
+        downloader::commands::check_ytdlp_update,
+        downloader::commands::update_ytdlp,
+        log_tail::start_log_tail,
+        log_tail::stop_log_tail,
+        logging::rotate_logs_now,
+        logging::get_paths,
+        thumbnail::clear_thumbnail_cache,
         remedia::set_always_on_top,
         remedia::is_wayland,
         remedia::is_wsl,
@@ -121,5 +190,14 @@ pub fn run() {
         remote_control::debug_broadcast
     ]);
 
-    builder.run(tauri::generate_context!()).expect("error while running tauri application");
+    let app = builder.build(tauri::generate_context!()).expect("error while running tauri application");
+    app.run(|_app, event| {
+        // Closing the last window requests exit by itself; hold it while the shutdown above
+        // is still stopping downloads, since it exits once they are gone
+        if let tauri::RunEvent::ExitRequested { code: None, api, .. } = event
+            && !downloader::registered_downloads().is_empty()
+        {
+            api.prevent_exit();
+        }
+    });
 }
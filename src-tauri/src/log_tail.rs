@@ -0,0 +1,154 @@
+//! Live tail of the yt-dlp log file for debugging.
+//!
+//! `start_log_tail` spawns a poll-based watcher that emits every newly appended
+//! line of `remedia-yt-dlp.log` as an `EVT_LOG_LINE` event; `stop_log_tail` halts it.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter};
+
+use crate::events::EVT_LOG_LINE;
+use crate::logging::resolve_log_path;
+
+/// Interval between checks for appended log data
+const TAIL_POLL_INTERVAL_MS: u64 = 500;
+
+/// Incremented on every start/stop; a running tail task exits once it no longer matches
+static TAIL_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Incremental reader that returns only the complete lines appended since the last read
+#[derive(Debug, Default)]
+pub struct LogTail {
+    offset: u64,
+}
+
+impl LogTail {
+    /// Start tailing from the current end of the file (existing content is skipped)
+    pub fn from_end(path: &Path) -> Self {
+        let offset = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        Self { offset }
+    }
+
+    /// Read lines appended since the previous call.
+    ///
+    /// A trailing line without a newline is left for the next read. If the file
+    /// shrank (rotation or truncation), reading restarts from the beginning.
+    pub fn read_new_lines(&mut self, path: &Path) -> io::Result<Vec<String>> {
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let len = file.metadata()?.len();
+        if len < self.offset {
+            self.offset = 0;
+        }
+        if len == self.offset {
+            return Ok(Vec::new());
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut buf = Vec::new();
+        file.take(len - self.offset).read_to_end(&mut buf)?;
+
+        let Some(last_newline) = buf.iter().rposition(|&b| b == b'\n') else {
+            return Ok(Vec::new());
+        };
+        self.offset += last_newline as u64 + 1;
+
+        Ok(String::from_utf8_lossy(&buf[..last_newline])
+            .lines()
+            .map(|line| line.to_string())
+            .collect())
+    }
+}
+
+/// Start emitting appended yt-dlp log lines as `EVT_LOG_LINE` events.
+/// Calling this while a tail is running restarts it.
+#[tauri::command]
+pub fn start_log_tail(app: AppHandle) -> Result<(), String> {
+    let path = resolve_log_path(&app).ok_or_else(|| "Unable to resolve yt-dlp log path".to_string())?;
+    let generation = TAIL_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    tauri::async_runtime::spawn(async move {
+        let mut tail = LogTail::from_end(&path);
+
+        while TAIL_GENERATION.load(Ordering::SeqCst) == generation {
+            match tail.read_new_lines(&path) {
+                Ok(lines) => {
+                    for line in lines {
+                        let _ = app.emit(EVT_LOG_LINE, line);
+                    }
+                }
+                Err(e) => eprintln!("Failed to tail yt-dlp log {}: {}", path.display(), e),
+            }
+
+            tokio::time::sleep(Duration::from_millis(TAIL_POLL_INTERVAL_MS)).await;
+        }
+    });
+
+    Ok(())
+}
+
+/// Stop the running log tail, if any
+#[tauri::command]
+pub fn stop_log_tail() {
+    TAIL_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+    use crate::test_dir::TestDir;
+
+    fn append(path: &Path, data: &str) {
+        let mut file = OpenOptions::new().create(true).append(true).open(path).unwrap();
+        file.write_all(data.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_read_new_lines_as_file_grows() {
+        let dir = TestDir::new("log-tail");
+        let path = dir.join("remedia.log");
+        append(&path, "old line\n");
+
+        let mut tail = LogTail::from_end(&path);
+        assert!(tail.read_new_lines(&path).unwrap().is_empty());
+
+        append(&path, "first\nsecond\n");
+        assert_eq!(tail.read_new_lines(&path).unwrap(), vec!["first", "second"]);
+        assert!(tail.read_new_lines(&path).unwrap().is_empty());
+
+        // Partial line is held back until its newline arrives
+        append(&path, "thi");
+        assert!(tail.read_new_lines(&path).unwrap().is_empty());
+        append(&path, "rd\n");
+        assert_eq!(tail.read_new_lines(&path).unwrap(), vec!["third"]);
+    }
+
+    #[test]
+    fn test_read_new_lines_restarts_after_rotation() {
+        let dir = TestDir::new("log-tail");
+        let path = dir.join("remedia.log");
+        append(&path, "a fairly long line before rotation\n");
+
+        let mut tail = LogTail::from_end(&path);
+        fs::write(&path, "fresh\n").unwrap();
+        assert_eq!(tail.read_new_lines(&path).unwrap(), vec!["fresh"]);
+    }
+
+    #[test]
+    fn test_read_new_lines_missing_file() {
+        let dir = TestDir::new("log-tail");
+        let path = dir.join("remedia.log");
+        let mut tail = LogTail::default();
+        assert!(tail.read_new_lines(&path).unwrap().is_empty());
+    }
+}
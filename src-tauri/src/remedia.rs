@@ -1,7 +1,9 @@
 use tauri::{AppHandle, WebviewUrl, WebviewWindowBuilder, Window};
 
 #[tauri::command]
-pub(crate) fn quit(app: AppHandle) {
+pub(crate) async fn quit(app: AppHandle) {
+    // Don't leave yt-dlp children running after exit
+    crate::downloader::shutdown_downloads(crate::downloader::SHUTDOWN_GRACE).await;
     app.exit(0);
 }
 
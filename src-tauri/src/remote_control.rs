@@ -1,13 +1,15 @@
 use futures_util::{SinkExt, StreamExt};
-use std::sync::OnceLock;
-use serde::Deserialize;
-use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{LazyLock, OnceLock};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
 use std::env;
 use std::net::SocketAddr;
 use std::process;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::net::TcpListener;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::{Mutex, broadcast};
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::{WebSocketStream, accept_async};
@@ -15,10 +17,12 @@ use uuid::Uuid;
 
 use tauri::{AppHandle, Emitter, Event, Listener, Manager};
 
-use crate::downloader::commands::{download_media, get_queue_status};
-use crate::downloader::DownloadSettings;
+use crate::download_queue::QueueStatus;
+use crate::downloader::commands::{download_media, get_queue_status, set_max_concurrent_downloads};
+use crate::downloader::{default_output_location, DownloadSettings};
 use crate::events::*;
 use crate::logging::{ErrorCategory, log_debug_simple, log_error_simple, log_info_simple};
+use crate::safe_mode::{is_safe_mode, is_script_action};
 
 pub type RemoteEmitter = Arc<dyn Fn(&str, Value) + Send + Sync + 'static>;
 pub type RemoteEval = Arc<dyn Fn(&str) -> Result<(), String> + Send + Sync + 'static>;
@@ -26,6 +30,42 @@ pub type RemoteEval = Arc<dyn Fn(&str) -> Result<(), String> + Send + Sync + 'st
 // Broadcast channel used to push app events back to remote test clients.
 static REMOTE_BROADCAST: OnceLock<broadcast::Sender<String>> = OnceLock::new();
 
+/// Messages buffered per client before a slow one starts missing events
+const REMOTE_BROADCAST_CAPACITY: usize = 128;
+
+static BROADCAST_METRICS: LazyLock<std::sync::Mutex<BroadcastMetrics>> =
+    LazyLock::new(|| std::sync::Mutex::new(BroadcastMetrics::default()));
+
+/// Broadcasts remote clients missed because they fell behind (`RecvError::Lagged`),
+/// reported by the `metrics` action
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BroadcastMetrics {
+    /// Dropped since startup, across all connections
+    total_dropped: u64,
+    /// Dropped per open connection, keyed by the connection id sent in `remote-hello`
+    dropped_by_connection: HashMap<String, u64>,
+}
+
+fn with_broadcast_metrics<R>(f: impl FnOnce(&mut BroadcastMetrics) -> R) -> R {
+    f(&mut BROADCAST_METRICS.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Next broadcast for one connection, counting messages it skipped by lagging.
+/// None once the channel is closed.
+async fn recv_broadcast(rx: &mut broadcast::Receiver<String>, conn_id: &str) -> Option<String> {
+    loop {
+        match rx.recv().await {
+            Ok(msg) => return Some(msg),
+            Err(RecvError::Lagged(skipped)) => with_broadcast_metrics(|metrics| {
+                metrics.total_dropped += skipped;
+                *metrics.dropped_by_connection.entry(conn_id.to_string()).or_insert(0) += skipped;
+            }),
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
 /// Check if any remote clients are connected (O(1) check to skip serialization overhead).
 pub fn is_remote_active() -> bool {
     REMOTE_BROADCAST.get().is_some_and(|tx| tx.receiver_count() > 0)
@@ -91,6 +131,8 @@ struct RemoteCommand {
     url: Option<String>,
     path: Option<String>,
     media_idx: Option<i32>,
+    /// Value for `setMaxConcurrent` (falls back to `mediaIdx`)
+    max_concurrent: Option<i64>,
     /// Arbitrary JSON data for debug commands
     data: Option<Value>,
 }
@@ -99,7 +141,7 @@ type WsStream = WebSocketStream<tokio::net::TcpStream>;
 type WsSink = futures_util::stream::SplitSink<WsStream, Message>;
 type WsSource = futures_util::stream::SplitStream<WsStream>;
 
-fn build_remote_hello() -> String {
+fn build_remote_hello(conn_id: &str) -> String {
     let env_flag = env::var("ENABLE_REMOTE_HARNESS").ok();
     let tauri_env = env::var("TAURI_ENVIRONMENT").ok();
     let ts = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
@@ -107,6 +149,7 @@ fn build_remote_hello() -> String {
         "event": "remote-hello",
         "payload": {
             "pid": process::id(),
+            "connId": conn_id,
             "enableRemoteHarnessEnv": env_flag,
             "debugFallback": cfg!(debug_assertions),
             "tauriEnv": tauri_env,
@@ -116,12 +159,89 @@ fn build_remote_hello() -> String {
     .to_string()
 }
 
+/// Reply to a remote command: `{"ok", "action", "error"}` plus any action-specific fields.
+/// `action` is only omitted when the command could not be parsed.
+#[derive(Debug, Serialize)]
+struct RemoteResponse {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    action: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(flatten)]
+    fields: Map<String, Value>,
+}
+
+impl RemoteResponse {
+    fn ok(action: &str) -> Self {
+        Self { ok: true, action: Some(action.to_string()), error: None, fields: Map::new() }
+    }
+
+    fn error(action: &str, error: impl Into<String>) -> Self {
+        Self { ok: false, action: Some(action.to_string()), error: Some(error.into()), fields: Map::new() }
+    }
+
+    /// Add an action-specific field (e.g. `status`, `result`)
+    fn with(mut self, key: &str, value: impl Serialize) -> Self {
+        self.fields.insert(key.to_string(), json!(value));
+        self
+    }
+}
+
+/// Event pushed to remote clients: `{"event", "payload"}`
+#[derive(Debug, Serialize)]
+struct RemoteNotice {
+    event: String,
+    payload: Value,
+}
+
+impl RemoteNotice {
+    fn new(event: &str, payload: Value) -> Self {
+        Self { event: event.to_string(), payload }
+    }
+
+    /// `remote-recv` acknowledgement echoing the handled command, e.g. "addUrl <url>"
+    fn received(summary: impl Into<String>) -> Self {
+        Self::new(EVT_REMOTE_RECV, Value::String(summary.into()))
+    }
+}
+
+/// Messages sent back for one command, in order
+type Replies = Vec<Value>;
+
+fn reply(replies: &mut Replies, message: impl Serialize) {
+    replies.push(json!(message));
+}
+
+/// Reply to `setMaxConcurrent`: validate the requested value (at least 1), apply it and report the queue status
+fn set_max_concurrent_response(
+    requested: Option<i64>,
+    apply: impl FnOnce(usize) -> Result<QueueStatus, String>,
+) -> RemoteResponse {
+    let result = match requested {
+        None => Err("maxConcurrent required".to_string()),
+        Some(n) if n < 1 => Err("maxConcurrent must be at least 1".to_string()),
+        Some(n) => usize::try_from(n).map_err(|e| e.to_string()).and_then(apply),
+    };
+
+    match result {
+        Ok(status) => RemoteResponse::ok("setMaxConcurrent").with("status", status),
+        Err(e) => RemoteResponse::error("setMaxConcurrent", e),
+    }
+}
+
+/// Error response for an action safe mode refuses, or None if the action may run
+fn safe_mode_rejection(action: &str, safe_mode: bool) -> Option<RemoteResponse> {
+    (safe_mode && is_script_action(action)).then(|| RemoteResponse::error(action, "disabled in safe mode"))
+}
+
 async fn handle_socket(
     mut rx: WsSource,
     tx: Arc<Mutex<WsSink>>,
     emitter: RemoteEmitter,
     eval: RemoteEval,
     app: Option<AppHandle>,
+    conn_id: String,
 ) {
     while let Some(msg) = rx.next().await {
         let Ok(msg) = msg else {
@@ -133,492 +253,298 @@ async fn handle_socket(
         }
 
         let text = msg.into_text().unwrap_or_default();
-        let cmd: RemoteCommand = match serde_json::from_str(&text) {
-            Ok(c) => c,
-            Err(e) => {
-                let _ = tx
-                    .lock()
-                    .await
-                    .send(Message::Text(format!(r#"{{"ok":false,"error":"bad command: {e}"}}"#).into()))
-                    .await;
-                continue;
-            }
+        let replies = match serde_json::from_str::<RemoteCommand>(&text) {
+            Ok(cmd) => handle_command(cmd, &emitter, &eval, &app, &conn_id).await,
+            Err(e) => vec![json!({"ok": false, "error": format!("bad command: {e}")})],
         };
 
-        match cmd.action.as_str() {
-            "addUrl" => {
-                if let Some(url) = cmd.url {
-                    emitter(EVT_REMOTE_ADD_URL, Value::String(url.clone()));
-                    let _ = tx
-                        .lock()
-                        .await
-                        .send(Message::Text(r#"{"ok":true,"action":"addUrl"}"#.to_string().into()))
-                        .await;
-                    let _ = tx
-                        .lock()
-                        .await
-                        .send(Message::Text(format!(r#"{{"event":"remote-recv","payload":"addUrl {url}"}}"#).into()))
-                        .await;
-                } else {
-                    let _ = tx
-                        .lock()
-                        .await
-                        .send(Message::Text(
-                            r#"{"ok":false,"error":"url required","action":"addUrl"}"#.to_string().into(),
-                        ))
-                        .await;
-                }
-            }
-            "startDownloads" => {
-                emitter(EVT_REMOTE_START, Value::Null);
-                let _ = tx
-                    .lock()
-                    .await
-                    .send(Message::Text(r#"{"ok":true,"action":"startDownloads"}"#.to_string().into()))
-                    .await;
-                let _ = tx
-                    .lock()
-                    .await
-                    .send(Message::Text(r#"{"event":"remote-recv","payload":"startDownloads"}"#.to_string().into()))
-                    .await;
-            }
-            "cancelAll" => {
-                emitter(EVT_REMOTE_CANCEL, Value::Null);
-                let _ =
-                    tx.lock().await.send(Message::Text(r#"{"ok":true,"action":"cancelAll"}"#.to_string().into())).await;
-                let _ = tx
-                    .lock()
-                    .await
-                    .send(Message::Text(r#"{"event":"remote-recv","payload":"cancelAll"}"#.to_string().into()))
-                    .await;
+        for message in replies {
+            let _ = tx.lock().await.send(Message::Text(message.to_string().into())).await;
+        }
+    }
+}
+
+/// Run one remote command from connection `conn_id` and collect its replies
+async fn handle_command(
+    cmd: RemoteCommand,
+    emitter: &RemoteEmitter,
+    eval: &RemoteEval,
+    app: &Option<AppHandle>,
+    conn_id: &str,
+) -> Replies {
+    let mut replies = Replies::new();
+    let action = cmd.action.as_str();
+
+    if let Some(rejection) = safe_mode_rejection(action, is_safe_mode()) {
+        reply(&mut replies, rejection);
+        return replies;
+    }
+
+    match action {
+        "addUrl" => {
+            if let Some(url) = cmd.url {
+                emitter(EVT_REMOTE_ADD_URL, Value::String(url.clone()));
+                reply(&mut replies, RemoteResponse::ok(action));
+                reply(&mut replies, RemoteNotice::received(format!("addUrl {url}")));
+            } else {
+                reply(&mut replies, RemoteResponse::error(action, "url required"));
             }
-            "clearList" => {
-                emitter(EVT_REMOTE_CLEAR_LIST, Value::Null);
-                let _ =
-                    tx.lock().await.send(Message::Text(r#"{"ok":true,"action":"clearList"}"#.to_string().into())).await;
-                let _ = tx
-                    .lock()
-                    .await
-                    .send(Message::Text(r#"{"event":"remote-recv","payload":"clearList"}"#.to_string().into()))
-                    .await;
+        }
+        "startDownloads" | "cancelAll" | "clearList" => {
+            let event = match action {
+                "startDownloads" => EVT_REMOTE_START,
+                "cancelAll" => EVT_REMOTE_CANCEL,
+                _ => EVT_REMOTE_CLEAR_LIST,
+            };
+            emitter(event, Value::Null);
+            reply(&mut replies, RemoteResponse::ok(action));
+            reply(&mut replies, RemoteNotice::received(action));
+        }
+        "setDownloadDir" => {
+            if let Some(path) = cmd.path.or(cmd.url) {
+                emitter(EVT_REMOTE_SET_DOWNLOAD_DIR, Value::String(path.clone()));
+                reply(&mut replies, RemoteResponse::ok(action));
+                reply(&mut replies, RemoteNotice::received(format!("setDownloadDir {path}")));
+            } else {
+                reply(&mut replies, RemoteResponse::error(action, "path required"));
             }
-            "setDownloadDir" => {
-                if let Some(path) = cmd.path.or(cmd.url) {
-                    emitter(EVT_REMOTE_SET_DOWNLOAD_DIR, Value::String(path.clone()));
-                    let _ = tx
-                        .lock()
-                        .await
-                        .send(Message::Text(r#"{"ok":true,"action":"setDownloadDir"}"#.to_string().into()))
-                        .await;
-                    let _ = tx
-                        .lock()
-                        .await
-                        .send(Message::Text(
-                            format!(r#"{{"event":"remote-recv","payload":"setDownloadDir {path}"}}"#).into(),
-                        ))
-                        .await;
-                } else {
-                    let _ = tx
-                        .lock()
-                        .await
-                        .send(Message::Text(
-                            r#"{"ok":false,"action":"setDownloadDir","error":"path required"}"#.to_string().into(),
-                        ))
-                        .await;
+        }
+        "setMaxConcurrent" => {
+            let requested = cmd.max_concurrent.or(cmd.media_idx.map(i64::from));
+            let response = set_max_concurrent_response(requested, |max| match app {
+                Some(app_handle) => set_max_concurrent_downloads(app_handle.clone(), max),
+                None => Err("app handle unavailable".to_string()),
+            });
+            reply(&mut replies, response);
+        }
+        "status" => {
+            let status = get_queue_status();
+            let response = RemoteResponse::ok(action)
+                .with("queued", status.queued)
+                .with("active", status.active)
+                .with("max", status.max_concurrent);
+            reply(&mut replies, response);
+            reply(&mut replies, RemoteNotice::received(action));
+        }
+        "metrics" => {
+            let metrics = with_broadcast_metrics(|metrics| metrics.clone());
+            let dropped = metrics.dropped_by_connection.get(conn_id).copied().unwrap_or(0);
+            let response = RemoteResponse::ok(action)
+                .with("broadcastCapacity", REMOTE_BROADCAST_CAPACITY)
+                .with("totalDropped", metrics.total_dropped)
+                .with("dropped", dropped)
+                .with("droppedByConnection", metrics.dropped_by_connection);
+            reply(&mut replies, response);
+        }
+        "debugEcho" => {
+            // Echo arbitrary data back as an event for debugging
+            reply(&mut replies, RemoteNotice::new(EVT_DEBUG_ECHO, cmd.data.unwrap_or(Value::Null)));
+        }
+        "runJs" => {
+            if let Some(script) = cmd.url {
+                match eval(script.as_str()) {
+                    Ok(_) => {
+                        reply(&mut replies, RemoteResponse::ok(action));
+                        reply(&mut replies, RemoteNotice::received(format!("runJs {script}")));
+                    }
+                    Err(e) => reply(&mut replies, RemoteResponse::error(action, e)),
                 }
+            } else {
+                reply(&mut replies, RemoteResponse::error(action, "script required"));
             }
-            "status" => {
-                let status = get_queue_status();
-                let _ = tx
-                    .lock()
-                    .await
-                    .send(Message::Text(
-                        format!(
-                            r#"{{"ok":true,"action":"status","queued":{},"active":{},"max":{}}}"#,
-                            status.queued, status.active, status.max_concurrent
-                        )
-                        .into(),
-                    ))
-                    .await;
-                let _ = tx
-                    .lock()
-                    .await
-                    .send(Message::Text(r#"{"event":"remote-recv","payload":"status"}"#.to_string().into()))
-                    .await;
-            }
-            "debugEcho" => {
-                // Echo arbitrary data back as an event for debugging
-                let data = cmd.data.unwrap_or(json!(null));
-                let _ = tx
-                    .lock()
-                    .await
-                    .send(Message::Text(json!({"event": EVT_DEBUG_ECHO, "payload": data}).to_string().into()))
-                    .await;
-            }
-            "runJs" => {
-                if let Some(script) = cmd.url {
-                    match eval(script.as_str()) {
-                        Ok(_) => {
-                            let _ = tx
-                                .lock()
-                                .await
-                                .send(Message::Text(r#"{"ok":true,"action":"runJs"}"#.to_string().into()))
-                                .await;
-                            let _ = tx
-                                .lock()
-                                .await
-                                .send(Message::Text(
-                                    format!(r#"{{"event":"remote-recv","payload":"runJs {script}"}}"#).into(),
-                                ))
-                                .await;
-                        }
-                        Err(e) => {
-                            let _ = tx
-                                .lock()
-                                .await
-                                .send(Message::Text(
-                                    format!(r#"{{"ok":false,"action":"runJs","error":"{}"}}"#, e).into(),
-                                ))
-                                .await;
+        }
+        // Run JS and automatically broadcast window.__DEBUG_RESULT if set
+        "runJsCapture" => {
+            if let Some(script) = cmd.url {
+                // Run the provided script
+                let _ = eval(script.as_str());
+
+                // Wait for async script completion
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+                // Read __DEBUG_RESULT and broadcast it via a callback script
+                let broadcast_script = r#"
+                    (function() {
+                        var result = window.__DEBUG_RESULT || null;
+                        if (result) {
+                            // Clear after reading
+                            delete window.__DEBUG_RESULT;
+                            // Store in DOM for retrieval
+                            document.body.setAttribute('data-remote-debug-result', result);
                         }
-                    }
-                } else {
-                    let _ = tx
-                        .lock()
-                        .await
-                        .send(Message::Text(
-                            r#"{"ok":false,"action":"runJs","error":"script required"}"#.to_string().into(),
-                        ))
-                        .await;
-                }
+                    })();
+                "#;
+                let _ = eval(broadcast_script);
+
+                // Wait a bit more, then read the DOM attribute via another eval
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+                // Use a simple polling approach - have the script set a known value
+                // Since we can't read eval results, we'll use a workaround:
+                // The script already stored result in window.__DEBUG_RESULT
+                // We send a response indicating the script ran
+                reply(&mut replies, RemoteResponse::ok(action).with("note", "check debug-echo for result"));
+            } else {
+                reply(&mut replies, RemoteResponse::error(action, "script required"));
             }
-            // Run JS and automatically broadcast window.__DEBUG_RESULT if set
-            "runJsCapture" => {
-                if let Some(script) = cmd.url {
-                    // Run the provided script
-                    let _ = eval(script.as_str());
-
-                    // Wait for async script completion
-                    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-
-                    // Read __DEBUG_RESULT and broadcast it via a callback script
-                    let broadcast_script = r#"
-                        (function() {
-                            var result = window.__DEBUG_RESULT || null;
-                            if (result) {
-                                // Clear after reading
-                                delete window.__DEBUG_RESULT;
-                                // Store in DOM for retrieval
-                                document.body.setAttribute('data-remote-debug-result', result);
-                            }
-                        })();
-                    "#;
-                    let _ = eval(broadcast_script);
-
-                    // Wait a bit more, then read the DOM attribute via another eval
-                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-
-                    // Use a simple polling approach - have the script set a known value
-                    // Since we can't read eval results, we'll use a workaround:
-                    // The script already stored result in window.__DEBUG_RESULT
-                    // We send a response indicating the script ran
-                    let _ =
-                        tx.lock()
-                            .await
-                            .send(
-                                Message::Text(
-                                    r#"{"ok":true,"action":"runJsCapture","note":"check debug-echo for result"}"#
-                                        .to_string()
-                                        .into(),
-                                ),
-                            )
-                            .await;
-                } else {
-                    let _ = tx
-                        .lock()
-                        .await
-                        .send(Message::Text(
-                            r#"{"ok":false,"action":"runJsCapture","error":"script required"}"#.to_string().into(),
-                        ))
-                        .await;
-                }
+        }
+        // Run JS and read result from document.body.dataset.debugResult, then broadcast
+        "runJsGetResult" => {
+            let Some(script) = cmd.url else {
+                reply(&mut replies, RemoteResponse::error(action, "script required"));
+                return replies;
+            };
+
+            // Execute the provided script and propagate eval errors back to the caller
+            if let Err(e) = eval(script.as_str()) {
+                reply(&mut replies, RemoteResponse::error(action, format!("eval failed: {}", e)));
+                return replies;
             }
-            // Run JS and read result from document.body.dataset.debugResult, then broadcast
-            "runJsGetResult" => {
-                if let Some(script) = cmd.url {
-                    // Execute the provided script and propagate eval errors back to the caller
-                    if let Err(e) = eval(script.as_str()) {
-                        let _ = tx
-                            .lock()
-                            .await
-                            .send(Message::Text(
-                                format!(r#"{{"ok":false,"action":"runJsGetResult","error":"eval failed: {}"}}"#, e)
-                                    .into(),
-                            ))
-                            .await;
-                        continue;
-                    }
 
-                    // Give the script a moment to complete
-                    tokio::time::sleep(Duration::from_millis(100)).await;
-
-                    // Build a unique event name using a UUID so we can listen for a one-off result
-                    let result_event_name = format!("remote-get-result-{}", Uuid::new_v4());
-
-                    // If we have an app handle, create a one-off listener for the result event
-                    if let Some(handle) = &app {
-                        let (result_tx, mut result_rx) = tokio::sync::mpsc::channel::<String>(1);
-                        let result_tx_clone = result_tx.clone();
-                        let app_for_listen = handle.clone();
-
-                        // Register a listener that forwards any payload into our mpsc channel.
-                        // The closure is synchronous but we spawn an async task to enqueue into the channel.
-                        let listener_id = app_for_listen.listen(&result_event_name, move |evt: Event| {
-                            let payload = evt.payload().to_string();
-                            let tx = result_tx_clone.clone();
-                            tauri::async_runtime::spawn(async move {
-                                let _ = tx.send(payload).await;
-                            });
-                        });
-
-                        // Build a script that reads fallback locations, clears them, and emits the
-                        // unique event with the captured payload. This tries to be resilient
-                        // across environments and emits a JSON string if the result is an object.
-                        let followup = format!(
-                            r#"
-                            (function() {{
-                                try {{
-                                    var result = window.__REMOTE_DEBUG_LAST_RESULT || document.body.dataset.debugResult || localStorage.getItem('__debug_result') || null;
-                                    delete window.__REMOTE_DEBUG_LAST_RESULT;
-                                    try {{ delete document.body.dataset.debugResult; }} catch(e) {{}}
-                                    try {{ localStorage.removeItem('__debug_result'); }} catch(e) {{}}
-                                    var emit = window.__TAURI__ && window.__TAURI__.event && window.__TAURI__.event.emit;
-                                    try {{
-                                        if (emit) {{
-                                            // If result looks like a JSON string/object, try to parse it
-                                            var payload = result;
-                                            try {{ payload = JSON.parse(result); }} catch(e) {{ /* not JSON */ }}
-                                            emit("{event}", payload);
-                                        }} else {{
-                                            // As a fallback, set the global so it can be polled
-                                            window.__REMOTE_DEBUG_LAST_RESULT = result;
-                                        }}
-                                    }} catch(e) {{
-                                        if (emit) {{ emit("{event}", {{"__error": String(e)}}); }}
-                                    }}
-                                }} catch(e) {{
-                                    var emit = window.__TAURI__ && window.__TAURI__.event && window.__TAURI__.event.emit;
-                                    if (emit) {{ emit("{event}", {{"__error": String(e)}}); }}
-                                }}
-                            }})();
-                        "#,
-                            event = result_event_name
-                        );
+            // Give the script a moment to complete
+            tokio::time::sleep(Duration::from_millis(100)).await;
 
-                        // Run the follow-up eval and propagate eval errors
-                        if let Err(e) = eval(&followup) {
-                            let _ = tx
-                                .lock()
-                                .await
-                                .send(Message::Text(
-                                    format!(r#"{{"ok":false,"action":"runJsGetResult","error":"followup eval failed: {}"}}"#, e).into(),
-                                ))
-                                .await;
-                            // Cleanup the listener
-                            handle.unlisten(listener_id);
-                            continue;
-                        }
+            // Build a unique event name using a UUID so we can listen for a one-off result
+            let result_event_name = format!("remote-get-result-{}", Uuid::new_v4());
 
-                        // Wait a short while for the listener to receive the payload
-                        match tokio::time::timeout(Duration::from_millis(2000), result_rx.recv()).await {
-                            Ok(Some(payload)) => {
-                                // Try to parse as JSON, fallback to string
-                                let value: Value = serde_json::from_str(&payload).unwrap_or_else(|_| json!(payload));
-                                let _ = tx
-                                    .lock()
-                                    .await
-                                    .send(Message::Text(
-                                        json!({"ok":true, "action":"runJsGetResult", "result": value})
-                                            .to_string()
-                                            .into(),
-                                    ))
-                                    .await;
-                            }
-                            Ok(None) => {
-                                let _ =
-                                    tx.lock()
-                                        .await
-                                        .send(Message::Text(
-                                            r#"{"ok":false,"action":"runJsGetResult","error":"no result received"}"#
-                                                .to_string()
-                                                .into(),
-                                        ))
-                                        .await;
-                            }
-                            Err(_) => {
-                                let _ = tx
-                                    .lock()
-                                    .await
-                                    .send(Message::Text(r#"{"ok":false,"action":"runJsGetResult","error":"timeout waiting for result"}"#.to_string().into()))
-                                    .await;
-                            }
-                        }
+            // Without an app handle there is no way to listen for the result
+            let Some(handle) = app else {
+                reply(&mut replies, RemoteResponse::error(action, "app handle unavailable to capture result"));
+                return replies;
+            };
 
-                        // Always cleanup the listener
-                        handle.unlisten(listener_id);
-                    } else {
-                        // No app handle to listen for result; return an error to caller
-                        let _ = tx
-                            .lock()
-                            .await
-                            .send(Message::Text(
-                                r#"{"ok":false,"action":"runJsGetResult","error":"app handle unavailable to capture result"}"#.to_string().into(),
-                            ))
-                            .await;
-                    }
-                } else {
-                    let _ = tx
-                        .lock()
-                        .await
-                        .send(Message::Text(
-                            r#"{"ok":false,"action":"runJsGetResult","error":"script required"}"#.to_string().into(),
-                        ))
-                        .await;
-                }
+            let (result_tx, mut result_rx) = tokio::sync::mpsc::channel::<String>(1);
+            let result_tx_clone = result_tx.clone();
+            let app_for_listen = handle.clone();
+
+            // Register a listener that forwards any payload into our mpsc channel.
+            // The closure is synchronous but we spawn an async task to enqueue into the channel.
+            let listener_id = app_for_listen.listen(&result_event_name, move |evt: Event| {
+                let payload = evt.payload().to_string();
+                let tx = result_tx_clone.clone();
+                tauri::async_runtime::spawn(async move {
+                    let _ = tx.send(payload).await;
+                });
+            });
+
+            // Build a script that reads fallback locations, clears them, and emits the
+            // unique event with the captured payload. This tries to be resilient
+            // across environments and emits a JSON string if the result is an object.
+            let followup = format!(
+                r#"
+                (function() {{
+                    try {{
+                        var result = window.__REMOTE_DEBUG_LAST_RESULT || document.body.dataset.debugResult || localStorage.getItem('__debug_result') || null;
+                        delete window.__REMOTE_DEBUG_LAST_RESULT;
+                        try {{ delete document.body.dataset.debugResult; }} catch(e) {{}}
+                        try {{ localStorage.removeItem('__debug_result'); }} catch(e) {{}}
+                        var emit = window.__TAURI__ && window.__TAURI__.event && window.__TAURI__.event.emit;
+                        try {{
+                            if (emit) {{
+                                // If result looks like a JSON string/object, try to parse it
+                                var payload = result;
+                                try {{ payload = JSON.parse(result); }} catch(e) {{ /* not JSON */ }}
+                                emit("{event}", payload);
+                            }} else {{
+                                // As a fallback, set the global so it can be polled
+                                window.__REMOTE_DEBUG_LAST_RESULT = result;
+                            }}
+                        }} catch(e) {{
+                            if (emit) {{ emit("{event}", {{"__error": String(e)}}); }}
+                        }}
+                    }} catch(e) {{
+                        var emit = window.__TAURI__ && window.__TAURI__.event && window.__TAURI__.event.emit;
+                        if (emit) {{ emit("{event}", {{"__error": String(e)}}); }}
+                    }}
+                }})();
+            "#,
+                event = result_event_name
+            );
+
+            // Run the follow-up eval and propagate eval errors
+            if let Err(e) = eval(&followup) {
+                reply(&mut replies, RemoteResponse::error(action, format!("followup eval failed: {}", e)));
+                // Cleanup the listener
+                handle.unlisten(listener_id);
+                return replies;
             }
-            "inspectWindow" => {
-                let Some(label) = cmd.url.clone() else {
-                    let _ = tx
-                        .lock()
-                        .await
-                        .send(Message::Text(
-                            r#"{"ok":false,"action":"inspectWindow","error":"label required"}"#.to_string().into(),
-                        ))
-                        .await;
-                    continue;
-                };
 
-                if let Some(handle) = &app {
-                    if let Some(win) = handle.get_webview_window(&label) {
-                        let visible = win.is_visible().unwrap_or(false);
-                        let focused = win.is_focused().unwrap_or(false);
-                        let minimized = win.is_minimized().unwrap_or(false);
-                        let _ = tx
-                            .lock()
-                            .await
-                            .send(Message::Text(
-                                json!({
-                                    "ok": true,
-                                    "action": "inspectWindow",
-                                    "label": label,
-                                    "visible": visible,
-                                    "focused": focused,
-                                    "minimized": minimized
-                                })
-                                .to_string()
-                                .into(),
-                            ))
-                            .await;
-                    } else {
-                        let _ = tx
-                            .lock()
-                            .await
-                            .send(Message::Text(
-                                json!({
-                                    "ok": false,
-                                    "action": "inspectWindow",
-                                    "error": format!("window '{label}' not found")
-                                })
-                                .to_string()
-                                .into(),
-                            ))
-                            .await;
-                    }
-                } else {
-                    let _ = tx
-                        .lock()
-                        .await
-                        .send(Message::Text(
-                            r#"{"ok":false,"action":"inspectWindow","error":"app handle unavailable"}"#.to_string().into(),
-                        ))
-                        .await;
+            // Wait a short while for the listener to receive the payload
+            let response = match tokio::time::timeout(Duration::from_millis(2000), result_rx.recv()).await {
+                Ok(Some(payload)) => {
+                    // Try to parse as JSON, fallback to string
+                    let value: Value = serde_json::from_str(&payload).unwrap_or_else(|_| json!(payload));
+                    RemoteResponse::ok(action).with("result", value)
                 }
-            }
-            "startDownloadDirect" => {
-                if let Some(url) = cmd.url {
-                    let path = cmd.path.unwrap_or_default();
-                    let media_idx = cmd.media_idx.unwrap_or(0);
-                    let settings = DownloadSettings::remote_defaults();
-                    match &app {
-                        Some(app_handle) => {
-                            if let Some(win) = app_handle.get_window("main") {
-                                download_media(
-                                    app_handle.clone(),
-                                    win,
-                                    media_idx,
-                                    url.clone(),
-                                    path.clone(),
-                                    None,
-                                    settings,
-                                );
-                                let _ = tx
-                                    .lock()
-                                    .await
-                                    .send(Message::Text(
-                                        r#"{"ok":true,"action":"startDownloadDirect"}"#.to_string().into(),
-                                    ))
-                                    .await;
-                                let _ = tx
-                                    .lock()
-                                    .await
-                                    .send(Message::Text(
-                                        format!(r#"{{"event":"remote-recv","payload":"startDownloadDirect {url}"}}"#)
-                                            .into(),
-                                    ))
-                                    .await;
-                            } else {
-                                let _ = tx
-                                    .lock()
-                                    .await
-                                    .send(Message::Text(
-                                        r#"{"ok":false,"action":"startDownloadDirect","error":"main window missing"}"#
-                                            .to_string()
-                                            .into(),
-                                    ))
-                                    .await;
-                            }
-                        }
-                        None => {
-                            let _ = tx
-                                .lock()
-                                .await
-                                .send(Message::Text(
-                                    r#"{"ok":false,"action":"startDownloadDirect","error":"app handle unavailable"}"#
-                                        .to_string()
-                                        .into(),
-                                ))
-                                .await;
-                        }
+                Ok(None) => RemoteResponse::error(action, "no result received"),
+                Err(_) => RemoteResponse::error(action, "timeout waiting for result"),
+            };
+            reply(&mut replies, response);
+
+            // Always cleanup the listener
+            handle.unlisten(listener_id);
+        }
+        "inspectWindow" => {
+            let Some(label) = cmd.url else {
+                reply(&mut replies, RemoteResponse::error(action, "label required"));
+                return replies;
+            };
+
+            let response = match app {
+                Some(handle) => match handle.get_webview_window(&label) {
+                    Some(win) => RemoteResponse::ok(action)
+                        .with("label", &label)
+                        .with("visible", win.is_visible().unwrap_or(false))
+                        .with("focused", win.is_focused().unwrap_or(false))
+                        .with("minimized", win.is_minimized().unwrap_or(false)),
+                    None => RemoteResponse::error(action, format!("window '{label}' not found")),
+                },
+                None => RemoteResponse::error(action, "app handle unavailable"),
+            };
+            reply(&mut replies, response);
+        }
+        "startDownloadDirect" => {
+            let Some(url) = cmd.url else {
+                reply(&mut replies, RemoteResponse::error(action, "url required"));
+                return replies;
+            };
+
+            // Fall back to the persisted default when the harness gives no path
+            let path = cmd
+                .path
+                .filter(|p| !p.is_empty())
+                .or_else(|| app.as_ref().and_then(default_output_location))
+                .unwrap_or_default();
+            let media_idx = cmd.media_idx.unwrap_or(0);
+            let settings = DownloadSettings::remote_defaults();
+            match app {
+                Some(app_handle) => {
+                    if let Some(win) = app_handle.get_window("main") {
+                        download_media(
+                            app_handle.clone(),
+                            win,
+                            media_idx,
+                            url.clone(),
+                            path,
+                            None,
+                            settings,
+                            None,
+                            None,
+                            None,
+                        );
+                        reply(&mut replies, RemoteResponse::ok(action));
+                        reply(&mut replies, RemoteNotice::received(format!("startDownloadDirect {url}")));
+                    } else {
+                        reply(&mut replies, RemoteResponse::error(action, "main window missing"));
                     }
-                } else {
-                    let _ = tx
-                        .lock()
-                        .await
-                        .send(Message::Text(
-                            r#"{"ok":false,"action":"startDownloadDirect","error":"url required"}"#.to_string().into(),
-                        ))
-                        .await;
                 }
-            }
-            _ => {
-                let _ = tx
-                    .lock()
-                    .await
-                    .send(Message::Text(r#"{"ok":false,"error":"unknown action"}"#.to_string().into()))
-                    .await;
+                None => reply(&mut replies, RemoteResponse::error(action, "app handle unavailable")),
             }
         }
+        _ => reply(&mut replies, RemoteResponse::error(action, "unknown action")),
     }
+
+    replies
 }
 
 /// Start a websocket server on the given address with a provided emitter (used by app and tests).
@@ -631,7 +557,7 @@ pub fn start_remote_control_on(
     tauri::async_runtime::spawn(async move {
         let tx_broadcast = REMOTE_BROADCAST
             .get_or_init(|| {
-                let (tx, _rx) = broadcast::channel(128);
+                let (tx, _rx) = broadcast::channel(REMOTE_BROADCAST_CAPACITY);
                 tx
             })
             .clone();
@@ -680,8 +606,9 @@ pub fn start_remote_control_on(
 
                 let (tx, rx) = ws_stream.split();
                 let tx = Arc::new(Mutex::new(tx));
+                let conn_id = Uuid::new_v4().to_string();
                 // Send a deterministic handshake so harnesses can verify the backend.
-                let hello = Message::Text(build_remote_hello().into());
+                let hello = Message::Text(build_remote_hello(&conn_id).into());
                 {
                     let mut guard = tx.lock().await;
                     if let Err(e) = guard.send(hello.clone()).await {
@@ -691,19 +618,22 @@ pub fn start_remote_control_on(
                 }
 
                 // Fan out broadcast channel messages to this websocket connection.
+                // A client that falls behind skips the missed messages (counted) and keeps going.
                 let tx_for_broadcast = tx.clone();
                 let mut rx_broadcast = tx_broadcast.subscribe();
+                let broadcast_conn_id = conn_id.clone();
                 tauri::async_runtime::spawn(async move {
-                    while let Ok(msg) = rx_broadcast.recv().await {
+                    while let Some(msg) = recv_broadcast(&mut rx_broadcast, &broadcast_conn_id).await {
                         let mut guard = tx_for_broadcast.lock().await;
                         if let Err(e) = guard.send(Message::Text(msg.clone().into())).await {
                             eprintln!("[remote] failed to forward broadcast: {e}");
                             break;
                         }
                     }
+                    with_broadcast_metrics(|metrics| metrics.dropped_by_connection.remove(&broadcast_conn_id));
                 });
 
-                handle_socket(rx, tx, emitter, eval, app_for_conn.clone()).await;
+                handle_socket(rx, tx, emitter, eval, app_for_conn.clone(), conn_id).await;
             });
         }
     })
@@ -748,18 +678,19 @@ mod tests {
 
     #[test]
     fn test_build_remote_hello_structure() {
-        let hello = build_remote_hello();
+        let hello = build_remote_hello("test-conn");
         let parsed: serde_json::Value = serde_json::from_str(&hello).unwrap();
 
         assert_eq!(parsed["event"], "remote-hello");
         assert!(parsed["payload"]["pid"].is_number());
         assert!(parsed["payload"]["ts"].is_number());
         assert!(parsed["payload"]["debugFallback"].is_boolean());
+        assert_eq!(parsed["payload"]["connId"], "test-conn");
     }
 
     #[test]
     fn test_build_remote_hello_includes_process_id() {
-        let hello = build_remote_hello();
+        let hello = build_remote_hello("test-conn");
         let parsed: serde_json::Value = serde_json::from_str(&hello).unwrap();
 
         let pid = parsed["payload"]["pid"].as_u64().unwrap();
@@ -768,7 +699,7 @@ mod tests {
 
     #[test]
     fn test_build_remote_hello_includes_timestamp() {
-        let hello = build_remote_hello();
+        let hello = build_remote_hello("test-conn");
         let parsed: serde_json::Value = serde_json::from_str(&hello).unwrap();
 
         let ts = parsed["payload"]["ts"].as_u64().unwrap();
@@ -777,7 +708,7 @@ mod tests {
 
     #[test]
     fn test_build_remote_hello_debug_flag() {
-        let hello = build_remote_hello();
+        let hello = build_remote_hello("test-conn");
         let parsed: serde_json::Value = serde_json::from_str(&hello).unwrap();
 
         let debug_flag = parsed["payload"]["debugFallback"].as_bool().unwrap();
@@ -786,7 +717,7 @@ mod tests {
 
     #[test]
     fn test_build_remote_hello_environment_vars() {
-        let hello = build_remote_hello();
+        let hello = build_remote_hello("test-conn");
         let parsed: serde_json::Value = serde_json::from_str(&hello).unwrap();
 
         let env_flag = parsed["payload"]["enableRemoteHarnessEnv"].as_str();
@@ -796,6 +727,131 @@ mod tests {
         assert!(tauri_env.is_some() || tauri_env.unwrap_or_default().is_empty());
     }
 
+    /// Run a command without an app handle, recording emitted events and evaluated scripts
+    async fn run_command(json: &str, eval_result: Result<(), String>) -> (Replies, Vec<(String, Value)>) {
+        run_command_as("test-conn", json, eval_result).await
+    }
+
+    async fn run_command_as(
+        conn_id: &str,
+        json: &str,
+        eval_result: Result<(), String>,
+    ) -> (Replies, Vec<(String, Value)>) {
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let events_for_emitter = events.clone();
+        let emitter: RemoteEmitter = Arc::new(move |event: &str, payload: Value| {
+            events_for_emitter.lock().unwrap().push((event.to_string(), payload));
+        });
+        let eval: RemoteEval = Arc::new(move |_script: &str| eval_result.clone());
+
+        let cmd: RemoteCommand = serde_json::from_str(json).unwrap();
+        let replies = handle_command(cmd, &emitter, &eval, &None, conn_id).await;
+        let events = events.lock().unwrap().clone();
+        (replies, events)
+    }
+
+    #[tokio::test]
+    async fn test_handle_command_acknowledged_actions() {
+        let cases = [
+            (r#"{"action":"addUrl","url":"https://a.test/v"}"#, EVT_REMOTE_ADD_URL, "addUrl https://a.test/v"),
+            (r#"{"action":"startDownloads"}"#, EVT_REMOTE_START, "startDownloads"),
+            (r#"{"action":"cancelAll"}"#, EVT_REMOTE_CANCEL, "cancelAll"),
+            (r#"{"action":"clearList"}"#, EVT_REMOTE_CLEAR_LIST, "clearList"),
+            (r#"{"action":"setDownloadDir","path":"/tmp/dl"}"#, EVT_REMOTE_SET_DOWNLOAD_DIR, "setDownloadDir /tmp/dl"),
+        ];
+
+        for (command, event, received) in cases {
+            let action = serde_json::from_str::<Value>(command).unwrap()["action"].clone();
+            let (replies, events) = run_command(command, Ok(())).await;
+            let ack = json!({"ok": true, "action": action});
+            let notice = json!({"event": "remote-recv", "payload": received});
+            assert_eq!(replies, vec![ack, notice]);
+            assert_eq!(events.len(), 1, "{command}");
+            assert_eq!(events[0].0, event);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_command_missing_arguments() {
+        let cases = [
+            (r#"{"action":"addUrl"}"#, "addUrl", "url required"),
+            (r#"{"action":"setDownloadDir"}"#, "setDownloadDir", "path required"),
+            (r#"{"action":"runJs"}"#, "runJs", "script required"),
+            (r#"{"action":"runJsCapture"}"#, "runJsCapture", "script required"),
+            (r#"{"action":"runJsGetResult"}"#, "runJsGetResult", "script required"),
+            (r#"{"action":"inspectWindow"}"#, "inspectWindow", "label required"),
+            (r#"{"action":"startDownloadDirect"}"#, "startDownloadDirect", "url required"),
+            (r#"{"action":"setMaxConcurrent"}"#, "setMaxConcurrent", "maxConcurrent required"),
+            (r#"{"action":"bogus"}"#, "bogus", "unknown action"),
+        ];
+
+        for (command, action, error) in cases {
+            let (replies, events) = run_command(command, Ok(())).await;
+            assert_eq!(replies, vec![json!({"ok": false, "action": action, "error": error})], "{command}");
+            assert!(events.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_command_status_debug_echo_and_run_js() {
+        let (replies, _) = run_command(r#"{"action":"status"}"#, Ok(())).await;
+        assert_eq!(replies.len(), 2);
+        assert_eq!(replies[0]["ok"], true);
+        assert_eq!(replies[0]["action"], "status");
+        for key in ["queued", "active", "max"] {
+            assert!(replies[0][key].is_u64(), "{key}");
+        }
+        assert_eq!(replies[1], json!({"event": "remote-recv", "payload": "status"}));
+
+        let (replies, _) = run_command(r#"{"action":"debugEcho","data":{"a":1}}"#, Ok(())).await;
+        assert_eq!(replies, vec![json!({"event": "debug-echo", "payload": {"a": 1}})]);
+
+        let (replies, _) = run_command(r#"{"action":"runJs","url":"1+1"}"#, Ok(())).await;
+        let ack = json!({"ok": true, "action": "runJs"});
+        let notice = json!({"event": "remote-recv", "payload": "runJs 1+1"});
+        assert_eq!(replies, vec![ack, notice]);
+
+        // Error text is escaped properly, even with quotes in it
+        let (replies, _) = run_command(r#"{"action":"runJs","url":"x"}"#, Err("bad \"quote\"".into())).await;
+        assert_eq!(replies, vec![json!({"ok": false, "action": "runJs", "error": "bad \"quote\""})]);
+
+        let (replies, _) = run_command(r#"{"action":"runJsGetResult","url":"x"}"#, Ok(())).await;
+        assert_eq!(replies[0]["error"], "app handle unavailable to capture result");
+        let (replies, _) = run_command(r#"{"action":"inspectWindow","url":"main"}"#, Ok(())).await;
+        assert_eq!(replies, vec![json!({"ok": false, "action": "inspectWindow", "error": "app handle unavailable"})]);
+    }
+
+    #[test]
+    fn test_set_max_concurrent_response() {
+        let status = |max| QueueStatus { queued: 2, active: 1, max_concurrent: max };
+
+        let ok = json!(set_max_concurrent_response(Some(4), |max| Ok(status(max))));
+        assert_eq!(
+            ok,
+            json!({"ok": true, "action": "setMaxConcurrent", "status": {"queued": 2, "active": 1, "max_concurrent": 4}})
+        );
+
+        for requested in [None, Some(0), Some(-3)] {
+            let rejected =
+                json!(set_max_concurrent_response(requested, |_| panic!("invalid values must not reach the queue")));
+            assert_eq!(rejected["ok"], false, "{requested:?}");
+            assert!(rejected["error"].as_str().unwrap().contains("maxConcurrent"));
+        }
+
+        let failed = json!(set_max_concurrent_response(Some(2), |_| Err("app handle unavailable".into())));
+        assert_eq!(failed, json!({"ok": false, "action": "setMaxConcurrent", "error": "app handle unavailable"}));
+    }
+
+    #[test]
+    fn test_safe_mode_rejects_script_actions() {
+        for action in ["runJs", "runJsCapture", "runJsGetResult"] {
+            let rejection = json!(safe_mode_rejection(action, true).expect(action));
+            assert_eq!(rejection, json!({"ok": false, "action": action, "error": "disabled in safe mode"}));
+            assert!(safe_mode_rejection(action, false).is_none());
+        }
+        assert!(safe_mode_rejection("addUrl", true).is_none());
+    }
+
     #[test]
     fn test_remote_command_deserialization_valid() {
         let json = r#"{"action":"addUrl","url":"https://example.com"}"#;
@@ -874,6 +930,37 @@ mod tests {
         assert_eq!(parsed["payload"], json!("test-payload"));
     }
 
+    #[tokio::test]
+    async fn test_recv_broadcast_counts_lagged_messages() {
+        let (tx, mut rx) = broadcast::channel(2);
+        let conn_id = format!("test-{}", Uuid::new_v4());
+        let before = with_broadcast_metrics(|metrics| metrics.total_dropped);
+
+        // Five sends into a two-slot channel: the receiver lags by three
+        for i in 0..5 {
+            tx.send(format!("msg-{i}")).unwrap();
+        }
+
+        assert_eq!(recv_broadcast(&mut rx, &conn_id).await.as_deref(), Some("msg-3"));
+        assert_eq!(recv_broadcast(&mut rx, &conn_id).await.as_deref(), Some("msg-4"));
+        with_broadcast_metrics(|metrics| {
+            assert_eq!(metrics.dropped_by_connection.get(&conn_id), Some(&3));
+            assert!(metrics.total_dropped >= before + 3);
+        });
+
+        drop(tx);
+        assert_eq!(recv_broadcast(&mut rx, &conn_id).await, None);
+
+        let (replies, _) = run_command_as(&conn_id, r#"{"action":"metrics"}"#, Ok(())).await;
+        assert_eq!(replies[0]["ok"], true);
+        assert_eq!(replies[0]["broadcastCapacity"], REMOTE_BROADCAST_CAPACITY);
+        assert_eq!(replies[0]["dropped"], 3, "the calling connection's own count");
+        assert_eq!(replies[0]["droppedByConnection"][&conn_id], 3);
+
+        let (replies, _) = run_command(r#"{"action":"metrics"}"#, Ok(())).await;
+        assert_eq!(replies[0]["dropped"], 0);
+    }
+
     #[test]
     fn test_message_text_construction_with_string() {
         // This test ensures that tokio-tungstenite's Message::Text API still accepts
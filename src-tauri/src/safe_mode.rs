@@ -0,0 +1,34 @@
+//! Safe mode for untrusted environments.
+//!
+//! With `REMEDIA_SAFE_MODE=1`, features that pass caller-supplied input through to
+//! yt-dlp flags (`extra_args`) or evaluate scripts in the webview (remote `runJs*`)
+//! are refused, regardless of any other setting.
+
+use crate::env_flag::env_flag_enabled;
+
+/// Environment variable enabling safe mode ("1" or "true")
+pub const SAFE_MODE_ENV_VAR: &str = "REMEDIA_SAFE_MODE";
+
+/// Whether safe mode is enabled (read once per process)
+pub fn is_safe_mode() -> bool {
+    env_flag_enabled(SAFE_MODE_ENV_VAR)
+}
+
+/// Remote-control actions that run scripts in the webview
+pub fn is_script_action(action: &str) -> bool {
+    action.starts_with("runJs")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_script_action() {
+        for action in ["runJs", "runJsCapture", "runJsGetResult"] {
+            assert!(is_script_action(action), "{action}");
+        }
+        assert!(!is_script_action("addUrl"));
+        assert!(!is_script_action("startDownloadDirect"));
+    }
+}
@@ -0,0 +1,50 @@
+//! Scratch directories for tests, removed again when the guard drops.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A unique directory under the system temp dir, deleted with its contents on drop
+pub(crate) struct TestDir(PathBuf);
+
+impl TestDir {
+    /// Create `remedia-<label>-<uuid>` in the system temp dir
+    pub(crate) fn new(label: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("remedia-{}-{}", label, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub(crate) fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_test_dir_is_unique_and_removed_on_drop() {
+        let dir = TestDir::new("test-dir");
+        let other = TestDir::new("test-dir");
+        assert_ne!(dir.path(), other.path());
+        assert!(dir.path().is_dir());
+
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("nested").join("file.txt"), b"x").unwrap();
+
+        let path = dir.path().to_path_buf();
+        drop(dir);
+        assert!(!path.exists());
+    }
+}
@@ -1,7 +1,123 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+
+use serde::Serialize;
 use serde_json::Value;
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+
+/// Thumbnail cache directory, relative to the app cache directory
+pub const THUMB_CACHE_DIR: &str = "thumb-cache";
+
+/// Channel artwork URLs from a yt-dlp channel JSON `thumbnails` list
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelArt {
+    pub avatar: Option<String>,
+    pub banner: Option<String>,
+}
+
+/// Channel art by channel URL; artwork rarely changes within a session
+static CHANNEL_ART_CACHE: LazyLock<Mutex<HashMap<String, ChannelArt>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Look up cached channel art for a URL
+pub fn cached_channel_art(url: &str) -> Option<ChannelArt> {
+    CHANNEL_ART_CACHE.lock().ok()?.get(url).cloned()
+}
+
+/// Remember channel art for a URL
+pub fn cache_channel_art(url: &str, art: &ChannelArt) {
+    if let Ok(mut cache) = CHANNEL_ART_CACHE.lock() {
+        cache.insert(url.to_string(), art.clone());
+    }
+}
+
+/// Pick the avatar and banner from channel JSON `thumbnails`, matched by `id`
+/// ("avatar_uncropped", "banner_uncropped").
+pub fn parse_channel_art(v: &Value) -> ChannelArt {
+    let thumbnail_url = |id: &str| {
+        v.get("thumbnails")?
+            .as_array()?
+            .iter()
+            .find(|t| t.get("id").and_then(Value::as_str) == Some(id))
+            .and_then(|t| t.get("url"))
+            .and_then(Value::as_str)
+            .filter(|u| u.starts_with("http"))
+            .map(str::to_string)
+    };
+
+    ChannelArt { avatar: thumbnail_url("avatar_uncropped"), banner: thumbnail_url("banner_uncropped") }
+}
+
+/// Thumbnail size bucket to pick from a yt-dlp `thumbnails` array
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ThumbnailQuality {
+    #[default]
+    Best,
+    Medium,
+    Low,
+}
+
+impl ThumbnailQuality {
+    /// Parse a frontend value: "best", "medium" or "low"
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "best" => Some(Self::Best),
+            "medium" => Some(Self::Medium),
+            "low" => Some(Self::Low),
+            _ => None,
+        }
+    }
+
+    /// Parse an optional command argument; missing means `Best`
+    pub fn from_arg(value: Option<&str>) -> Result<Self, String> {
+        value.map_or(Ok(Self::Best), |v| Self::parse(v).ok_or_else(|| format!("Invalid thumbnail quality: {}", v)))
+    }
+
+    /// Widest thumbnail (in pixels) this bucket accepts; `None` for no limit
+    fn max_width(self) -> Option<u64> {
+        match self {
+            Self::Best => None,
+            Self::Medium => Some(720),
+            Self::Low => Some(320),
+        }
+    }
+}
+
+/// Pick the widest thumbnail no wider than `max_width`, or the narrowest one when all are wider.
+/// Entries without a `width` are ignored.
+fn pick_sized_thumbnail(thumbs: &[Value], max_width: u64) -> Option<String> {
+    let sized: Vec<(u64, &str)> = thumbs
+        .iter()
+        .filter_map(|t| {
+            let width = t.get("width")?.as_u64()?;
+            let url = t.get("url")?.as_str().filter(|s| !s.is_empty())?;
+            Some((width, url))
+        })
+        .collect();
+
+    sized
+        .iter()
+        .filter(|(width, _)| *width <= max_width)
+        .max_by_key(|(width, _)| *width)
+        .or_else(|| sized.iter().min_by_key(|(width, _)| *width))
+        .map(|(_, url)| url.to_string())
+}
 
 /// Resolve a thumbnail URL from yt-dlp JSON output, including extractor-specific fallbacks.
-pub fn resolve_thumbnail(v: &Value) -> Option<String> {
+/// Below `Best`, a sized entry from `thumbnails` wins over the (full-size) `thumbnail` field.
+pub fn resolve_thumbnail(v: &Value, quality: ThumbnailQuality) -> Option<String> {
+    if let Some(max_width) = quality.max_width()
+        && let Some(thumbs) = v.get("thumbnails").and_then(Value::as_array)
+        && let Some(url) = pick_sized_thumbnail(thumbs, max_width)
+        && url.starts_with("http")
+    {
+        return Some(url);
+    }
+
     // First, honor direct fields
     let mut thumbnail = v.get("thumbnail").and_then(|t| t.as_str()).filter(|s| !s.is_empty()).map(|s| s.to_string());
 
@@ -63,17 +179,121 @@ pub fn resolve_thumbnail(v: &Value) -> Option<String> {
     thumbnail.filter(|s| s.starts_with("http"))
 }
 
+/// What clearing the thumbnail cache removed
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClearResult {
+    pub removed_files: usize,
+    pub freed_bytes: u64,
+}
+
+/// Remove every file under `dir` (recursively), keeping the directories themselves.
+/// A missing directory counts as already empty.
+pub fn clear_cache_dir(dir: &Path) -> io::Result<ClearResult> {
+    let mut result = ClearResult::default();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(result),
+        Err(e) => return Err(e),
+    };
+
+    for entry in entries {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+        if meta.is_dir() {
+            let nested = clear_cache_dir(&entry.path())?;
+            result.removed_files += nested.removed_files;
+            result.freed_bytes += nested.freed_bytes;
+        } else {
+            fs::remove_file(entry.path())?;
+            result.removed_files += 1;
+            result.freed_bytes += meta.len();
+        }
+    }
+
+    Ok(result)
+}
+
+/// Delete cached thumbnails and report how many files and bytes were freed
+#[tauri::command]
+pub fn clear_thumbnail_cache(app: AppHandle) -> Result<ClearResult, String> {
+    let dir = app
+        .path()
+        .resolve(THUMB_CACHE_DIR, BaseDirectory::AppCache)
+        .map_err(|e| format!("Failed to resolve thumbnail cache directory: {}", e))?;
+    clear_cache_dir(&dir).map_err(|e| format!("Failed to clear {}: {}", dir.display(), e))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
+    use crate::test_dir::TestDir;
+
+    fn resolve_best(v: &Value) -> Option<String> {
+        resolve_thumbnail(v, ThumbnailQuality::Best)
+    }
+
+    #[test]
+    fn test_clear_cache_dir_reports_removed_files_and_bytes() {
+        let scratch = TestDir::new("thumbs");
+        let dir = scratch.path();
+        fs::create_dir_all(dir.join("redgifs")).unwrap();
+        fs::write(dir.join("a.jpg"), [0u8; 100]).unwrap();
+        fs::write(dir.join("b.webp"), [0u8; 50]).unwrap();
+        fs::write(dir.join("redgifs").join("c.jpg"), [0u8; 25]).unwrap();
+
+        let result = clear_cache_dir(dir).unwrap();
+        assert_eq!(result, ClearResult { removed_files: 3, freed_bytes: 175 });
+        assert!(dir.is_dir(), "cache directory itself is kept");
+        assert_eq!(clear_cache_dir(dir).unwrap(), ClearResult::default());
+
+        fs::remove_dir_all(dir).unwrap();
+        assert_eq!(clear_cache_dir(dir).unwrap(), ClearResult::default());
+    }
+
+    #[test]
+    fn test_parse_channel_art_from_channel_json() {
+        let v = json!({
+            "_type": "playlist",
+            "id": "UC123",
+            "title": "Some Channel - Videos",
+            "thumbnails": [
+                {"url": "https://yt3.example.com/banner=w1060", "height": 175, "width": 1060, "id": "0"},
+                {"url": "https://yt3.example.com/banner=s0", "id": "banner_uncropped", "preference": -5},
+                {"url": "https://yt3.example.com/avatar=s900", "height": 900, "width": 900, "id": "7"},
+                {"url": "https://yt3.example.com/avatar=s0", "id": "avatar_uncropped", "preference": 1}
+            ]
+        });
+
+        let art = parse_channel_art(&v);
+        assert_eq!(art.avatar.as_deref(), Some("https://yt3.example.com/avatar=s0"));
+        assert_eq!(art.banner.as_deref(), Some("https://yt3.example.com/banner=s0"));
+    }
+
+    #[test]
+    fn test_parse_channel_art_missing_entries() {
+        let v = json!({"thumbnails": [{"url": "https://example.com/x.jpg", "id": "0"}]});
+        assert_eq!(parse_channel_art(&v), ChannelArt::default());
+        assert_eq!(parse_channel_art(&json!({"title": "no thumbnails"})), ChannelArt::default());
+    }
+
+    #[test]
+    fn test_channel_art_cache_roundtrip() {
+        let url = format!("https://www.youtube.com/@cache-test-{}", uuid::Uuid::new_v4());
+        assert_eq!(cached_channel_art(&url), None);
+
+        let art = ChannelArt { avatar: Some("https://example.com/a.jpg".to_string()), banner: None };
+        cache_channel_art(&url, &art);
+        assert_eq!(cached_channel_art(&url), Some(art));
+    }
 
     #[test]
     fn test_resolve_thumbnail_direct_field() {
         let v = json!({
             "thumbnail": "https://example.com/thumb.jpg"
         });
-        assert_eq!(resolve_thumbnail(&v), Some("https://example.com/thumb.jpg".to_string()));
+        assert_eq!(resolve_best(&v), Some("https://example.com/thumb.jpg".to_string()));
     }
 
     #[test]
@@ -81,7 +301,7 @@ mod tests {
         let v = json!({
             "thumbnail": ""
         });
-        assert_eq!(resolve_thumbnail(&v), None);
+        assert_eq!(resolve_best(&v), None);
     }
 
     #[test]
@@ -93,7 +313,50 @@ mod tests {
             ]
         });
         // Should pick last (highest resolution)
-        assert_eq!(resolve_thumbnail(&v), Some("https://example.com/large.jpg".to_string()));
+        assert_eq!(resolve_best(&v), Some("https://example.com/large.jpg".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_thumbnail_quality_buckets() {
+        let v = json!({
+            "thumbnail": "https://example.com/maxres.jpg",
+            "thumbnails": [
+                {"url": "https://example.com/default.jpg", "width": 120, "height": 90},
+                {"url": "https://example.com/mq.jpg", "width": 320, "height": 180},
+                {"url": "https://example.com/hq.jpg", "width": 480, "height": 360},
+                {"url": "https://example.com/unsized.webp"},
+                {"url": "https://example.com/sd.jpg", "width": 640, "height": 480},
+                {"url": "https://example.com/maxres.jpg", "width": 1280, "height": 720}
+            ]
+        });
+        let pick = |quality| resolve_thumbnail(&v, quality).unwrap();
+        assert_eq!(pick(ThumbnailQuality::Best), "https://example.com/maxres.jpg");
+        assert_eq!(pick(ThumbnailQuality::Medium), "https://example.com/sd.jpg");
+        assert_eq!(pick(ThumbnailQuality::Low), "https://example.com/mq.jpg");
+    }
+
+    #[test]
+    fn test_resolve_thumbnail_quality_falls_back() {
+        // Every sized entry is too wide: take the narrowest
+        let v = json!({"thumbnails": [
+            {"url": "https://example.com/big.jpg", "width": 1920},
+            {"url": "https://example.com/smaller.jpg", "width": 1280}
+        ]});
+        assert_eq!(resolve_thumbnail(&v, ThumbnailQuality::Low), Some("https://example.com/smaller.jpg".to_string()));
+
+        // No widths known: behave like Best
+        let v = json!({
+            "thumbnail": "https://example.com/thumb.jpg",
+            "thumbnails": [{"url": "https://example.com/a.jpg"}]
+        });
+        assert_eq!(resolve_thumbnail(&v, ThumbnailQuality::Medium), Some("https://example.com/thumb.jpg".to_string()));
+    }
+
+    #[test]
+    fn test_thumbnail_quality_from_arg() {
+        assert_eq!(ThumbnailQuality::from_arg(None), Ok(ThumbnailQuality::Best));
+        assert_eq!(ThumbnailQuality::from_arg(Some("low")), Ok(ThumbnailQuality::Low));
+        assert!(ThumbnailQuality::from_arg(Some("huge")).is_err());
     }
 
     #[test]
@@ -101,7 +364,7 @@ mod tests {
         let v = json!({
             "thumbnail_url": "https://example.com/alt.jpg"
         });
-        assert_eq!(resolve_thumbnail(&v), Some("https://example.com/alt.jpg".to_string()));
+        assert_eq!(resolve_best(&v), Some("https://example.com/alt.jpg".to_string()));
     }
 
     #[test]
@@ -112,7 +375,7 @@ mod tests {
             "thumbnails": [{"url": "https://example.com/array.jpg"}],
             "thumbnail_url": "https://example.com/alt.jpg"
         });
-        assert_eq!(resolve_thumbnail(&v), Some("https://example.com/primary.jpg".to_string()));
+        assert_eq!(resolve_best(&v), Some("https://example.com/primary.jpg".to_string()));
     }
 
     #[test]
@@ -122,7 +385,7 @@ mod tests {
             "thumbnail": "",
             "thumbnails": [{"url": "https://example.com/array.jpg"}]
         });
-        assert_eq!(resolve_thumbnail(&v), Some("https://example.com/array.jpg".to_string()));
+        assert_eq!(resolve_best(&v), Some("https://example.com/array.jpg".to_string()));
     }
 
     #[test]
@@ -130,7 +393,7 @@ mod tests {
         let v = json!({
             "title": "Some Video"
         });
-        assert_eq!(resolve_thumbnail(&v), None);
+        assert_eq!(resolve_best(&v), None);
     }
 
     #[test]
@@ -138,7 +401,7 @@ mod tests {
         let v = json!({
             "thumbnail": "file:///local/path.jpg"
         });
-        assert_eq!(resolve_thumbnail(&v), None);
+        assert_eq!(resolve_best(&v), None);
     }
 
     #[test]
@@ -146,7 +409,7 @@ mod tests {
         let v = json!({
             "thumbnail": "https://secure.example.com/thumb.jpg"
         });
-        assert_eq!(resolve_thumbnail(&v), Some("https://secure.example.com/thumb.jpg".to_string()));
+        assert_eq!(resolve_best(&v), Some("https://secure.example.com/thumb.jpg".to_string()));
     }
 
     #[test]
@@ -154,7 +417,7 @@ mod tests {
         let v = json!({
             "thumbnails": []
         });
-        assert_eq!(resolve_thumbnail(&v), None);
+        assert_eq!(resolve_best(&v), None);
     }
 
     #[test]
@@ -162,7 +425,7 @@ mod tests {
         let v = json!({
             "thumbnails": [{"width": 100, "height": 100}]
         });
-        assert_eq!(resolve_thumbnail(&v), None);
+        assert_eq!(resolve_best(&v), None);
     }
 
     #[test]
@@ -173,7 +436,7 @@ mod tests {
                 {"url": "https://files.redgifs.com/SomeGifId.mp4"}
             ]
         });
-        assert_eq!(resolve_thumbnail(&v), Some("https://thumbs2.redgifs.com/SomeGifId-mobile.jpg".to_string()));
+        assert_eq!(resolve_best(&v), Some("https://thumbs2.redgifs.com/SomeGifId-mobile.jpg".to_string()));
     }
 
     #[test]
@@ -184,7 +447,7 @@ mod tests {
                 {"url": "https://files.redgifs.com/TestId-mobile.mp4"}
             ]
         });
-        assert_eq!(resolve_thumbnail(&v), Some("https://thumbs2.redgifs.com/TestId-mobile.jpg".to_string()));
+        assert_eq!(resolve_best(&v), Some("https://thumbs2.redgifs.com/TestId-mobile.jpg".to_string()));
     }
 
     #[test]
@@ -193,7 +456,7 @@ mod tests {
             "extractor": "RedGifs",
             "id": "FallbackId"
         });
-        assert_eq!(resolve_thumbnail(&v), Some("https://thumbs2.redgifs.com/FallbackId-mobile.jpg".to_string()));
+        assert_eq!(resolve_best(&v), Some("https://thumbs2.redgifs.com/FallbackId-mobile.jpg".to_string()));
     }
 
     #[test]
@@ -202,7 +465,7 @@ mod tests {
             "extractor": "RedGifs",
             "display_id": "DisplayFallback"
         });
-        assert_eq!(resolve_thumbnail(&v), Some("https://thumbs2.redgifs.com/DisplayFallback-mobile.jpg".to_string()));
+        assert_eq!(resolve_best(&v), Some("https://thumbs2.redgifs.com/DisplayFallback-mobile.jpg".to_string()));
     }
 
     #[test]
@@ -214,7 +477,7 @@ mod tests {
                 {"url": "https://files.redgifs.com/PreferredId.mp4"}
             ]
         });
-        assert_eq!(resolve_thumbnail(&v), Some("https://thumbs2.redgifs.com/PreferredId-mobile.jpg".to_string()));
+        assert_eq!(resolve_best(&v), Some("https://thumbs2.redgifs.com/PreferredId-mobile.jpg".to_string()));
     }
 
     #[test]
@@ -224,7 +487,7 @@ mod tests {
             "id": "dQw4w9WgXcQ"
         });
         // Should not generate RedGifs thumbnail URL
-        assert_eq!(resolve_thumbnail(&v), None);
+        assert_eq!(resolve_best(&v), None);
     }
 
     #[test]
@@ -233,7 +496,7 @@ mod tests {
             "extractor": "RedGifs",
             "id": "   "
         });
-        assert_eq!(resolve_thumbnail(&v), None);
+        assert_eq!(resolve_best(&v), None);
     }
 
     #[test]
@@ -244,6 +507,6 @@ mod tests {
             "thumbnail": "https://thumbs.redgifs.com/direct.jpg",
             "id": "SomeId"
         });
-        assert_eq!(resolve_thumbnail(&v), Some("https://thumbs.redgifs.com/direct.jpg".to_string()));
+        assert_eq!(resolve_best(&v), Some("https://thumbs.redgifs.com/direct.jpg".to_string()));
     }
 }
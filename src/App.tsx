@@ -44,7 +44,12 @@ import { DEBUG_CONSOLE_WIDTH, DEBUG_CONSOLE_HEIGHT } from "@/utils/constants";
 import { isTauriRuntime } from "@/utils/env";
 
 // Types
-import type { MediaInfoEvent, MediaProgressEvent } from "@/types";
+import type {
+  DownloadCancelledEvent,
+  DownloadStartedEvent,
+  MediaInfoEvent,
+  MediaProgressEvent,
+} from "@/types";
 import { TAURI_EVENT } from "@/types";
 
 // Tauri API
@@ -459,7 +464,7 @@ function App(): JSX.Element {
 
       // Mirror media-info updates into the debug console so thumbnail decisions are visible.
       try {
-        const [, mediaSourceUrl, title, thumbnail] = payload;
+        const { mediaSourceUrl, title, thumbnail } = payload;
         addLogEntry({
           timestamp: Date.now(),
           source: "app",
@@ -517,8 +522,8 @@ function App(): JSX.Element {
   );
 
   const handleCancelled = useCallback(
-    (event: Event<number>): void => {
-      const mediaIdx = event.payload;
+    (event: Event<DownloadCancelledEvent>): void => {
+      const [mediaIdx] = event.payload;
       updateMediaItemByIndex(mediaIdx, { status: "Cancelled" });
     },
     [updateMediaItemByIndex],
@@ -533,8 +538,8 @@ function App(): JSX.Element {
   );
 
   const handleStarted = useCallback(
-    (event: Event<number>): void => {
-      const mediaIdx = event.payload;
+    (event: Event<DownloadStartedEvent>): void => {
+      const [mediaIdx] = event.payload;
       updateMediaItemByIndex(mediaIdx, { status: "Downloading" });
     },
     [updateMediaItemByIndex],
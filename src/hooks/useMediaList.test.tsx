@@ -306,6 +306,19 @@ describe("useMediaList", () => {
       expect(result.current.mediaList[0]?.url).toBe("https://example.com/video2");
     });
 
+    it("evicts the removed rows from the backend media info cache", () => {
+      const { result } = renderHook(() => useMediaList(), { wrapper });
+
+      act(() => {
+        result.current.addMediaUrl("https://example.com/video1");
+        result.current.addMediaUrl("https://example.com/video2");
+        result.current.removeItemsAtIndices(new Set([1]));
+      });
+
+      const evictions = mockState.commandCalls.filter((call) => call.command === "clear_media_info_cache");
+      expect(evictions).toEqual([{ command: "clear_media_info_cache", args: { mediaIndices: [1] } }]);
+    });
+
     it("handles empty set", () => {
       const { result } = renderHook(() => useMediaList(), { wrapper });
 
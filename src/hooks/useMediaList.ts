@@ -164,6 +164,12 @@ export function useMediaList() {
           return;
         }
 
+        if (!expansion.singleVideo) {
+          console.warn("Playlist expansion returned no entries; probing as a single video", {
+            url,
+          });
+        }
+
         // Single video - find current index (state is now committed)
         const currentIdx = findUrlIndex(url);
         if (currentIdx >= 0) {
@@ -265,10 +271,26 @@ export function useMediaList() {
     });
   }, []);
 
+  /**
+   * Drop the backend's cached media info for removed rows (all rows when omitted)
+   */
+  const forgetMediaInfo = useCallback(
+    (indices?: number[]) => {
+      void tauriApi.commands
+        .clearMediaInfoCache(indices)
+        .catch((error) => console.warn("clearMediaInfoCache failed", { indices, error }));
+    },
+    [tauriApi.commands],
+  );
+
   /**
    * Remove an item by id (typically the URL)
    */
   const removeItem = useCallback((id: string) => {
+    const index = Array.from(mediaMapRef.current.values()).findIndex((item) => item.id === id);
+    if (index >= 0) {
+      forgetMediaInfo([index]);
+    }
     setMediaMap((prev) => {
       const next = new Map(prev);
       // Find and delete by id
@@ -281,7 +303,7 @@ export function useMediaList() {
       mediaMapRef.current = next; // Keep ref in sync
       return next;
     });
-  }, []);
+  }, [forgetMediaInfo]);
 
   /**
    * Remove all items
@@ -290,12 +312,14 @@ export function useMediaList() {
     const empty = new Map<string, VideoInfo>();
     mediaMapRef.current = empty; // Keep ref in sync
     setMediaMap(empty);
-  }, []);
+    forgetMediaInfo();
+  }, [forgetMediaInfo]);
 
   /**
    * Remove items at specific indices
    */
   const removeItemsAtIndices = useCallback((indices: Set<number>) => {
+    forgetMediaInfo(Array.from(indices));
     setMediaMap((prev) => {
       const entries = Array.from(prev.entries());
       const next = new Map<string, VideoInfo>();
@@ -307,7 +331,7 @@ export function useMediaList() {
       mediaMapRef.current = next; // Keep ref in sync
       return next;
     });
-  }, []);
+  }, [forgetMediaInfo]);
 
   return {
     mediaList,
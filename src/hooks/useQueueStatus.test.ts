@@ -41,7 +41,10 @@ describe("useQueueStatus", () => {
     mockState.queuedDownloads = [];
     mockState.activeDownloads.add(1);
     act(() => {
-      mockState.emitEvent(TAURI_EVENT.downloadStarted, 1);
+      mockState.emitEvent(TAURI_EVENT.downloadStarted, [
+        1,
+        { mode: "video", resolution: "no-limit", format: "best" },
+      ]);
     });
     await waitFor(() =>
       expect(result.current.queueStats).toEqual({ queued: 0, active: 1, maxConcurrent: 3 }),
@@ -9,7 +9,7 @@ interface PlaylistContextValue {
 
 const PlaylistContext = createContext<PlaylistContextValue | null>(null);
 
-const EMPTY_EXPANSION: PlaylistExpansion = { entries: [] };
+const EMPTY_EXPANSION: PlaylistExpansion = { entries: [], singleVideo: true };
 
 export function PlaylistProvider({ children }: { children: ReactNode }) {
   const tauriApi = useTauriApi();
@@ -25,6 +25,7 @@ export function PlaylistProvider({ children }: { children: ReactNode }) {
           playlistName: expansion.playlistName,
           uploader: expansion.uploader,
           entries: expansion.entries.filter((entry) => Boolean(entry?.url)),
+          singleVideo: expansion.singleVideo,
         };
       } catch (error) {
         console.warn("expandPlaylist failed; falling back to single URL", {
@@ -4,18 +4,18 @@ import { mapMediaInfoEventToUpdate } from "./media-mapping";
 
 describe("mapMediaInfoEventToUpdate", () => {
   it("does NOT set collection metadata for single videos even with uploader", () => {
-    const payload: MediaInfoEvent = [
-      0,
-      "https://example.com/video",
-      "Some Title",
-      "thumb.jpg",
-      "https://example.com/preview",
-      "Some Channel",
-      null,
-      null,
-      null,
-      null,
-    ];
+    const payload: MediaInfoEvent = {
+      mediaIdx: 0,
+      mediaSourceUrl: "https://example.com/video",
+      title: "Some Title",
+      thumbnail: "thumb.jpg",
+      previewUrl: "https://example.com/preview",
+      uploader: "Some Channel",
+      collectionId: null,
+      collectionKind: null,
+      collectionName: null,
+      folderSlug: null,
+    };
 
     const update = mapMediaInfoEventToUpdate(payload);
 
@@ -32,18 +32,18 @@ describe("mapMediaInfoEventToUpdate", () => {
   });
 
   it("sets collection metadata for playlist videos", () => {
-    const payload: MediaInfoEvent = [
-      0,
-      "https://example.com/video",
-      "Some Title",
-      "thumb.jpg",
-      "https://example.com/preview",
-      "Some Channel",
-      "playlist:My Playlist",
-      "playlist",
-      "My Playlist",
-      "My_Playlist",
-    ];
+    const payload: MediaInfoEvent = {
+      mediaIdx: 0,
+      mediaSourceUrl: "https://example.com/video",
+      title: "Some Title",
+      thumbnail: "thumb.jpg",
+      previewUrl: "https://example.com/preview",
+      uploader: "Some Channel",
+      collectionId: "playlist:My Playlist",
+      collectionKind: "playlist",
+      collectionName: "My Playlist",
+      folderSlug: "My_Playlist",
+    };
 
     const update = mapMediaInfoEventToUpdate(payload);
 
@@ -60,18 +60,18 @@ describe("mapMediaInfoEventToUpdate", () => {
   });
 
   it("sets collection metadata for channel videos", () => {
-    const payload: MediaInfoEvent = [
-      0,
-      "https://example.com/video",
-      "Some Title",
-      "thumb.jpg",
-      "https://example.com/preview",
-      "Some Channel",
-      "channel:Some Channel",
-      "channel",
-      "Some Channel",
-      "Some_Channel",
-    ];
+    const payload: MediaInfoEvent = {
+      mediaIdx: 0,
+      mediaSourceUrl: "https://example.com/video",
+      title: "Some Title",
+      thumbnail: "thumb.jpg",
+      previewUrl: "https://example.com/preview",
+      uploader: "Some Channel",
+      collectionId: "channel:Some Channel",
+      collectionKind: "channel",
+      collectionName: "Some Channel",
+      folderSlug: "Some_Channel",
+    };
 
     const update = mapMediaInfoEventToUpdate(payload);
 
@@ -88,18 +88,18 @@ describe("mapMediaInfoEventToUpdate", () => {
   });
 
   it("does not set collection metadata when uploader is missing", () => {
-    const payload: MediaInfoEvent = [
-      0,
-      "https://example.com/video",
-      "Some Title",
-      "thumb.jpg",
-      "https://example.com/preview",
-      null,
-      null,
-      null,
-      null,
-      null,
-    ];
+    const payload: MediaInfoEvent = {
+      mediaIdx: 0,
+      mediaSourceUrl: "https://example.com/video",
+      title: "Some Title",
+      thumbnail: "thumb.jpg",
+      previewUrl: "https://example.com/preview",
+      uploader: null,
+      collectionId: null,
+      collectionKind: null,
+      collectionName: null,
+      folderSlug: null,
+    };
 
     const update = mapMediaInfoEventToUpdate(payload);
 
@@ -2,18 +2,16 @@ import { sanitizeFolderName, type VideoInfo, type CollectionKind } from "@/utils
 import type { MediaInfoEvent } from "@/types";
 
 export function mapMediaInfoEventToUpdate(payload: MediaInfoEvent): Partial<VideoInfo> {
-  const [
-    _mediaIdx,
+  const {
     mediaSourceUrl,
     title,
     thumbnail,
     previewUrl,
-    _uploader,
-    backendCollectionId,
-    backendCollectionKind,
-    backendCollectionName,
-    backendFolderSlug,
-  ] = payload;
+    collectionId: backendCollectionId,
+    collectionKind: backendCollectionKind,
+    collectionName: backendCollectionName,
+    folderSlug: backendFolderSlug,
+  } = payload;
 
   const collectionType: CollectionKind | undefined = backendCollectionKind ?? undefined;
 
@@ -26,7 +26,7 @@ import type {
 export interface MockTauriWindow extends TauriWindow {
   clearWindows(): void;
 }
-import type { DownloadSettings, PlaylistExpansion } from "@/types";
+import type { DownloadSettings, PlaylistExpansion, QueueStatus } from "@/types";
 import { TAURI_EVENT } from "@/types";
 
 /**
@@ -222,17 +222,22 @@ class MockCommands implements TauriCommands {
 
     // Cancel all active downloads
     mockState.activeDownloads.forEach((mediaIdx) => {
-      mockState.emitEvent(TAURI_EVENT.downloadCancelled, mediaIdx);
+      mockState.emitEvent(TAURI_EVENT.downloadCancelled, [mediaIdx, "all"]);
     });
     mockState.activeDownloads.clear();
   }
 
-  async setMaxConcurrentDownloads(maxConcurrent: number): Promise<void> {
+  async setMaxConcurrentDownloads(maxConcurrent: number): Promise<QueueStatus> {
     mockState.commandCalls.push({
       command: "set_max_concurrent_downloads",
       args: { maxConcurrent },
     });
     mockState.maxConcurrentDownloads = maxConcurrent;
+    return {
+      queued: mockState.queuedDownloads.length,
+      active: mockState.activeDownloads.size,
+      max_concurrent: maxConcurrent,
+    };
   }
 
   async getQueueStatus(): Promise<[number, number, number]> {
@@ -246,6 +251,14 @@ class MockCommands implements TauriCommands {
     const maxConcurrent = mockState.maxConcurrentDownloads;
     return [queued, active, maxConcurrent];
   }
+  async clearMediaInfoCache(mediaIndices?: number[]): Promise<number> {
+    mockState.commandCalls.push({
+      command: "clear_media_info_cache",
+      args: { mediaIndices },
+    });
+    return mediaIndices?.length ?? 0;
+  }
+
 
   async quit(): Promise<void> {
     mockState.commandCalls.push({
@@ -293,10 +306,10 @@ class MockCommands implements TauriCommands {
     });
   }
 
-  async expandPlaylist(mediaSourceUrl: string): Promise<PlaylistExpansion> {
+  async expandPlaylist(mediaSourceUrl: string, dateafter?: string): Promise<PlaylistExpansion> {
     mockState.commandCalls.push({
       command: "expand_playlist",
-      args: { mediaSourceUrl },
+      args: { mediaSourceUrl, dateafter },
     });
 
     if (mockState.playlistExpansion) {
@@ -304,7 +317,7 @@ class MockCommands implements TauriCommands {
     }
 
     // Default mock response: return empty to signal non-playlist
-    return { entries: [] };
+    return { entries: [], singleVideo: true };
   }
 }
 
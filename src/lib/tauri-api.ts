@@ -18,7 +18,7 @@ import {
 import { open as openDialog } from "@tauri-apps/plugin-dialog";
 import { openUrl } from "@tauri-apps/plugin-opener";
 import { WebviewWindow } from "@tauri-apps/api/webviewWindow";
-import type { DownloadSettings, PlaylistExpansion } from "@/types";
+import type { DownloadSettings, PlaylistExpansion, QueueStatus } from "@/types";
 
 /**
  * Tauri Command API
@@ -30,9 +30,10 @@ export interface TauriCommands {
   getMediaInfo(mediaIdx: number, mediaSourceUrl: string): Promise<void>;
 
   /**
-   * Expand playlist/profile URLs into concrete media URLs with metadata
+   * Expand playlist/profile URLs into concrete media URLs with metadata.
+   * With `dateafter` (YYYYMMDD) only items uploaded on or after that day are listed.
    */
-  expandPlaylist(mediaSourceUrl: string): Promise<PlaylistExpansion>;
+  expandPlaylist(mediaSourceUrl: string, dateafter?: string): Promise<PlaylistExpansion>;
 
   /**
    * Download media from a URL
@@ -51,15 +52,20 @@ export interface TauriCommands {
   cancelAllDownloads(): Promise<void>;
 
   /**
-   * Set maximum number of concurrent downloads
+   * Set maximum number of concurrent downloads, returning the updated queue status
    */
-  setMaxConcurrentDownloads(maxConcurrent: number): Promise<void>;
+  setMaxConcurrentDownloads(maxConcurrent: number): Promise<QueueStatus>;
 
   /**
    * Get current queue status (queued, active, max_concurrent)
    */
   getQueueStatus(): Promise<[number, number, number]>;
 
+  /**
+   * Forget cached media info for removed rows (all rows when omitted) so a resync won't replay them
+   */
+  clearMediaInfoCache(mediaIndices?: number[]): Promise<number>;
+
   /**
    * Quit the application
    */
@@ -218,8 +224,8 @@ class RealTauriApi implements TauriApi {
       await tauriInvoke("get_media_info", { mediaIdx, mediaSourceUrl });
     },
 
-    async expandPlaylist(mediaSourceUrl: string): Promise<PlaylistExpansion> {
-      return await tauriInvoke<PlaylistExpansion>("expand_playlist", { mediaSourceUrl });
+    async expandPlaylist(mediaSourceUrl: string, dateafter?: string): Promise<PlaylistExpansion> {
+      return await tauriInvoke<PlaylistExpansion>("expand_playlist", { mediaSourceUrl, dateafter });
     },
 
     async downloadMedia(
@@ -242,8 +248,8 @@ class RealTauriApi implements TauriApi {
       await tauriInvoke("cancel_all_downloads");
     },
 
-    async setMaxConcurrentDownloads(maxConcurrent: number): Promise<void> {
-      await tauriInvoke("set_max_concurrent_downloads", { maxConcurrent });
+    async setMaxConcurrentDownloads(maxConcurrent: number): Promise<QueueStatus> {
+      return tauriInvoke<QueueStatus>("set_max_concurrent_downloads", { maxConcurrent });
     },
 
     async getQueueStatus(): Promise<[number, number, number]> {
@@ -251,6 +257,10 @@ class RealTauriApi implements TauriApi {
       return result;
     },
 
+    async clearMediaInfoCache(mediaIndices?: number[]): Promise<number> {
+      return await tauriInvoke<number>("clear_media_info_cache", { mediaIndices });
+    },
+
     async quit(): Promise<void> {
       await tauriInvoke("quit");
     },
@@ -3,41 +3,116 @@
 // Tauri event payloads
 // [mediaIdx, progress]
 export type MediaProgressEvent = [number, number];
-// [
-//   mediaIdx,
-//   mediaSourceUrl,
-//   title,
-//   thumbnail,
-//   previewUrl,
-//   uploader,
-//   collectionId,
-//   collectionKind,
-//   collectionName,
-//   folderSlug,
-// ]
-export type MediaInfoEvent = [
-  number,
-  string,
-  string,
-  string,
-  string,
-  string | null,
-  string | null,
-  ("playlist" | "channel" | "single") | null,
-  string | null,
-  string | null,
-];
+// Metadata that fills the requesting row
+export interface MediaInfoEvent {
+  mediaIdx: number;
+  mediaSourceUrl: string;
+  title: string;
+  thumbnail: string;
+  previewUrl: string;
+  uploader: string | null;
+  collectionId: string | null;
+  collectionKind: ("playlist" | "channel" | "single") | null;
+  collectionName: string | null;
+  folderSlug: string | null;
+}
+// Emitted alongside update-media-info when one URL yields several entries (e.g. a thread).
+// items[0] mirrors the requesting row; entries with subIndex >= 1 need their own rows.
+export interface MediaInfoItem {
+  subIndex: number;
+  // The entry's webpage_url, or the source URL when yt-dlp reported none
+  url: string;
+  title: string;
+  thumbnail: string;
+  previewUrl: string;
+  uploader: string | null;
+}
+export interface MediaInfoMultiEvent {
+  mediaIdx: number;
+  mediaSourceUrl: string;
+  items: MediaInfoItem[];
+}
+// Settings a download runs with, as reported by download-started
+export interface SettingsSummary {
+  mode: "video" | "audio";
+  resolution: string | null; // maxResolution in video mode, null for audio
+  format: string; // videoFormat or audioFormat, depending on mode
+}
+// [mediaIdx, settingsSummary, outputDir]
+export type DownloadStartedEvent = [number, SettingsSummary, string];
+// [mediaIdx, {path, reason}]: the subfolder could not be created, so the file goes to the output location
+export type SubfolderFallbackEvent = [number, { path: string; reason: string }];
+// Why a download was cancelled ("diskLow" = another download filled the disk; "tooOld" = cancel_stale_downloads;
+// "paused" = requeued after the limit was lowered, download-queued follows)
+export type CancelReason = "user" | "all" | "diskLow" | "tooOld" | "paused";
+// [mediaIdx, reason]
+export type DownloadCancelledEvent = [number, CancelReason];
+// Queue counts, as returned by set_max_concurrent_downloads and sent with queue-changed
+export interface QueueStatus {
+  queued: number;
+  active: number;
+  max_concurrent: number;
+}
+// A download refused because the queue already holds maxQueued items; raise it with set_max_queued
+export interface QueueFullEvent {
+  mediaIdx: number;
+  queued: number;
+  maxQueued: number;
+}
+// [host, cooldownSecs]
+export type HostCooldownEvent = [string, number];
+// Outcome counts since the queue last drained
+export interface BatchSummaryEvent {
+  completed: number;
+  failed: number;
+  cancelled: number;
+  skipped: number;
+}
+// ffmpeg phase that runs after the download reaches 100%
+export type PostprocessPhase = "merging" | "remuxing" | "extractingAudio";
+// [mediaIdx, phase]
+export type DownloadPostprocessEvent = [number, PostprocessPhase];
+// Transfer statistics sent once a download completes
+export interface DownloadStatsEvent {
+  mediaIdx: number;
+  avgSpeed: number; // bytes/sec
+  peakSpeed: number; // bytes/sec
+  durationSecs: number;
+  totalBytes: number; // estimated from speed samples
+}
+// One get_progress_history point, for charting speed over time
+export interface ProgressSample {
+  timestampMs: number; // Unix epoch milliseconds
+  percent: number;
+  speed: number | null; // bytes/sec
+}
+// [mediaSourceUrl, entriesSeen] while expand_playlist runs
+export type PlaylistExpandProgressEvent = [string, number];
 // [mediaIdx, stderrLine]
 export type YtDlpStderrEvent = [number, string];
 
 // Tauri event name constants
 export const TAURI_EVENT = {
   updateMediaInfo: "update-media-info",
+  mediaInfoMulti: "media-info-multi",
   downloadProgress: "download-progress",
   downloadComplete: "download-complete",
   downloadError: "download-error",
   downloadCancelled: "download-cancelled",
   downloadQueued: "download-queued",
+  downloadSkipped: "download-skipped",
+  queueChanged: "queue-changed",
+  hostCooldown: "host-cooldown",
+  diskLow: "disk-low",
+  subfolderFallback: "subfolder-fallback",
+  ffmpegMissing: "ffmpeg-missing",
+  meteredChanged: "metered-changed",
+  queueFull: "queue-full",
+  batchSummary: "batch-summary",
+  downloadPostprocess: "download-postprocess",
+  downloadVerified: "download-verified",
+  downloadStats: "download-stats",
+  playlistExpandProgress: "playlist-expand-progress",
   downloadStarted: "download-started",
   ytDlpStderr: "yt-dlp-stderr",
   remoteAddUrl: "remote-add-url",
@@ -46,6 +121,7 @@ export const TAURI_EVENT = {
   remoteClearList: "remote-clear-list",
   remoteSetDownloadDir: "remote-set-download-dir",
   startupError: "startup-error",
+  logLine: "log-line",
 } as const;
 
 export type TauriEventName = (typeof TAURI_EVENT)[keyof typeof TAURI_EVENT];
@@ -56,6 +132,7 @@ export type DownloadEventName =
   | (typeof TAURI_EVENT)["downloadError"]
   | (typeof TAURI_EVENT)["downloadCancelled"]
   | (typeof TAURI_EVENT)["downloadQueued"]
+  | (typeof TAURI_EVENT)["downloadSkipped"]
   | (typeof TAURI_EVENT)["downloadStarted"];
 
 export type RemoteEventName =
@@ -66,14 +143,32 @@ export type RemoteEventName =
   | (typeof TAURI_EVENT)["remoteSetDownloadDir"];
 
 // Mapping from event name to its payload type for strongly-typed listeners
+// Why download-skipped fired: URL already done this session (skipIfCompleted), already queued or
+// downloading, over maxFileSize (yt-dlp declined it), or uploaded before the dateafter cutoff
+export type DownloadSkipReason = "already_completed" | "duplicate" | "too_large" | "date_filtered";
+
 export interface TauriEventPayloadMap {
   [TAURI_EVENT.updateMediaInfo]: MediaInfoEvent;
+  [TAURI_EVENT.mediaInfoMulti]: MediaInfoMultiEvent;
   [TAURI_EVENT.downloadProgress]: MediaProgressEvent;
   [TAURI_EVENT.downloadComplete]: number;
   [TAURI_EVENT.downloadError]: number;
-  [TAURI_EVENT.downloadCancelled]: number;
+  [TAURI_EVENT.downloadCancelled]: DownloadCancelledEvent;
   [TAURI_EVENT.downloadQueued]: number;
-  [TAURI_EVENT.downloadStarted]: number;
+  [TAURI_EVENT.downloadSkipped]: [number, DownloadSkipReason];
+  [TAURI_EVENT.queueChanged]: QueueStatus;
+  [TAURI_EVENT.hostCooldown]: HostCooldownEvent;
+  [TAURI_EVENT.diskLow]: string; // Output directory that ran out of space; the queue is now paused
+  [TAURI_EVENT.subfolderFallback]: SubfolderFallbackEvent;
+  [TAURI_EVENT.ffmpegMissing]: undefined; // ffmpeg not on PATH; downloads skip post-processing
+  [TAURI_EVENT.meteredChanged]: boolean; // true while new downloads are held for a metered connection
+  [TAURI_EVENT.queueFull]: QueueFullEvent;
+  [TAURI_EVENT.batchSummary]: BatchSummaryEvent;
+  [TAURI_EVENT.downloadPostprocess]: DownloadPostprocessEvent;
+  [TAURI_EVENT.downloadVerified]: number; // mediaIdx; a failed check arrives as a yt-dlp-stderr warning
+  [TAURI_EVENT.downloadStats]: DownloadStatsEvent;
+  [TAURI_EVENT.playlistExpandProgress]: PlaylistExpandProgressEvent;
+  [TAURI_EVENT.downloadStarted]: DownloadStartedEvent;
   [TAURI_EVENT.ytDlpStderr]: YtDlpStderrEvent;
   [TAURI_EVENT.remoteAddUrl]: string;
   [TAURI_EVENT.remoteStartDownloads]: undefined;
@@ -81,6 +176,7 @@ export interface TauriEventPayloadMap {
   [TAURI_EVENT.remoteClearList]: undefined;
   [TAURI_EVENT.remoteSetDownloadDir]: string;
   [TAURI_EVENT.startupError]: string;
+  [TAURI_EVENT.logLine]: string;
 }
 
 
@@ -96,6 +192,29 @@ export interface DownloadSettings {
   maxFileSize: string; // "unlimited" | "50M" | "100M" | "500M" | "1G" | "5G"
   appendUniqueId: boolean; // Append unique ID to filenames
   uniqueIdType: "native" | "hash"; // "native" = yt-dlp's video ID, "hash" = FNV-1a hash
+  extractorArgs?: string[]; // Extra "key:subkey=value" pairs passed via --extractor-args
+  metadataOverrides?: Record<string, string>; // Keys: "title" | "artist" | "album" | "date"
+  maxFilenameLength?: number; // 10-255, passed to --trim-filenames
+  fallbackToBest?: boolean; // Retry once with "-f best" if the video format is unavailable
+  downloaderBackend?: "native" | "aria2c"; // "aria2c" requires aria2c on PATH
+  preferCodec?: "h264" | "vp9" | "av1" | "any"; // Preferred video codec, falls back to any
+  tempDir?: string; // Existing writable directory for intermediate files
+  noPart?: boolean; // Write directly to the final file (--no-part)
+  onDuplicate?: "skip" | "overwrite" | "number"; // "number" keeps both copies via a unique ID
+  extraArgs?: string[]; // Extra yt-dlp flags; only allowlisted flags are accepted
+  avoidPostprocessing?: boolean; // Download a single pre-muxed file so ffmpeg is never needed
+  cookiesSource?: "brave" | "chrome" | "chromium" | "edge" | "firefox" | "opera" | "safari" | "vivaldi" | "whale";
+  cookiesFile?: string; // Path to a Netscape cookies.txt; mutually exclusive with cookiesSource
+  verifyAfterDownload?: boolean; // Re-check the URL and file size after completion
+  prefixDate?: boolean; // Prefix filenames with the upload date (YYYY-MM-DD - title)
+  fileMode?: string; // Unix permissions for the finished file, 3-4 octal digits (e.g. "644"); ignored on Windows
+  dateafter?: string; // YYYYMMDD; only items uploaded on/after it; older ones are skipped as "date_filtered"
+  parseTitleMetadata?: boolean; // Audio only: embed artist/title split from "Artist - Title" video titles
+  concurrentFragments?: number; // 1-64 parallel fragments; lowered to this download's share of the fragment budget
+  writeDescription?: boolean; // Save the description as "<title> [<id>].description" beside the file
+  flattenOutput?: boolean; // Ignore subfolder and write every download directly into outputLocation
+  keepOriginal?: boolean; // Also keep the pre-merge/remux source files (--keep-video); about twice the disk space
+  downloadArchive?: string; // --download-archive file recording finished IDs; relative to the output folder
 }
 
 export interface DownloadMediaCommand {
@@ -104,16 +223,70 @@ export interface DownloadMediaCommand {
   outputLocation: string;
   subfolder?: string;
   settings: DownloadSettings;
+  skipIfCompleted?: boolean; // Emit download-skipped instead of queuing URLs already done this session
+  title?: string; // Used by sort_queue("title")
+  priority?: number; // Higher runs first after sort_queue("priority"); defaults to 0
 }
 
+export type ThumbnailQuality = "best" | "medium" | "low";
+
 export interface GetMediaInfoCommand {
   mediaIdx: number;
   mediaSourceUrl: string;
+  cookiesSource?: string;
+  cookiesFile?: string;
+  thumbnailQuality?: ThumbnailQuality; // Size bucket from yt-dlp thumbnails (default "best")
+}
+
+// One row of get_media_info_batch; results arrive as update-media-info
+export interface MediaInfoRequest {
+  mediaIdx: number;
+  mediaSourceUrl: string;
+}
+
+export interface GetMediaInfoBatchCommand {
+  batchId: string; // Pass the same id to cancel_media_info_batch
+  items: MediaInfoRequest[];
+  extractorArgs?: string[]; // Applied to every row, as in get_media_info
+  cookiesSource?: string;
+  cookiesFile?: string;
+  thumbnailQuality?: ThumbnailQuality;
+}
+
+// Structured command error; branch on `code` (e.g. "E_VAL_INVALID_URL")
+export interface FrontendError {
+  code: string;
+  message: string;
+  retryable: boolean;
+}
+
+// One row of validate_batch
+export interface BatchItem {
+  mediaIdx: number;
+  url: string;
+}
+
+// validate_batch result: rows that can be queued, and [mediaIdx, error] for the rest (input order)
+export interface BatchValidation {
+  valid: number[];
+  invalid: [number, FrontendError][];
+}
+
+// check_remux_compat: false means the UI should warn before downloading
+export interface CheckRemuxCompatCommand {
+  videoFormat: "mp4" | "mkv" | "webm" | "best";
+  sourceCodec: string; // yt-dlp vcodec/acodec, e.g. "avc1.64001F" or "mp4a.40.2"
+}
+
+// set_metered: call with the connection's metered state (e.g. from navigator.connection)
+export interface SetMeteredCommand {
+  metered: boolean;
 }
 
 export interface PlaylistEntry {
   url: string;
   title?: string;
+  id?: string; // Extractor video ID (matches download archive entries)
 }
 
 export interface PlaylistExpansion {
@@ -124,10 +297,54 @@ export interface PlaylistExpansion {
   collectionKind?: "playlist" | "channel" | "single";
   collectionName?: string;
   folderSlug?: string;
+  totalCount?: number; // yt-dlp playlist_count; may exceed entries.length (capped at 500)
+  singleVideo?: boolean; // No playlist at this URL; fall back to getMediaInfo
 }
 
 export interface ExpandPlaylistCommand {
   mediaSourceUrl: string;
 }
 
+export interface ResumePlaylistCommand {
+  mediaSourceUrl: string;
+  outputLocation: string;
+  subfolder?: string;
+  archiveFile: string; // Relative to the collection folder unless absolute
+  settings: DownloadSettings; // downloadArchive is set to archiveFile so resumed items are recorded too
+  firstMediaIdx: number; // Entry i of the result is enqueued as firstMediaIdx + i
+}
+
+// Result of fetch_channel_art
+export interface ChannelArt {
+  avatar: string | null;
+  banner: string | null;
+}
+
+// Result of check_ytdlp_update
+export interface UpdateStatus {
+  current: string;
+  latest: string | null; // null when the release lookup failed (e.g. offline)
+  updateAvailable: boolean;
+}
+
+// Result of get_paths
+export interface AppPaths {
+  configDir: string;
+  logDir: string;
+  ytDlpLog: string;
+  errorLog: string;
+}
+
+// Result of clear_thumbnail_cache
+export interface ClearResult {
+  removedFiles: number;
+  freedBytes: number;
+}
+
+// Result of check_host_reachable
+export interface ReachResult {
+  reachable: boolean;
+  latencyMs: number | null; // null when the host did not answer
+}
+
 export type QuitCommand = undefined;